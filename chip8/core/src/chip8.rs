@@ -0,0 +1,931 @@
+use rand::Rng;
+use std::ops::Range;
+
+mod assembler;
+mod cpu;
+mod debugger;
+mod disassembler;
+mod display;
+mod error;
+mod gdb;
+mod host;
+mod keyboard;
+mod keymap;
+mod memory;
+mod mode;
+mod quirks;
+mod rewind;
+mod speaker;
+mod state;
+mod trace;
+mod wav;
+
+pub use assembler::{assemble, AssembleError, Assembly};
+pub use debugger::{Debugger, Stop};
+pub use disassembler::{disassemble, disassemble_at, Syntax};
+pub use display::Display;
+pub use error::{Chip8Error, StepOutcome};
+pub use gdb::GdbStub;
+pub use host::{InputSource, NullVideo, VideoOutput};
+pub use keymap::{InvalidKey, Keymap};
+pub use mode::Mode;
+pub use quirks::{IndexIncrement, Quirks};
+pub use rewind::Rewind;
+pub use speaker::{NullSink, SampleSink, SAMPLE_RATE};
+pub use trace::Tracer;
+pub use wav::WavSink;
+
+pub struct Chip8 {
+    pub cpu: cpu::Cpu,
+    pub memory: memory::Memory,
+    pub display: display::Display,
+    pub keyboard: keyboard::Keyboard,
+    pub speaker: speaker::Speaker,
+    pub mode: Mode,
+    pub quirks: Quirks,
+
+    // Set by DXYN under the display wait quirk, cleared on the next timer tick
+    pub vblank_wait: bool,
+
+    // Set by the SUPER-CHIP 00FD exit instruction
+    pub exited: bool,
+
+    // SUPER-CHIP and XO-CHIP RPL user flags, kept by the host across runs of the program
+    pub rpl_flags: [u8; 16],
+
+    // Identifies the loaded program, save states taken with another program are rejected
+    pub rom_hash: u64,
+
+    // Memory written by the last instruction, for watchpoints
+    pub last_write: Option<Range<usize>>,
+
+    // Logs every instruction run when set
+    pub tracer: Option<trace::Tracer>,
+}
+
+impl Chip8 {
+    pub fn new(mode: Mode, quirks: Quirks) -> Chip8 {
+        return Chip8 {
+            cpu: cpu::Cpu::new(),
+            memory: memory::Memory::new(mode.memory_size()),
+            display: display::Display::new(),
+            keyboard: keyboard::Keyboard::new(),
+            speaker: speaker::Speaker::new(),
+            mode,
+            quirks,
+            vblank_wait: false,
+            exited: false,
+            rpl_flags: [0x0; 16],
+            rom_hash: state::rom_hash(&[]),
+            last_write: None,
+            tracer: None,
+        };
+    }
+
+    pub fn load_program(&mut self, program: &[u8]) -> Result<(), Chip8Error> {
+        /*  Loads the program into memory and remembers its hash for save states.
+            A program too large for the mode's memory leaves the machine untouched
+        */
+        let capacity = self.memory.primary_memory.len() - 0x200;
+        if program.len() > capacity {
+            return Err(Chip8Error::ProgramTooLarge {
+                len: program.len(),
+                capacity,
+            });
+        }
+        self.memory.load_program(program);
+        self.rom_hash = state::rom_hash(program);
+        return Ok(());
+    }
+
+    fn advance_i(&mut self, x: usize) {
+        /*  Moves i on after FX55/FX65 by however much the quirks say
+         */
+        self.cpu.i = match self.quirks.load_store_increment {
+            IndexIncrement::None => self.cpu.i,
+            IndexIncrement::ByX => self.cpu.i.wrapping_add(x as u16),
+            IndexIncrement::ByXPlusOne => self.cpu.i.wrapping_add(x as u16 + 1),
+        };
+    }
+
+    fn skip(&mut self) {
+        /*  Skips the next instruction, which on XO-CHIP may be the 4-byte F000 NNNN
+         */
+        let pc = self.cpu.program_counter as usize;
+        let memory = &self.memory.primary_memory;
+        let next =
+            (*memory.get(pc).unwrap_or(&0) as u16) << 8 | *memory.get(pc + 1).unwrap_or(&0) as u16;
+        if self.mode.xochip() && next == 0xF000 {
+            self.cpu.program_counter = self.cpu.program_counter.wrapping_add(4);
+        } else {
+            self.cpu.program_counter = self.cpu.program_counter.wrapping_add(2);
+        }
+    }
+
+    fn memory_range(
+        &self,
+        address: u16,
+        start: usize,
+        len: usize,
+    ) -> Result<Range<usize>, Chip8Error> {
+        /*  The len bytes of memory from start, if they are all addressable
+            arg: the address of the instruction making the access
+        */
+        let size = self.memory.primary_memory.len();
+        if start + len > size {
+            return Err(Chip8Error::MemoryOutOfBounds {
+                address,
+                target: start.max(size),
+            });
+        }
+        return Ok(start..start + len);
+    }
+
+    pub fn execute(&mut self, instruction: u16) -> Result<StepOutcome, Chip8Error> {
+        /*  executes an instruction on the hardware
+            arg: the hex instruction
+            On failure the program counter is left on the faulting instruction
+        */
+        let address = self.cpu.program_counter;
+        let outcome = self.dispatch(instruction, address);
+        if outcome.is_err() {
+            self.cpu.program_counter = address;
+        }
+        return outcome;
+    }
+
+    fn dispatch(&mut self, instruction: u16, address: u16) -> Result<StepOutcome, Chip8Error> {
+        self.last_write = None;
+        self.cpu.program_counter = self.cpu.program_counter.wrapping_add(2);
+        let x: usize = ((instruction & 0x0F00) >> (4 * 2)) as usize;
+        let y: usize = ((instruction & 0x00F0) >> 4) as usize;
+        match instruction & 0xF000 {
+            0x0000 => {
+                /*  Case 0NNN
+                 */
+                match instruction {
+                    0x00E0 => {
+                        /*  Clear the display.
+                         */
+                        self.display.clear();
+                    }
+                    0x00EE => {
+                        /*  Return from a subroutine.
+                            The interpreter sets the program counter to the address at the top of the stack, then subtracts 1 from the stack pointer.
+                        */
+                        if self.cpu.stack_pointer as usize >= self.memory.stack.len() {
+                            return Err(Chip8Error::StackUnderflow { address });
+                        }
+                        self.cpu.program_counter =
+                            self.memory.stack[self.cpu.stack_pointer as usize];
+                        self.cpu.stack_pointer = self.cpu.stack_pointer.wrapping_sub(1);
+                    }
+                    0x00C0..=0x00CF if self.mode.superchip() => {
+                        /*  Scroll the display down by n pixels.
+                         */
+                        self.display.scroll_down((instruction & 0x000F) as usize);
+                    }
+                    0x00D0..=0x00DF if self.mode.xochip() => {
+                        /*  Scroll the display up by n pixels.
+                         */
+                        self.display.scroll_up((instruction & 0x000F) as usize);
+                    }
+                    0x00FB if self.mode.superchip() => {
+                        /*  Scroll the display right by 4 pixels.
+                         */
+                        self.display.scroll_right(4);
+                    }
+                    0x00FC if self.mode.superchip() => {
+                        /*  Scroll the display left by 4 pixels.
+                         */
+                        self.display.scroll_left(4);
+                    }
+                    0x00FD if self.mode.superchip() => {
+                        /*  Exit the interpreter.
+                            The program counter is left on the exit instruction so that nothing runs past it.
+                        */
+                        self.cpu.program_counter = address;
+                        self.exited = true;
+                        return Ok(StepOutcome::Exited);
+                    }
+                    0x00FE if self.mode.superchip() => {
+                        /*  Switch to the 64x32 low resolution mode.
+                         */
+                        self.display.set_hires(false);
+                    }
+                    0x00FF if self.mode.superchip() => {
+                        /*  Switch to the 128x64 high resolution mode.
+                         */
+                        self.display.set_hires(true);
+                    }
+                    _ => {
+                        /*  Jump to a machine code routine at nnn.
+                            This instruction is only used on the old computers on which Chip-8 was originally implemented. It is ignored by modern interpreters.
+                        */
+                    }
+                }
+            }
+            0x1000 => {
+                /*  Jump to location nnn.
+                    The interpreter sets the program counter to nnn.
+                */
+                self.cpu.program_counter = instruction & 0x0FFF;
+            }
+            0x2000 => {
+                /*  Call subroutine at nnn.
+                    The interpreter increments the stack pointer, then puts the current PC on the top of the stack. The PC is then set to nnn.
+                */
+                if self.cpu.stack_pointer.wrapping_add(1) as usize >= self.memory.stack.len() {
+                    return Err(Chip8Error::StackOverflow { address });
+                }
+                self.cpu.stack_pointer = self.cpu.stack_pointer.wrapping_add(1);
+                self.memory.stack[self.cpu.stack_pointer as usize] = self.cpu.program_counter;
+                self.cpu.program_counter = instruction & 0x0FFF;
+            }
+            0x3000 => {
+                /*  Skip next instruction if Vx = kk.
+                    The interpreter compares register Vx to kk, and if they are equal, increments the program counter by 2.
+                */
+                if self.cpu.registers[x] == (instruction & 0x00FF) as u8 {
+                    self.skip();
+                }
+            }
+            0x4000 => {
+                /*  Skip next instruction if Vx != kk.
+                    The interpreter compares register Vx to kk, and if they are not equal, increments the program counter by 2.
+                */
+                if self.cpu.registers[x] != (instruction & 0x00FF) as u8 {
+                    self.skip();
+                }
+            }
+            0x5000 => {
+                /*  Case 5xyN
+                 */
+                match instruction & 0x000F {
+                    0x0000 => {
+                        /*  Skip next instruction if Vx = Vy.
+                            The interpreter compares register Vx to register Vy, and if they are equal, increments the program counter by 2.
+                        */
+                        if self.cpu.registers[x] == self.cpu.registers[y] {
+                            self.skip();
+                        }
+                    }
+                    0x0002 if self.mode.xochip() => {
+                        /*  Store registers Vx through Vy in memory starting at location i.
+                            The registers are stored in reverse order when x > y. i is not changed.
+                        */
+                        let range =
+                            self.memory_range(address, self.cpu.i as usize, x.abs_diff(y) + 1)?;
+                        self.last_write = Some(range.clone());
+                        for (offset, register) in register_range(x, y).enumerate() {
+                            self.memory.primary_memory[range.start + offset] =
+                                self.cpu.registers[register];
+                        }
+                    }
+                    0x0003 if self.mode.xochip() => {
+                        /*  Read registers Vx through Vy from memory starting at location i.
+                            The registers are read in reverse order when x > y. i is not changed.
+                        */
+                        let range =
+                            self.memory_range(address, self.cpu.i as usize, x.abs_diff(y) + 1)?;
+                        for (offset, register) in register_range(x, y).enumerate() {
+                            self.cpu.registers[register] =
+                                self.memory.primary_memory[range.start + offset];
+                        }
+                    }
+                    _ => {
+                        return Err(Chip8Error::InvalidOpcode {
+                            address,
+                            opcode: instruction,
+                        })
+                    }
+                }
+            }
+            0x6000 => {
+                /*  Set Vx = kk.
+                    The interpreter puts the value kk into register Vx.
+                */
+                self.cpu.registers[x] = (instruction & 0x00FF) as u8;
+            }
+            0x7000 => {
+                /*  Set Vx = Vx + kk.
+                    Adds the value kk to the value of register Vx, then stores the result in Vx.
+                */
+                self.cpu.registers[x] =
+                    self.cpu.registers[x].wrapping_add((instruction & 0x00FF) as u8);
+            }
+            0x8000 => {
+                /*  Case 8xyN
+                 */
+                match instruction & 0x000F {
+                    0x0000 => {
+                        /*  Set Vx = Vy.
+                            Stores the value of register Vy in register Vx.
+                        */
+                        self.cpu.registers[x] = self.cpu.registers[y];
+                    }
+                    0x0001 => {
+                        /*  Set Vx = Vx OR Vy.
+                            Performs a bitwise OR on the values of Vx and Vy, then stores the result in Vx. A bitwise OR compares the corrseponding bits from two values, and if either bit is 1, then the same bit in the result is also 1. Otherwise, it is 0.
+                        */
+                        self.cpu.registers[x] |= self.cpu.registers[y];
+                        if self.quirks.vf_reset {
+                            self.cpu.registers[0xF] = 0;
+                        }
+                    }
+                    0x0002 => {
+                        /*  Set Vx = Vx AND Vy.
+                            Performs a bitwise AND on the values of Vx and Vy, then stores the result in Vx. A bitwise AND compares the corrseponding bits from two values, and if both bits are 1, then the same bit in the result is also 1. Otherwise, it is 0.
+                        */
+                        self.cpu.registers[x] &= self.cpu.registers[y];
+                        if self.quirks.vf_reset {
+                            self.cpu.registers[0xF] = 0;
+                        }
+                    }
+                    0x0003 => {
+                        /*  Set Vx = Vx XOR Vy.
+                            Performs a bitwise exclusive OR on the values of Vx and Vy, then stores the result in Vx. An exclusive OR compares the corrseponding bits from two values, and if the bits are not both the same, then the corresponding bit in the result is set to 1. Otherwise, it is 0.
+                        */
+                        self.cpu.registers[x] ^= self.cpu.registers[y];
+                        if self.quirks.vf_reset {
+                            self.cpu.registers[0xF] = 0;
+                        }
+                    }
+                    0x0004 => {
+                        /*  Set Vx = Vx + Vy, set VF = carry.
+                            The values of Vx and Vy are added together. If the result is greater than 8 bits (i.e., > 255,) VF is set to 1, otherwise 0. Only the lowest 8 bits of the result are kept, and stored in Vx.
+                        */
+                        let sum = self.cpu.registers[x] as u16 + self.cpu.registers[y] as u16;
+                        if sum > 0xFF {
+                            self.cpu.registers[0xF] = 1;
+                        } else {
+                            self.cpu.registers[0xF] = 0;
+                        }
+                        self.cpu.registers[x] = sum as u8;
+                    }
+                    0x0005 => {
+                        /*  Set Vx = Vx - Vy, set VF = NOT borrow.
+                            If Vx > Vy, then VF is set to 1, otherwise 0. Then Vy is subtracted from Vx, and the results stored in Vx.
+                        */
+                        let diff = self.cpu.registers[x] as i16 - self.cpu.registers[y] as i16;
+                        if diff > 0x0 {
+                            self.cpu.registers[0xF] = 1;
+                        } else {
+                            self.cpu.registers[0xF] = 0;
+                        }
+                        self.cpu.registers[x] = diff as u8;
+                    }
+                    0x0006 => {
+                        /*  Set Vx = Vx SHR 1.
+                            If the least-significant bit of Vx is 1, then VF is set to 1, otherwise 0. Then Vx is divided by 2.
+                            Under the shift quirk Vy is shifted and the result stored in Vx.
+                        */
+                        let value = if self.quirks.shift_uses_vy {
+                            self.cpu.registers[y]
+                        } else {
+                            self.cpu.registers[x]
+                        };
+                        self.cpu.registers[x] = value >> 1;
+                        self.cpu.registers[0xF] = value & 0x1;
+                    }
+                    0x0007 => {
+                        /*  Set Vx = Vy - Vx, set VF = NOT borrow.
+                            If Vy > Vx, then VF is set to 1, otherwise 0. Then Vx is subtracted from Vy, and the results stored in Vx..
+                        */
+
+                        let diff = self.cpu.registers[y] as i16 - self.cpu.registers[x] as i16;
+                        if diff > 0x0 {
+                            self.cpu.registers[0xF] = 1;
+                        } else {
+                            self.cpu.registers[0xF] = 0;
+                        }
+                        self.cpu.registers[x] = diff as u8;
+                    }
+                    0x000E => {
+                        /*  Set Vx = Vx SHL 1.
+                            If the most-significant bit of Vx is 1, then VF is set to 1, otherwise to 0. Then Vx is multiplied by 2.
+                            Under the shift quirk Vy is shifted and the result stored in Vx.
+                        */
+                        let value = if self.quirks.shift_uses_vy {
+                            self.cpu.registers[y]
+                        } else {
+                            self.cpu.registers[x]
+                        };
+                        self.cpu.registers[x] = value << 1;
+                        self.cpu.registers[0xF] = (value & 0x80) >> 7;
+                    }
+                    _ => {
+                        return Err(Chip8Error::InvalidOpcode {
+                            address,
+                            opcode: instruction,
+                        })
+                    }
+                }
+            }
+            0x9000 => {
+                /*  Skip next instruction if Vx != Vy.
+                    The values of Vx and Vy are compared, and if they are not equal, the program counter is increased by 2.
+                */
+                if self.cpu.registers[x] != self.cpu.registers[y] {
+                    self.skip();
+                }
+            }
+            0xA000 => {
+                /*  Set i = nnn.
+                    The value of register i is set to nnn.
+                */
+                self.cpu.i = instruction & 0x0FFF;
+            }
+            0xB000 => {
+                /*  Jump to location nnn + V0.
+                    The program counter is set to nnn plus the value of V0.
+                    Under the jump quirk the offset is taken from Vx instead, x being the top nibble of nnn.
+                */
+                let offset = if self.quirks.jump_uses_vx {
+                    self.cpu.registers[x]
+                } else {
+                    self.cpu.registers[0]
+                };
+                self.cpu.program_counter = offset as u16 + (instruction & 0x0FFF);
+            }
+            0xC000 => {
+                /*  Set Vx = random byte AND kk.
+                    The interpreter generates a random number from 0 to 255, which is then ANDed with the value kk. The results are stored in Vx. See instruction 8xy2 for more information on AND.
+                */
+                let mut rng = rand::thread_rng();
+                self.cpu.registers[x] =
+                    (rng.gen_range(0..256) as u16 & (instruction & 0x00FF)) as u8;
+            }
+            0xD000 => {
+                /*  Display n-byte sprite starting at memory location i at
+                (Vx, Vy), set VF = collision.
+                The interpreter reads n bytes from memory, starting at the address stored in i. These bytes are then displayed as sprites on screen at coordinates (Vx, Vy). Sprites are XORed onto the existing screen. If self.causes any pixels to be erased, VF is set to 1, otherwise it is set to 0. If the sprite is positioned so part of it is outside the coordinates of the display, it wraps around to the opposite side of the screen. See instruction 8xy3 for more information on XOR, and section 2.4, Display, for more information on the Chip-8 screen and sprites.
+                */
+                /*  On SUPER-CHIP, n = 0 draws a 16x16 sprite from 32 bytes at i instead.
+                    On XO-CHIP the sprite holds the data for each selected plane in turn.
+                */
+                let wide = self.mode.superchip() && instruction & 0x000F == 0;
+                let size = if wide {
+                    32
+                } else {
+                    (instruction & 0x000F) as usize
+                };
+                let range = self.memory_range(
+                    address,
+                    self.cpu.i as usize,
+                    size * self.display.planes.count_ones() as usize,
+                )?;
+                let collision = self.display.render(
+                    self.cpu.registers[x],
+                    self.cpu.registers[y],
+                    &self.memory.primary_memory[range],
+                    wide,
+                    self.quirks.clip_sprites,
+                );
+                self.cpu.registers[0xF] = collision as u8;
+                if self.quirks.display_wait {
+                    self.vblank_wait = true;
+                }
+            }
+            0xE000 => {
+                /*  Case ExNN
+                 */
+                match instruction & 0x00FF {
+                    0x009E => {
+                        /*  Skip next instruction if key with the value of Vx
+                            is pressed. Checks the keyboard, and if the key corresponding to the value of Vx is currently in the down position, PC is increased by 2.
+                        */
+                        if self.keyboard.is_key_pressed(self.cpu.registers[x]) {
+                            self.skip();
+                        }
+                    }
+                    0x00A1 => {
+                        /*  Skip next instruction if key with the value of Vx
+                            is not pressed. Checks the keyboard, and if the key corresponding to the value of Vx is currently in the up position, PC is increased by 2.
+                        */
+                        if !self.keyboard.is_key_pressed(self.cpu.registers[x]) {
+                            self.skip();
+                        }
+                    }
+                    _ => {
+                        return Err(Chip8Error::InvalidOpcode {
+                            address,
+                            opcode: instruction,
+                        })
+                    }
+                }
+            }
+            0xF000 => {
+                /*  Case FxNN
+                 */
+                match instruction & 0x00FF {
+                    0x0000 if self.mode.xochip() && x == 0 => {
+                        /*  Set i = nnnn.
+                            The value of register i is set to the 16-bit address following the instruction.
+                        */
+                        let range =
+                            self.memory_range(address, self.cpu.program_counter as usize, 2)?;
+                        self.cpu.i = (self.memory.primary_memory[range.start] as u16) << 8
+                            | self.memory.primary_memory[range.start + 1] as u16;
+                        self.cpu.program_counter = self.cpu.program_counter.wrapping_add(2);
+                    }
+                    0x0001 if self.mode.xochip() => {
+                        /*  Select the planes n.
+                            Drawing, clearing and scrolling only affect the bitplanes in the mask n.
+                            XO-CHIP has two planes, the upper bits of n are ignored.
+                        */
+                        self.display.planes = x as u8 & 0b11;
+                    }
+                    0x0002 if self.mode.xochip() && x == 0 => {
+                        /*  Load the audio pattern buffer.
+                            The 16 bytes starting at location i are copied into the audio pattern buffer.
+                        */
+                        let range = self.memory_range(address, self.cpu.i as usize, 16)?;
+                        self.speaker
+                            .pattern
+                            .copy_from_slice(&self.memory.primary_memory[range]);
+                    }
+                    0x0007 => {
+                        /*  Set Vx = delay timer value.
+                            The value of DT is placed into Vx.
+                        */
+                        self.cpu.registers[x] = self.cpu.delay_timer;
+                    }
+                    0x000A => {
+                        /*  Wait for a key press, store the value of the key
+                            in Vx. All execution stops until a key is pressed, then the value of that key is stored in Vx.
+                            As on the COSMAC VIP the key only counts once it is released again.
+                        */
+                        match self.keyboard.wait_for_key() {
+                            Some(key_code) => self.cpu.registers[x] = key_code,
+                            None => {
+                                self.cpu.program_counter = address;
+                                return Ok(StepOutcome::WaitingForKey);
+                            }
+                        }
+                    }
+                    0x0015 => {
+                        /*  Set delay timer = Vx.
+                            DT is set equal to the value of Vx.
+                        */
+                        self.cpu.delay_timer = self.cpu.registers[x];
+                    }
+                    0x0018 => {
+                        /*  Set sound timer = Vx.
+                            ST is set equal to the value of Vx.
+                        */
+                        self.cpu.sound_timer = self.cpu.registers[x];
+                    }
+                    0x001E => {
+                        /*  Set i = i + Vx.
+                            The values of i and Vx are added, and the results are stored in i.
+                        */
+                        self.cpu.i = self.cpu.i.wrapping_add(self.cpu.registers[x] as u16);
+                    }
+                    0x0029 => {
+                        /*  Set i = location of sprite for digit Vx.
+                            The value of i is set to the location for the hexadecimal sprite corresponding to the value of Vx. See section 2.4, Display, for more information on the Chip-8 hexadecimal font.
+                        */
+                        self.cpu.i = memory::FONT_ADDRESS + self.cpu.registers[x] as u16 * 5;
+                    }
+                    0x0030 if self.mode.superchip() => {
+                        /*  Set i = location of big sprite for digit Vx.
+                            The value of i is set to the location of the 10-byte SUPER-CHIP hexadecimal sprite corresponding to the value of Vx.
+                        */
+                        self.cpu.i = memory::BIG_FONT_ADDRESS + self.cpu.registers[x] as u16 * 10;
+                    }
+                    0x003A if self.mode.xochip() => {
+                        /*  Set pitch = Vx.
+                            The playback rate of the audio pattern buffer is set from Vx.
+                        */
+                        self.speaker.pitch = self.cpu.registers[x];
+                    }
+                    0x0033 => {
+                        /*  Store BCD representation of Vx in memory
+                            locations i, i+1, and i+2. The interpreter takes the decimal value of Vx, and places the hundreds digit in memory at location in i, the tens digit at location i+1, and the ones digit at location i+2.
+                        */
+                        let range = self.memory_range(address, self.cpu.i as usize, 3)?;
+                        self.last_write = Some(range.clone());
+                        self.memory.primary_memory[range.start + 2] = self.cpu.registers[x] % 10;
+                        self.memory.primary_memory[range.start + 1] =
+                            (self.cpu.registers[x] % 100) / 10;
+                        self.memory.primary_memory[range.start] = self.cpu.registers[x] / 100;
+                    }
+                    0x0055 => {
+                        /*  Store registers V0 through Vx in memory starting
+                            at location i. The interpreter copies the values of registers V0 through Vx into memory, starting at the address in i.
+                        */
+                        let range = self.memory_range(address, self.cpu.i as usize, x + 1)?;
+                        self.last_write = Some(range.clone());
+                        self.memory.primary_memory[range]
+                            .copy_from_slice(&self.cpu.registers[..=x]);
+                        self.advance_i(x);
+                    }
+                    0x0065 => {
+                        /*  Read registers V0 through Vx from memory starting
+                            at location i. The interpreter reads values from memory starting at location i into registers V0 through Vx.
+                        */
+                        let range = self.memory_range(address, self.cpu.i as usize, x + 1)?;
+                        self.cpu.registers[..=x]
+                            .copy_from_slice(&self.memory.primary_memory[range]);
+                        self.advance_i(x);
+                    }
+                    0x0075 if self.mode.superchip() => {
+                        /*  Store registers V0 through Vx in the RPL user flags.
+                            SUPER-CHIP only has flags for the first 8 registers.
+                        */
+                        let count = (x + 1).min(self.mode.rpl_flag_count());
+                        self.rpl_flags[..count].copy_from_slice(&self.cpu.registers[..count]);
+                    }
+                    0x0085 if self.mode.superchip() => {
+                        /*  Read registers V0 through Vx from the RPL user flags.
+                         */
+                        let count = (x + 1).min(self.mode.rpl_flag_count());
+                        self.cpu.registers[..count].copy_from_slice(&self.rpl_flags[..count]);
+                    }
+                    _ => {
+                        return Err(Chip8Error::InvalidOpcode {
+                            address,
+                            opcode: instruction,
+                        })
+                    }
+                }
+            }
+            _ => {
+                return Err(Chip8Error::InvalidOpcode {
+                    address,
+                    opcode: instruction,
+                })
+            }
+        }
+        return Ok(StepOutcome::Executed);
+    }
+
+    pub fn emulate_cycle(&mut self) -> Result<StepOutcome, Chip8Error> {
+        /*  Fetches and executes a single instruction, unless the program has
+            exited or is waiting for the vertical blank
+        */
+        if self.exited {
+            return Ok(StepOutcome::Exited);
+        }
+        if self.vblank_wait {
+            return Ok(StepOutcome::WaitingForVblank);
+        }
+        let pc = self.cpu.program_counter;
+        if pc as usize + 1 >= self.memory.primary_memory.len() {
+            return Err(Chip8Error::PcOutOfRange { address: pc });
+        }
+        self.cpu.current_instruction = (self.memory.primary_memory[pc as usize] as u16) << 8
+            | self.memory.primary_memory[pc as usize + 1] as u16;
+        if let Some(mut tracer) = self.tracer.take() {
+            tracer.record(self);
+            self.tracer = Some(tracer);
+        }
+        return self.execute(self.cpu.current_instruction);
+    }
+
+    pub fn tick_timers(&mut self) {
+        /*  The 60 Hz tick, which is also the vertical blank the display wait quirk waits for
+         */
+        self.speaker.tick(self.cpu.sound_timer > 0);
+        self.cpu.update_timers();
+        self.vblank_wait = false;
+    }
+
+    pub fn run_frame(&mut self, instructions_per_frame: usize) -> Result<StepOutcome, Chip8Error> {
+        /*  Emulates one 60 Hz frame: runs up to instructions_per_frame
+            instructions, then ticks the timers exactly once.
+            Returns the outcome of the last step, or the first error, in which
+            case the timers are left alone
+        */
+        let mut outcome = StepOutcome::Executed;
+        for _ in 0..instructions_per_frame {
+            outcome = self.emulate_cycle()?;
+            if outcome != StepOutcome::Executed {
+                break;
+            }
+        }
+        self.tick_timers();
+        return Ok(outcome);
+    }
+
+    pub fn poll_input(&mut self, input: &mut dyn InputSource) {
+        self.keyboard.set_pressed(input.poll_keys());
+    }
+
+    pub fn present(&mut self, video: &mut dyn VideoOutput) {
+        /*  Shows the screen, it is not dirty anymore afterwards
+         */
+        video.present(&self.display);
+        self.display.dirty = false;
+    }
+
+    pub fn run_host_frame(
+        &mut self,
+        input: &mut dyn InputSource,
+        video: &mut dyn VideoOutput,
+        instructions_per_frame: usize,
+    ) -> Result<StepOutcome, Chip8Error> {
+        /*  One frame for a simple host: reads the keypad, runs the frame and
+            shows the screen if it changed. Hosts pace the calls at 60 Hz
+        */
+        self.poll_input(input);
+        let outcome = self.run_frame(instructions_per_frame)?;
+        if self.display.dirty {
+            self.present(video);
+        }
+        return Ok(outcome);
+    }
+}
+
+fn register_range(x: usize, y: usize) -> Box<dyn Iterator<Item = usize>> {
+    /*  The registers Vx through Vy, counting down when x > y
+     */
+    if x <= y {
+        Box::new(x..=y)
+    } else {
+        Box::new((y..=x).rev())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn machine(mode: Mode) -> Chip8 {
+        let mut c8 = Chip8::new(mode, mode.default_quirks());
+        c8.memory.load_sprites();
+        c8.cpu.program_counter = 0x200;
+        return c8;
+    }
+
+    #[test]
+    fn superchip_switches_resolution() {
+        let mut c8 = machine(Mode::SuperChip);
+        c8.display.buffer[0] = 1;
+        c8.execute(0x00FF).unwrap();
+        assert!(c8.display.hires);
+        assert_eq!(c8.display.buffer[0], 0);
+        c8.execute(0x00FE).unwrap();
+        assert!(!c8.display.hires);
+
+        // Plain CHIP-8 takes them as machine code routines, which are ignored
+        let mut c8 = machine(Mode::Chip8);
+        c8.execute(0x00FF).unwrap();
+        assert!(!c8.display.hires);
+    }
+
+    #[test]
+    fn superchip_scrolls() {
+        let mut c8 = machine(Mode::SuperChip);
+        c8.display.buffer[0] = 1;
+        c8.execute(0x00C3).unwrap();
+        c8.execute(0x00FB).unwrap();
+        assert_eq!(c8.display.buffer[3 * 64 + 4], 1);
+        c8.execute(0x00FC).unwrap();
+        assert_eq!(c8.display.buffer[3 * 64], 1);
+    }
+
+    #[test]
+    fn dxy0_draws_a_16_by_16_sprite_on_superchip() {
+        let mut c8 = machine(Mode::SuperChip);
+        c8.cpu.i = 0x300;
+        c8.memory.primary_memory[0x300..0x320].fill(0xFF);
+        c8.execute(0xD000).unwrap();
+        assert_eq!(
+            c8.display
+                .buffer
+                .iter()
+                .filter(|&&pixel| pixel != 0)
+                .count(),
+            256
+        );
+        assert_eq!(c8.cpu.registers[0xF], 0);
+    }
+
+    #[test]
+    fn exit_stops_on_the_exit_instruction() {
+        let mut c8 = machine(Mode::SuperChip);
+        c8.memory.primary_memory[0x200..0x202].copy_from_slice(&[0x00, 0xFD]);
+        assert_eq!(c8.emulate_cycle(), Ok(StepOutcome::Exited));
+        assert!(c8.exited);
+        assert_eq!(c8.cpu.program_counter, 0x200);
+        assert_eq!(c8.emulate_cycle(), Ok(StepOutcome::Exited));
+    }
+
+    #[test]
+    fn fx30_points_i_at_the_big_font() {
+        let mut c8 = machine(Mode::SuperChip);
+        c8.cpu.registers[2] = 0xA;
+        c8.execute(0xF230).unwrap();
+        assert_eq!(c8.cpu.i, memory::BIG_FONT_ADDRESS + 100);
+        assert_eq!(c8.memory.primary_memory[c8.cpu.i as usize], 0x7E);
+    }
+
+    #[test]
+    fn rpl_flags_save_and_restore_registers() {
+        let mut c8 = machine(Mode::SuperChip);
+        c8.cpu.registers = std::array::from_fn(|register| register as u8 + 1);
+        c8.execute(0xFF75).unwrap();
+        assert_eq!(c8.rpl_flags[..9], [1, 2, 3, 4, 5, 6, 7, 8, 0]);
+
+        c8.cpu.registers = [0; 16];
+        c8.execute(0xF285).unwrap();
+        assert_eq!(c8.cpu.registers[..4], [1, 2, 3, 0]);
+    }
+
+    #[test]
+    fn xochip_has_16_rpl_flags() {
+        let mut c8 = machine(Mode::XoChip);
+        c8.cpu.registers = [7; 16];
+        c8.execute(0xFF75).unwrap();
+        assert_eq!(c8.rpl_flags, [7; 16]);
+    }
+
+    #[test]
+    fn xochip_long_i_is_skipped_whole() {
+        let mut c8 = machine(Mode::XoChip);
+        c8.memory.primary_memory[0x200..0x208]
+            .copy_from_slice(&[0x30, 0x00, 0xF0, 0x00, 0x12, 0x34, 0x60, 0x01]);
+        c8.emulate_cycle().unwrap();
+        assert_eq!(c8.cpu.program_counter, 0x206);
+
+        c8.cpu.program_counter = 0x202;
+        c8.emulate_cycle().unwrap();
+        assert_eq!(c8.cpu.i, 0x1234);
+        assert_eq!(c8.cpu.program_counter, 0x206);
+
+        // Before XO-CHIP F000 is not an instruction, so the skip is the usual 2 bytes
+        let mut c8 = machine(Mode::SuperChip);
+        c8.memory.primary_memory[0x200..0x204].copy_from_slice(&[0x30, 0x00, 0xF0, 0x00]);
+        c8.emulate_cycle().unwrap();
+        assert_eq!(c8.cpu.program_counter, 0x204);
+    }
+
+    #[test]
+    fn xochip_saves_and_loads_register_ranges_both_ways() {
+        let mut c8 = machine(Mode::XoChip);
+        c8.cpu.registers = std::array::from_fn(|register| register as u8 * 0x11);
+        c8.cpu.i = 0x400;
+        c8.execute(0x5242).unwrap();
+        assert_eq!(
+            c8.memory.primary_memory[0x400..0x404],
+            [0x22, 0x33, 0x44, 0x00]
+        );
+        c8.execute(0x5732).unwrap();
+        assert_eq!(
+            c8.memory.primary_memory[0x400..0x406],
+            [0x77, 0x66, 0x55, 0x44, 0x33, 0x00]
+        );
+        assert_eq!(c8.last_write, Some(0x400..0x405));
+        assert_eq!(c8.cpu.i, 0x400);
+
+        c8.execute(0x5AE3).unwrap();
+        assert_eq!(c8.cpu.registers[0xA..=0xE], [0x77, 0x66, 0x55, 0x44, 0x33]);
+        c8.execute(0x5103).unwrap();
+        assert_eq!(c8.cpu.registers[..2], [0x66, 0x77]);
+        assert_eq!(c8.cpu.i, 0x400);
+    }
+
+    #[test]
+    fn xochip_draws_and_collides_per_plane() {
+        let mut c8 = machine(Mode::XoChip);
+        c8.cpu.i = 0x300;
+        c8.memory.primary_memory[0x300..0x302].copy_from_slice(&[0x80, 0xC0]);
+
+        // Plane 2 alone takes the first byte
+        c8.execute(0xF201).unwrap();
+        c8.execute(0xD011).unwrap();
+        assert_eq!(c8.display.buffer[..2], [0b10, 0b00]);
+
+        // Both planes take a byte each, plane 1 first. Erasing the plane 2 pixel collides
+        c8.execute(0xF301).unwrap();
+        c8.execute(0xD011).unwrap();
+        assert_eq!(c8.display.buffer[..2], [0b01, 0b10]);
+        assert_eq!(c8.cpu.registers[0xF], 1);
+
+        // Plane 1 alone only collides with plane 1 pixels
+        c8.execute(0xF101).unwrap();
+        c8.execute(0xD011).unwrap();
+        assert_eq!(c8.display.buffer[..2], [0b00, 0b10]);
+        assert_eq!(c8.cpu.registers[0xF], 1);
+        c8.cpu.registers[0] = 1;
+        c8.execute(0xD011).unwrap();
+        assert_eq!(c8.display.buffer[..2], [0b00, 0b11]);
+        assert_eq!(c8.cpu.registers[0xF], 0);
+    }
+
+    #[test]
+    fn xochip_plane_mask_has_two_planes() {
+        let mut c8 = machine(Mode::XoChip);
+        c8.execute(0xFF01).unwrap();
+        assert_eq!(c8.display.planes, 0b11);
+        c8.execute(0xF401).unwrap();
+        assert_eq!(c8.display.planes, 0b00);
+
+        // With no plane selected nothing is drawn and nothing is read
+        c8.cpu.i = 0xFFFF;
+        c8.execute(0xD01F).unwrap();
+        assert!(c8.display.buffer.iter().all(|&pixel| pixel == 0));
+    }
+}
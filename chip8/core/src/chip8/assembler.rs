@@ -0,0 +1,631 @@
+use std::collections::{BTreeMap, HashMap};
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// Where programs are loaded, the address of the first byte assembled
+const PROGRAM_START: usize = 0x200;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct AssembleError {
+    /* A problem in the source, with the file and line it was found on
+     */
+    pub file: String,
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for AssembleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.file, self.line, self.message)
+    }
+}
+
+impl Error for AssembleError {}
+
+pub struct Assembly {
+    // The rom image, loaded at 0x200
+    pub program: Vec<u8>,
+
+    // Every label and its address
+    pub labels: BTreeMap<String, u16>,
+}
+
+impl Assembly {
+    pub fn symbol_map(&self) -> String {
+        /*  One `<address> <label>` line per label, in address order
+         */
+        let mut symbols: Vec<(&u16, &String)> = self
+            .labels
+            .iter()
+            .map(|(name, address)| (address, name))
+            .collect();
+        symbols.sort();
+        return symbols
+            .iter()
+            .map(|(address, name)| format!("{:#06X} {}\n", address, name))
+            .collect();
+    }
+}
+
+#[derive(Clone)]
+struct Token {
+    text: String,
+    file: String,
+    line: usize,
+}
+
+impl Token {
+    fn error(&self, message: String) -> AssembleError {
+        return AssembleError {
+            file: self.file.clone(),
+            line: self.line,
+            message,
+        };
+    }
+}
+
+fn tokenize(
+    path: &Path,
+    from: Option<&Token>,
+    open: &mut Vec<PathBuf>,
+) -> Result<Vec<Token>, AssembleError> {
+    /*  Splits a source file into whitespace separated tokens, dropping
+        comments and splicing in the tokens of included files.
+        arg: from is the :include naming this file, if any
+        arg: open is the chain of files including this one, to catch include cycles
+    */
+    let file = path.display().to_string();
+    let source = fs::read_to_string(path).map_err(|error| {
+        let message = format!("unable to read {}: {}", file, error);
+        match from {
+            Some(token) => token.error(message),
+            None => AssembleError {
+                file: file.clone(),
+                line: 0,
+                message,
+            },
+        }
+    })?;
+    let canonical = fs::canonicalize(path).unwrap_or(path.to_path_buf());
+    if let (Some(token), true) = (from, open.contains(&canonical)) {
+        return Err(token.error(format!("{} includes itself", file)));
+    }
+    open.push(canonical);
+
+    let mut tokens = Vec::new();
+    let mut include: Option<Token> = None;
+    for (number, line) in source.lines().enumerate() {
+        let code = line.split('#').next().unwrap_or("");
+        for text in code.split_whitespace() {
+            let token = Token {
+                text: text.to_string(),
+                file: file.clone(),
+                line: number + 1,
+            };
+            if include.take().is_some() {
+                let name = token.text.trim_matches('"');
+                let included = path.parent().unwrap_or(Path::new("")).join(name);
+                tokens.extend(tokenize(&included, Some(&token), open)?);
+            } else if token.text == ":include" {
+                include = Some(token);
+            } else {
+                tokens.push(token);
+            }
+        }
+    }
+    if let Some(token) = include {
+        return Err(token.error("expected a file name after :include".to_string()));
+    }
+    open.pop();
+    return Ok(tokens);
+}
+
+fn parse_number(text: &str) -> Option<i64> {
+    let (negative, digits) = match text.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, text),
+    };
+    let value = if let Some(hex) = digits.strip_prefix("0x") {
+        i64::from_str_radix(hex, 16).ok()?
+    } else if let Some(binary) = digits.strip_prefix("0b") {
+        i64::from_str_radix(binary, 2).ok()?
+    } else {
+        digits.parse().ok()?
+    };
+    return Some(if negative { -value } else { value });
+}
+
+fn register(token: &Token) -> Option<u16> {
+    let digit = token.text.strip_prefix(['v', 'V'])?;
+    if digit.len() != 1 {
+        return None;
+    }
+    return u16::from_str_radix(digit, 16).ok();
+}
+
+// An address the label will fill in once every label is known
+struct Fixup {
+    position: usize,
+    label: Token,
+
+    // The 16-bit operand of F000 NNNN rather than the 12-bit one of an instruction
+    long: bool,
+}
+
+struct Assembler {
+    tokens: Vec<Token>,
+    position: usize,
+    program: Vec<u8>,
+    labels: BTreeMap<String, u16>,
+    constants: HashMap<String, i64>,
+    fixups: Vec<Fixup>,
+}
+
+impl Assembler {
+    fn next(&mut self) -> Result<Token, AssembleError> {
+        match self.tokens.get(self.position) {
+            Some(token) => {
+                self.position += 1;
+                return Ok(token.clone());
+            }
+            None => {
+                let last = self.tokens.last().unwrap();
+                return Err(last.error("unexpected end of source".to_string()));
+            }
+        }
+    }
+
+    fn expect(&mut self, text: &str) -> Result<(), AssembleError> {
+        let token = self.next()?;
+        if token.text != text {
+            return Err(token.error(format!("expected `{}`, found `{}`", text, token.text)));
+        }
+        return Ok(());
+    }
+
+    fn register(&mut self) -> Result<u16, AssembleError> {
+        let token = self.next()?;
+        return register(&token)
+            .ok_or_else(|| token.error(format!("expected a register, found `{}`", token.text)));
+    }
+
+    fn number(&mut self, min: i64, max: i64) -> Result<i64, AssembleError> {
+        /*  A number, constant or already defined label within min..=max
+         */
+        let token = self.next()?;
+        let value = parse_number(&token.text)
+            .or_else(|| self.constants.get(&token.text).copied())
+            .or_else(|| self.labels.get(&token.text).map(|&address| address as i64))
+            .ok_or_else(|| token.error(format!("expected a number, found `{}`", token.text)))?;
+        if value < min || value > max {
+            return Err(token.error(format!("{} is out of range", value)));
+        }
+        return Ok(value);
+    }
+
+    fn value(&mut self, min: i64, max: i64) -> Result<u16, AssembleError> {
+        /*  Like number, with negative values wrapping around as two's complement
+         */
+        return Ok((self.number(min, max)? & 0xFFFF) as u16);
+    }
+
+    fn byte(&mut self) -> Result<u16, AssembleError> {
+        return Ok(self.value(-128, 255)? & 0xFF);
+    }
+
+    fn nibble(&mut self) -> Result<u16, AssembleError> {
+        return self.value(0, 15);
+    }
+
+    fn address(&mut self, long: bool) -> Result<u16, AssembleError> {
+        /*  A number, constant or label, which may not be defined yet
+         */
+        let token = self.next()?;
+        if parse_number(&token.text).is_some() || self.constants.contains_key(&token.text) {
+            self.position -= 1;
+            return self.value(0, if long { 0xFFFF } else { 0xFFF });
+        }
+        self.fixups.push(Fixup {
+            position: self.program.len(),
+            label: token,
+            long,
+        });
+        return Ok(0);
+    }
+
+    fn emit(&mut self, instruction: u16) {
+        self.program.extend_from_slice(&instruction.to_be_bytes());
+    }
+
+    fn statement(&mut self) -> Result<(), AssembleError> {
+        let token = self.next()?;
+        if let Some(x) = register(&token) {
+            return self.assignment(x);
+        }
+        match token.text.as_str() {
+            ":" => {
+                let name = self.next()?;
+                let address = (PROGRAM_START + self.program.len()) as u16;
+                if self.labels.insert(name.text.clone(), address).is_some() {
+                    return Err(name.error(format!("label `{}` is defined twice", name.text)));
+                }
+            }
+            ":const" => {
+                let name = self.next()?;
+                let value = self.number(-0x8000, 0xFFFF)?;
+                self.constants.insert(name.text, value);
+            }
+            ":byte" => {
+                let value = self.byte()?;
+                self.program.push(value as u8);
+            }
+            ":call" => {
+                let address = self.address(false)?;
+                self.emit(0x2000 | address);
+            }
+            "clear" => self.emit(0x00E0),
+            "return" => self.emit(0x00EE),
+            "scroll-down" => {
+                let n = self.nibble()?;
+                self.emit(0x00C0 | n);
+            }
+            "scroll-up" => {
+                let n = self.nibble()?;
+                self.emit(0x00D0 | n);
+            }
+            "scroll-right" => self.emit(0x00FB),
+            "scroll-left" => self.emit(0x00FC),
+            "exit" => self.emit(0x00FD),
+            "lores" => self.emit(0x00FE),
+            "hires" => self.emit(0x00FF),
+            "jump" => {
+                let address = self.address(false)?;
+                self.emit(0x1000 | address);
+            }
+            "jump0" => {
+                let address = self.address(false)?;
+                self.emit(0xB000 | address);
+            }
+            "if" => return self.condition(),
+            "save" | "load" => {
+                let x = self.register()?;
+                let range = self
+                    .tokens
+                    .get(self.position)
+                    .map(|token| token.text.as_str());
+                if range == Some("-") {
+                    self.position += 1;
+                    let y = self.register()?;
+                    let low = if token.text == "save" { 0x2 } else { 0x3 };
+                    self.emit(0x5000 | x << 8 | y << 4 | low);
+                } else {
+                    let low = if token.text == "save" { 0x55 } else { 0x65 };
+                    self.emit(0xF000 | x << 8 | low);
+                }
+            }
+            "sprite" => {
+                let x = self.register()?;
+                let y = self.register()?;
+                let n = self.nibble()?;
+                self.emit(0xD000 | x << 8 | y << 4 | n);
+            }
+            "bcd" => {
+                let x = self.register()?;
+                self.emit(0xF033 | x << 8);
+            }
+            "saveflags" => {
+                let x = self.register()?;
+                self.emit(0xF075 | x << 8);
+            }
+            "loadflags" => {
+                let x = self.register()?;
+                self.emit(0xF085 | x << 8);
+            }
+            "plane" => {
+                let n = self.nibble()?;
+                self.emit(0xF001 | n << 8);
+            }
+            "audio" => self.emit(0xF002),
+            "i" => return self.index(),
+            "delay" | "buzzer" | "pitch" => {
+                self.expect(":=")?;
+                let x = self.register()?;
+                let low = match token.text.as_str() {
+                    "delay" => 0x15,
+                    "buzzer" => 0x18,
+                    _ => 0x3A,
+                };
+                self.emit(0xF000 | x << 8 | low);
+            }
+            _ => {
+                if parse_number(&token.text).is_some() || self.constants.contains_key(&token.text) {
+                    // Bare numbers are data
+                    self.position -= 1;
+                    let value = self.byte()?;
+                    self.program.push(value as u8);
+                } else if token.text.starts_with(':') {
+                    return Err(token.error(format!("unknown directive `{}`", token.text)));
+                } else {
+                    // A bare name calls the subroutine of that name
+                    self.position -= 1;
+                    let address = self.address(false)?;
+                    self.emit(0x2000 | address);
+                }
+            }
+        }
+        return Ok(());
+    }
+
+    fn assignment(&mut self, x: u16) -> Result<(), AssembleError> {
+        /*  The statements starting with a register, e.g. `v0 += v1`
+         */
+        let operator = self.next()?;
+        let operand = self.next()?;
+        let vx = x << 8;
+        if let Some(y) = register(&operand) {
+            let low = match operator.text.as_str() {
+                ":=" => 0x0,
+                "|=" => 0x1,
+                "&=" => 0x2,
+                "^=" => 0x3,
+                "+=" => 0x4,
+                "-=" => 0x5,
+                ">>=" => 0x6,
+                "=-" => 0x7,
+                "<<=" => 0xE,
+                _ => return Err(operator.error(format!("unknown operator `{}`", operator.text))),
+            };
+            self.emit(0x8000 | vx | y << 4 | low);
+            return Ok(());
+        }
+        match (operator.text.as_str(), operand.text.as_str()) {
+            (":=", "random") => {
+                let nn = self.byte()?;
+                self.emit(0xC000 | vx | nn);
+            }
+            (":=", "delay") => self.emit(0xF007 | vx),
+            (":=", "key") => self.emit(0xF00A | vx),
+            (":=", _) | ("+=", _) => {
+                self.position -= 1;
+                let nn = self.byte()?;
+                let high = if operator.text == ":=" {
+                    0x6000
+                } else {
+                    0x7000
+                };
+                self.emit(high | vx | nn);
+            }
+            _ => return Err(operator.error(format!("unknown operator `{}`", operator.text))),
+        }
+        return Ok(());
+    }
+
+    fn index(&mut self) -> Result<(), AssembleError> {
+        /*  The statements on the i register, e.g. `i := hex v0`
+         */
+        let operator = self.next()?;
+        match operator.text.as_str() {
+            "+=" => {
+                let x = self.register()?;
+                self.emit(0xF01E | x << 8);
+            }
+            ":=" => {
+                let operand = self.next()?;
+                match operand.text.as_str() {
+                    "hex" => {
+                        let x = self.register()?;
+                        self.emit(0xF029 | x << 8);
+                    }
+                    "bighex" => {
+                        let x = self.register()?;
+                        self.emit(0xF030 | x << 8);
+                    }
+                    "long" => {
+                        self.emit(0xF000);
+                        let address = self.address(true)?;
+                        self.emit(address);
+                    }
+                    _ => {
+                        self.position -= 1;
+                        let address = self.address(false)?;
+                        self.emit(0xA000 | address);
+                    }
+                }
+            }
+            _ => return Err(operator.error(format!("unknown operator `{}`", operator.text))),
+        }
+        return Ok(());
+    }
+
+    fn condition(&mut self) -> Result<(), AssembleError> {
+        /*  `if <condition> then`, which skips the next statement when the
+            condition does not hold
+        */
+        let x = self.register()? << 8;
+        let operator = self.next()?;
+        match operator.text.as_str() {
+            "key" => self.emit(0xE0A1 | x),
+            "-key" => self.emit(0xE09E | x),
+            "==" | "!=" => {
+                let equal = operator.text == "==";
+                let operand = self.next()?;
+                if let Some(y) = register(&operand) {
+                    self.emit(if equal { 0x9000 } else { 0x5000 } | x | y << 4);
+                } else {
+                    self.position -= 1;
+                    let nn = self.byte()?;
+                    self.emit(if equal { 0x4000 } else { 0x3000 } | x | nn);
+                }
+            }
+            _ => return Err(operator.error(format!("unknown condition `{}`", operator.text))),
+        }
+        return self.expect("then");
+    }
+
+    fn resolve(&mut self) -> Result<(), AssembleError> {
+        /*  Fills in the addresses of labels used before they were defined
+         */
+        for fixup in self.fixups.iter() {
+            let address = *self.labels.get(&fixup.label.text).ok_or_else(|| {
+                fixup
+                    .label
+                    .error(format!("undefined label `{}`", fixup.label.text))
+            })?;
+            let position = fixup.position;
+            if fixup.long {
+                self.program[position..position + 2].copy_from_slice(&address.to_be_bytes());
+            } else if address > 0xFFF {
+                return Err(fixup.label.error(format!(
+                    "label `{}` at {:#06X} is out of reach, use `i := long`",
+                    fixup.label.text, address
+                )));
+            } else {
+                self.program[position] |= (address >> 8) as u8;
+                self.program[position + 1] = address as u8;
+            }
+        }
+        return Ok(());
+    }
+}
+
+pub fn assemble(path: &str) -> Result<Assembly, AssembleError> {
+    /*  Assembles a source file, and the files it includes, into a rom
+     */
+    let tokens = tokenize(Path::new(path), None, &mut Vec::new())?;
+    let mut assembler = Assembler {
+        tokens,
+        position: 0,
+        program: Vec::new(),
+        labels: BTreeMap::new(),
+        constants: HashMap::new(),
+        fixups: Vec::new(),
+    };
+    while assembler.position < assembler.tokens.len() {
+        assembler.statement()?;
+    }
+    assembler.resolve()?;
+    return Ok(Assembly {
+        program: assembler.program,
+        labels: assembler.labels,
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chip8::{disassemble, Mode, Syntax};
+
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("chip8-asm-{}-{}", std::process::id(), name));
+        fs::create_dir_all(&dir).unwrap();
+        return dir;
+    }
+
+    fn assemble_source(name: &str, source: &str) -> Result<Assembly, AssembleError> {
+        let path = scratch_dir(name).join("main.8o");
+        fs::write(&path, source).unwrap();
+        return assemble(path.to_str().unwrap());
+    }
+
+    fn error_message(name: &str, source: &str) -> String {
+        return assemble_source(name, source).err().unwrap().message;
+    }
+
+    #[test]
+    fn disassembled_roms_reassemble_byte_for_byte() {
+        let roms = Path::new(env!("CARGO_MANIFEST_DIR")).join("..");
+        let mut paths: Vec<PathBuf> = ["roms", "community-roms"]
+            .iter()
+            .flat_map(|dir| fs::read_dir(roms.join(dir)).unwrap())
+            .map(|entry| entry.unwrap().path())
+            .collect();
+        paths.sort();
+        assert!(!paths.is_empty());
+
+        let dir = scratch_dir("roms");
+        for path in paths {
+            let rom = fs::read(&path).unwrap();
+            let source = dir.join("rom.8o");
+            fs::write(&source, disassemble(&rom, Mode::XoChip, Syntax::Octo)).unwrap();
+            let assembly = assemble(source.to_str().unwrap())
+                .unwrap_or_else(|error| panic!("{}: {}", path.display(), error));
+            assert!(
+                assembly.program == rom,
+                "{} did not reassemble",
+                path.display()
+            );
+        }
+    }
+
+    #[test]
+    fn labels_resolve_forwards_and_backwards() {
+        let assembly = assemble_source(
+            "labels",
+            ": main\n  jump end\n: end\n  jump main\n  i := long end\n",
+        )
+        .unwrap();
+        assert_eq!(
+            assembly.program,
+            [0x12, 0x02, 0x12, 0x00, 0xF0, 0x00, 0x02, 0x02]
+        );
+        assert_eq!(assembly.labels["end"], 0x202);
+        assert_eq!(assembly.symbol_map(), "0x0200 main\n0x0202 end\n");
+    }
+
+    #[test]
+    fn label_errors() {
+        assert_eq!(
+            error_message("undefined", "jump nowhere\n"),
+            "undefined label `nowhere`"
+        );
+        assert_eq!(
+            error_message("twice", ": here\n: here\n"),
+            "label `here` is defined twice"
+        );
+    }
+
+    #[test]
+    fn byte_takes_one_byte() {
+        let assembly = assemble_source("byte", ":byte 0xAB :byte 7 :byte -1\n").unwrap();
+        assert_eq!(assembly.program, [0xAB, 0x07, 0xFF]);
+
+        let error = assemble_source("byte-range", "\n:byte 0x100\n")
+            .err()
+            .unwrap();
+        assert_eq!(error.line, 2);
+        assert!(assemble_source("byte-missing", ":byte\n").is_err());
+    }
+
+    #[test]
+    fn include_splices_in_the_file() {
+        let dir = scratch_dir("include");
+        fs::write(dir.join("data.8o"), ": data :byte 0x42\n").unwrap();
+        let path = dir.join("main.8o");
+        fs::write(&path, "jump data\n:include \"data.8o\"\n").unwrap();
+
+        let assembly = assemble(path.to_str().unwrap()).unwrap();
+        assert_eq!(assembly.program, [0x12, 0x02, 0x42]);
+    }
+
+    #[test]
+    fn include_errors() {
+        let dir = scratch_dir("include-errors");
+        fs::write(dir.join("loop.8o"), ":include loop.8o\n").unwrap();
+        let path = dir.join("loop.8o");
+        let error = assemble(path.to_str().unwrap()).err().unwrap();
+        assert!(error.message.ends_with("includes itself"), "{}", error);
+
+        let error = assemble_source("include-missing", "clear\n:include missing.8o\n")
+            .err()
+            .unwrap();
+        assert_eq!(error.line, 2);
+        assert!(error.message.starts_with("unable to read"), "{}", error);
+
+        assert_eq!(
+            error_message("include-name", ":include\n"),
+            "expected a file name after :include"
+        );
+    }
+}
@@ -0,0 +1,157 @@
+use std::collections::BTreeSet;
+use std::ops::Range;
+
+use super::{Chip8, Chip8Error, StepOutcome};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Stop {
+    /* Why the debugger paused the machine
+     */
+    // Paused by the user, or a single step finished
+    User,
+    Breakpoint(u16),
+
+    // The instruction at address wrote to memory inside a watched range
+    Watchpoint { address: u16, write: Range<usize> },
+}
+
+pub struct Debugger {
+    // Some while the machine is paused, with the reason
+    pub stopped: Option<Stop>,
+
+    // Program counters that pause the machine before the instruction there runs
+    pub breakpoints: BTreeSet<u16>,
+
+    // Ranges of memory that pause the machine after an instruction writes to them
+    pub watchpoints: Vec<Range<usize>>,
+
+    // Stepping over a call, pause when the pc gets back to this address at
+    // this stack depth
+    step_over: Option<(u16, u8)>,
+
+    // Resuming from a breakpoint, the instruction at this pc runs without stopping
+    resume_from: Option<u16>,
+}
+
+impl Debugger {
+    pub fn new() -> Debugger {
+        return Debugger {
+            stopped: None,
+            breakpoints: BTreeSet::new(),
+            watchpoints: Vec::new(),
+            step_over: None,
+            resume_from: None,
+        };
+    }
+
+    pub fn pause(&mut self) {
+        self.stopped.get_or_insert(Stop::User);
+    }
+
+    pub fn resume(&mut self, c8: &Chip8) {
+        if self.stopped.take().is_some() {
+            self.resume_from = Some(c8.cpu.program_counter);
+        }
+    }
+
+    pub fn toggle_breakpoint(&mut self, address: u16) {
+        if !self.breakpoints.remove(&address) {
+            self.breakpoints.insert(address);
+        }
+    }
+
+    pub fn toggle_watchpoint(&mut self, range: Range<usize>) {
+        match self
+            .watchpoints
+            .iter()
+            .position(|watched| *watched == range)
+        {
+            Some(index) => {
+                self.watchpoints.remove(index);
+            }
+            None => self.watchpoints.push(range),
+        }
+    }
+
+    fn cycle(&mut self, c8: &mut Chip8) -> Result<StepOutcome, Chip8Error> {
+        /*  Runs one instruction unless a breakpoint is in the way, pausing
+            afterwards if it hit a watchpoint or finished a step over
+        */
+        let pc = c8.cpu.program_counter;
+        if self.resume_from.take() != Some(pc) && self.breakpoints.contains(&pc) {
+            self.stopped = Some(Stop::Breakpoint(pc));
+            return Ok(StepOutcome::Executed);
+        }
+        let outcome = c8.emulate_cycle()?;
+        if let Some(write) = &c8.last_write {
+            let watched = self
+                .watchpoints
+                .iter()
+                .any(|range| range.start < write.end && write.start < range.end);
+            if watched {
+                self.stopped = Some(Stop::Watchpoint {
+                    address: pc,
+                    write: write.clone(),
+                });
+            }
+        }
+        if self.step_over == Some((c8.cpu.program_counter, c8.cpu.stack_pointer)) {
+            self.step_over = None;
+            self.stopped.get_or_insert(Stop::User);
+        }
+        return Ok(outcome);
+    }
+
+    pub fn run_frame(
+        &mut self,
+        c8: &mut Chip8,
+        instructions_per_frame: usize,
+    ) -> Result<StepOutcome, Chip8Error> {
+        /*  Chip8::run_frame, stopping early at breakpoints and watchpoints.
+            Nothing runs, and the timers stand still, while paused
+        */
+        if self.stopped.is_some() {
+            return Ok(StepOutcome::Executed);
+        }
+        let mut outcome = StepOutcome::Executed;
+        for _ in 0..instructions_per_frame {
+            outcome = self.cycle(c8)?;
+            if outcome != StepOutcome::Executed || self.stopped.is_some() {
+                break;
+            }
+        }
+        c8.tick_timers();
+        return Ok(outcome);
+    }
+
+    pub fn step(&mut self, c8: &mut Chip8) -> Result<StepOutcome, Chip8Error> {
+        /*  Runs the next instruction and pauses again
+         */
+        self.stopped = None;
+        self.resume_from = Some(c8.cpu.program_counter);
+        self.step_over = None;
+        let outcome = self.cycle(c8)?;
+        self.stopped.get_or_insert(Stop::User);
+        return Ok(outcome);
+    }
+
+    pub fn step_over(&mut self, c8: &mut Chip8) -> Result<StepOutcome, Chip8Error> {
+        /*  Like step, but a 2NNN call runs until the subroutine returns
+         */
+        let pc = c8.cpu.program_counter as usize;
+        let memory = &c8.memory.primary_memory;
+        if pc + 1 < memory.len() && memory[pc] & 0xF0 == 0x20 {
+            self.step_over = Some((pc as u16 + 2, c8.cpu.stack_pointer));
+            self.stopped = None;
+            self.resume_from = Some(pc as u16);
+            return Ok(StepOutcome::Executed);
+        }
+        return self.step(c8);
+    }
+}
+
+impl Default for Debugger {
+    fn default() -> Debugger {
+        return Debugger::new();
+    }
+}
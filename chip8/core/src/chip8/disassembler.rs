@@ -0,0 +1,411 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use super::mode::Mode;
+
+// Where programs are loaded, and where tracing starts
+const PROGRAM_START: u16 = 0x200;
+
+// Bytes of data shown per line of the listing
+const DATA_PER_LINE: usize = 8;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Syntax {
+    /* Mnemonic style of the listing
+     */
+    // Octo assembly, e.g. `v0 += 0x01`
+    Octo,
+
+    // Cowgod's Chip-8 Technical Reference, e.g. `ADD V0, 0x01`
+    Cowgod,
+}
+
+impl Syntax {
+    pub fn from_name(name: &str) -> Option<Syntax> {
+        match name {
+            "octo" => Some(Syntax::Octo),
+            "cowgod" => Some(Syntax::Cowgod),
+            _ => None,
+        }
+    }
+
+    fn comment(&self) -> &'static str {
+        match self {
+            Syntax::Octo => "#",
+            Syntax::Cowgod => ";",
+        }
+    }
+}
+
+enum Flow {
+    /* Where execution can go after an instruction
+     */
+    // On to the next instruction
+    Next,
+
+    // To the next instruction or the one after it
+    Skip,
+    Jump(u16),
+
+    // To the subroutine, then back to the next instruction
+    Call(u16),
+
+    // Nowhere that can be followed: returns, exits and computed jumps
+    Stop,
+}
+
+struct Decoded {
+    text: String,
+
+    // 2, or 4 for the XO-CHIP F000 NNNN
+    len: u16,
+    flow: Flow,
+
+    // Address loaded into i, which is probably data
+    data: Option<u16>,
+}
+
+fn word(memory: &[u8], address: usize) -> Option<u16> {
+    if address + 1 >= memory.len() {
+        return None;
+    }
+    return Some((memory[address] as u16) << 8 | memory[address + 1] as u16);
+}
+
+fn decode(
+    memory: &[u8],
+    address: u16,
+    mode: Mode,
+    syntax: Syntax,
+    labels: &BTreeMap<u16, String>,
+) -> Option<Decoded> {
+    /*  Decodes the instruction at address, None when it is not one that
+        Chip8::execute accepts in this mode. Addresses with a label are named by it
+    */
+    let instruction = word(memory, address as usize)?;
+    let x = ((instruction & 0x0F00) >> 8) as usize;
+    let y = ((instruction & 0x00F0) >> 4) as usize;
+    let n = instruction & 0x000F;
+    let nn = instruction & 0x00FF;
+    let nnn = instruction & 0x0FFF;
+    let octo = syntax == Syntax::Octo;
+    let (vx, vy) = if octo {
+        (format!("v{:x}", x), format!("v{:x}", y))
+    } else {
+        (format!("V{:X}", x), format!("V{:X}", y))
+    };
+    let target = |address: u16| match labels.get(&address) {
+        Some(label) => label.clone(),
+        None => format!("{:#05X}", address),
+    };
+    let pick = |octo_text: String, cowgod_text: String| if octo { octo_text } else { cowgod_text };
+
+    let mut len = 2;
+    let mut flow = Flow::Next;
+    let mut data = None;
+    let text = match instruction & 0xF000 {
+        0x0000 => match instruction {
+            0x00E0 => pick("clear".into(), "CLS".into()),
+            0x00EE => {
+                flow = Flow::Stop;
+                pick("return".into(), "RET".into())
+            }
+            0x00C0..=0x00CF if mode.superchip() => {
+                pick(format!("scroll-down {}", n), format!("SCD {}", n))
+            }
+            0x00D0..=0x00DF if mode.xochip() => {
+                pick(format!("scroll-up {}", n), format!("SCU {}", n))
+            }
+            0x00FB if mode.superchip() => pick("scroll-right".into(), "SCR".into()),
+            0x00FC if mode.superchip() => pick("scroll-left".into(), "SCL".into()),
+            0x00FD if mode.superchip() => {
+                flow = Flow::Stop;
+                pick("exit".into(), "EXIT".into())
+            }
+            0x00FE if mode.superchip() => pick("lores".into(), "LOW".into()),
+            0x00FF if mode.superchip() => pick("hires".into(), "HIGH".into()),
+            _ => return None,
+        },
+        0x1000 => {
+            flow = Flow::Jump(nnn);
+            pick(
+                format!("jump {}", target(nnn)),
+                format!("JP {}", target(nnn)),
+            )
+        }
+        0x2000 => {
+            flow = Flow::Call(nnn);
+            pick(
+                format!(":call {}", target(nnn)),
+                format!("CALL {}", target(nnn)),
+            )
+        }
+        0x3000 => {
+            flow = Flow::Skip;
+            pick(
+                format!("if {} != {:#04X} then", vx, nn),
+                format!("SE {}, {:#04X}", vx, nn),
+            )
+        }
+        0x4000 => {
+            flow = Flow::Skip;
+            pick(
+                format!("if {} == {:#04X} then", vx, nn),
+                format!("SNE {}, {:#04X}", vx, nn),
+            )
+        }
+        0x5000 => match n {
+            0x0 => {
+                flow = Flow::Skip;
+                pick(
+                    format!("if {} != {} then", vx, vy),
+                    format!("SE {}, {}", vx, vy),
+                )
+            }
+            0x2 if mode.xochip() => pick(
+                format!("save {} - {}", vx, vy),
+                format!("SAVE {}, {}", vx, vy),
+            ),
+            0x3 if mode.xochip() => pick(
+                format!("load {} - {}", vx, vy),
+                format!("LOAD {}, {}", vx, vy),
+            ),
+            _ => return None,
+        },
+        0x6000 => pick(
+            format!("{} := {:#04X}", vx, nn),
+            format!("LD {}, {:#04X}", vx, nn),
+        ),
+        0x7000 => pick(
+            format!("{} += {:#04X}", vx, nn),
+            format!("ADD {}, {:#04X}", vx, nn),
+        ),
+        0x8000 => {
+            let (operator, mnemonic) = match n {
+                0x0 => (":=", "LD"),
+                0x1 => ("|=", "OR"),
+                0x2 => ("&=", "AND"),
+                0x3 => ("^=", "XOR"),
+                0x4 => ("+=", "ADD"),
+                0x5 => ("-=", "SUB"),
+                0x6 => (">>=", "SHR"),
+                0x7 => ("=-", "SUBN"),
+                0xE => ("<<=", "SHL"),
+                _ => return None,
+            };
+            pick(
+                format!("{} {} {}", vx, operator, vy),
+                format!("{} {}, {}", mnemonic, vx, vy),
+            )
+        }
+        0x9000 => {
+            flow = Flow::Skip;
+            pick(
+                format!("if {} == {} then", vx, vy),
+                format!("SNE {}, {}", vx, vy),
+            )
+        }
+        0xA000 => {
+            data = Some(nnn);
+            pick(
+                format!("i := {}", target(nnn)),
+                format!("LD I, {}", target(nnn)),
+            )
+        }
+        0xB000 => {
+            flow = Flow::Stop;
+            pick(
+                format!("jump0 {}", target(nnn)),
+                format!("JP V0, {}", target(nnn)),
+            )
+        }
+        0xC000 => pick(
+            format!("{} := random {:#04X}", vx, nn),
+            format!("RND {}, {:#04X}", vx, nn),
+        ),
+        0xD000 => pick(
+            format!("sprite {} {} {}", vx, vy, n),
+            format!("DRW {}, {}, {}", vx, vy, n),
+        ),
+        0xE000 => match nn {
+            0x9E => {
+                flow = Flow::Skip;
+                pick(format!("if {} -key then", vx), format!("SKP {}", vx))
+            }
+            0xA1 => {
+                flow = Flow::Skip;
+                pick(format!("if {} key then", vx), format!("SKNP {}", vx))
+            }
+            _ => return None,
+        },
+        _ => match nn {
+            0x00 if mode.xochip() && x == 0 => {
+                let long = word(memory, address as usize + 2)?;
+                len = 4;
+                data = Some(long);
+                pick(
+                    format!("i := long {}", target(long)),
+                    format!("LD I, LONG {}", target(long)),
+                )
+            }
+            0x01 if mode.xochip() => pick(format!("plane {}", x), format!("PLANE {}", x)),
+            0x02 if mode.xochip() && x == 0 => pick("audio".into(), "AUDIO".into()),
+            0x07 => pick(format!("{} := delay", vx), format!("LD {}, DT", vx)),
+            0x0A => pick(format!("{} := key", vx), format!("LD {}, K", vx)),
+            0x15 => pick(format!("delay := {}", vx), format!("LD DT, {}", vx)),
+            0x18 => pick(format!("buzzer := {}", vx), format!("LD ST, {}", vx)),
+            0x1E => pick(format!("i += {}", vx), format!("ADD I, {}", vx)),
+            0x29 => pick(format!("i := hex {}", vx), format!("LD F, {}", vx)),
+            0x30 if mode.superchip() => {
+                pick(format!("i := bighex {}", vx), format!("LD HF, {}", vx))
+            }
+            0x33 => pick(format!("bcd {}", vx), format!("LD B, {}", vx)),
+            0x3A if mode.xochip() => pick(format!("pitch := {}", vx), format!("PITCH {}", vx)),
+            0x55 => pick(format!("save {}", vx), format!("LD [I], {}", vx)),
+            0x65 => pick(format!("load {}", vx), format!("LD {}, [I]", vx)),
+            0x75 if mode.superchip() => pick(format!("saveflags {}", vx), format!("LD R, {}", vx)),
+            0x85 if mode.superchip() => pick(format!("loadflags {}", vx), format!("LD {}, R", vx)),
+            _ => return None,
+        },
+    };
+    return Some(Decoded {
+        text,
+        len,
+        flow,
+        data,
+    });
+}
+
+pub fn disassemble_at(
+    memory: &[u8],
+    address: u16,
+    mode: Mode,
+    syntax: Syntax,
+) -> Option<(String, u16)> {
+    /*  The mnemonic and length in bytes of the instruction at address, for
+        showing a single instruction such as the one about to run
+    */
+    let decoded = decode(memory, address, mode, syntax, &BTreeMap::new())?;
+    return Some((decoded.text, decoded.len));
+}
+
+fn trace(memory: &[u8], mode: Mode) -> (BTreeSet<u16>, BTreeMap<u16, String>) {
+    /*  Follows every path of control flow from the start of the program.
+        Returns where the reachable instructions start, and labels for the
+        addresses jumped to, called and loaded into i
+    */
+    let empty = BTreeMap::new();
+    let mut instructions = BTreeSet::new();
+    let mut labels = BTreeMap::new();
+    let mut data = BTreeSet::new();
+    let mut pending = vec![PROGRAM_START];
+    while let Some(address) = pending.pop() {
+        if (address as usize) < PROGRAM_START as usize || instructions.contains(&address) {
+            continue;
+        }
+        let Some(decoded) = decode(memory, address, mode, Syntax::Octo, &empty) else {
+            continue;
+        };
+        instructions.insert(address);
+        if let Some(target) = decoded.data {
+            data.insert(target);
+        }
+        let next = address.wrapping_add(decoded.len);
+        match decoded.flow {
+            Flow::Next => pending.push(next),
+            Flow::Skip => {
+                // The skipped instruction may be the 4-byte F000 NNNN
+                let skipped = match word(memory, next as usize) {
+                    Some(0xF000) if mode.xochip() => 4,
+                    _ => 2,
+                };
+                pending.push(next);
+                pending.push(next.wrapping_add(skipped));
+            }
+            Flow::Jump(target) => {
+                labels
+                    .entry(target)
+                    .or_insert(format!("label_{:03x}", target));
+                pending.push(target);
+            }
+            Flow::Call(target) => {
+                labels.insert(target, format!("sub_{:03x}", target));
+                pending.push(target);
+                pending.push(next);
+            }
+            Flow::Stop => {}
+        }
+    }
+    labels.insert(PROGRAM_START, "main".to_string());
+    for target in data {
+        if target >= PROGRAM_START && !labels.contains_key(&target) {
+            labels.insert(target, format!("data_{:03x}", target));
+        }
+    }
+    return (instructions, labels);
+}
+
+pub fn disassemble(program: &[u8], mode: Mode, syntax: Syntax) -> String {
+    /*  Listing of a program loaded at 0x200. Bytes that no path of control
+        flow reaches are shown as data
+    */
+    let mut memory = vec![0x0; PROGRAM_START as usize];
+    memory.extend_from_slice(program);
+    let end = memory.len();
+    let (instructions, mut labels) = trace(&memory, mode);
+
+    // Lay out the lines first, as a label inside another line cannot be shown
+    let mut lines = Vec::new();
+    let mut address = PROGRAM_START as usize;
+    while address < end {
+        if instructions.contains(&(address as u16)) {
+            let decoded = decode(&memory, address as u16, mode, syntax, &BTreeMap::new()).unwrap();
+            lines.push((address, decoded.len as usize, true));
+            address += decoded.len as usize;
+        } else {
+            let start = address;
+            address += 1;
+            while address < end
+                && address - start < DATA_PER_LINE
+                && !instructions.contains(&(address as u16))
+                && !labels.contains_key(&(address as u16))
+            {
+                address += 1;
+            }
+            lines.push((start, address - start, false));
+        }
+    }
+    let starts: BTreeSet<u16> = lines.iter().map(|&(start, _, _)| start as u16).collect();
+    labels.retain(|address, _| starts.contains(address));
+
+    let comment = syntax.comment();
+    let mut listing = String::new();
+    for (start, len, code) in lines {
+        if let Some(label) = labels.get(&(start as u16)) {
+            match syntax {
+                Syntax::Octo => listing.push_str(&format!(": {}\n", label)),
+                Syntax::Cowgod => listing.push_str(&format!("{}:\n", label)),
+            }
+        }
+        let bytes = &memory[start..start + len];
+        let text = if code {
+            decode(&memory, start as u16, mode, syntax, &labels)
+                .unwrap()
+                .text
+        } else {
+            let values: Vec<String> = bytes.iter().map(|byte| format!("{:#04X}", byte)).collect();
+            match syntax {
+                Syntax::Octo => values.join(" "),
+                Syntax::Cowgod => format!("DB {}", values.join(", ")),
+            }
+        };
+        let hex: Vec<String> = bytes.iter().map(|byte| format!("{:02X}", byte)).collect();
+        listing.push_str(&format!(
+            "    {:<40} {} {:#05X}: {}\n",
+            text,
+            comment,
+            start,
+            hex.join(" ")
+        ));
+    }
+    return listing;
+}
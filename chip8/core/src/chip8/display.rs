@@ -0,0 +1,235 @@
+const LORES_WIDTH: usize = 64;
+const LORES_HEIGHT: usize = 32;
+const HIRES_WIDTH: usize = 128;
+const HIRES_HEIGHT: usize = 64;
+pub struct Display {
+    // Pixels of the active resolution, stored row by row with width() as stride.
+    // Each pixel holds one bit per bitplane, so XO-CHIP pixels range over 4 colors
+    pub buffer: [u8; HIRES_WIDTH * HIRES_HEIGHT],
+
+    // SUPER-CHIP 128x64 mode
+    pub hires: bool,
+
+    // Bitmask of the XO-CHIP planes drawn, cleared and scrolled. Plain CHIP-8 only uses plane 1
+    pub planes: u8,
+
+    // Set whenever the pixels change, the frontend clears it once it has drawn them
+    pub dirty: bool,
+}
+
+impl Display {
+    pub fn new() -> Display {
+        return Display {
+            buffer: [0x0; HIRES_WIDTH * HIRES_HEIGHT],
+            hires: false,
+            planes: 0b01,
+            dirty: true,
+        };
+    }
+
+    pub fn width(&self) -> usize {
+        if self.hires {
+            HIRES_WIDTH
+        } else {
+            LORES_WIDTH
+        }
+    }
+
+    pub fn height(&self) -> usize {
+        if self.hires {
+            HIRES_HEIGHT
+        } else {
+            LORES_HEIGHT
+        }
+    }
+
+    pub fn clear(&mut self) {
+        /*  Clears the selected planes of the whole screen
+         */
+        for pixel in self.buffer.iter_mut() {
+            *pixel &= !self.planes;
+        }
+        self.dirty = true;
+    }
+
+    pub fn set_hires(&mut self, hires: bool) {
+        /*  Switches between 64x32 and 128x64, clearing every plane
+         */
+        self.hires = hires;
+        self.buffer.fill(0x0);
+        self.dirty = true;
+    }
+
+    pub fn scroll_down(&mut self, n: usize) {
+        self.scroll(0, n as isize);
+    }
+
+    pub fn scroll_up(&mut self, n: usize) {
+        self.scroll(0, -(n as isize));
+    }
+
+    pub fn scroll_right(&mut self, n: usize) {
+        self.scroll(n as isize, 0);
+    }
+
+    pub fn scroll_left(&mut self, n: usize) {
+        self.scroll(-(n as isize), 0);
+    }
+
+    fn scroll(&mut self, dx: isize, dy: isize) {
+        /*  Moves the selected planes by (dx, dy) pixels, blanking whatever
+            scrolls in from the edges
+        */
+        let (width, height) = (self.width() as isize, self.height() as isize);
+        let source = self.buffer;
+        self.dirty = true;
+        for y in 0..height {
+            for x in 0..width {
+                let (from_x, from_y) = (x - dx, y - dy);
+                let moved = if from_x >= 0 && from_x < width && from_y >= 0 && from_y < height {
+                    source[(from_y * width + from_x) as usize] & self.planes
+                } else {
+                    0x0
+                };
+                let index = (y * width + x) as usize;
+                self.buffer[index] = (self.buffer[index] & !self.planes) | moved;
+            }
+        }
+    }
+
+    pub fn render(&mut self, vx: u8, vy: u8, sprite: &[u8], wide: bool, clip: bool) -> bool {
+        /*  XORs the sprite rows onto the screen at (vx, vy), returns true
+            if any pixel was erased. Rows are 8 pixels, or 16 pixels over two
+            bytes when wide is set. With several planes selected the sprite
+            holds the data for each plane one after the other. Pixels running
+            off the edge are dropped when clip is set, otherwise they wrap
+            around to the opposite side
+        */
+        let (width, height) = (self.width(), self.height());
+        let coord_x = vx as usize % width;
+        let coord_y = vy as usize % height;
+        let row_bytes = if wide { 2 } else { 1 };
+        let selected: Vec<u8> = (0..8)
+            .map(|plane| 1 << plane)
+            .filter(|plane| self.planes & plane != 0)
+            .collect();
+        let mut collision = false;
+        if selected.is_empty() || sprite.is_empty() {
+            return collision;
+        }
+        self.dirty = true;
+        let plane_bytes = sprite.len() / selected.len();
+        for (&plane, data) in selected.iter().zip(sprite.chunks(plane_bytes)) {
+            for (row, bytes) in data.chunks(row_bytes).enumerate() {
+                for col in 0..8 * bytes.len() {
+                    if clip && (coord_x + col >= width || coord_y + row >= height) {
+                        continue;
+                    }
+                    let byte = (bytes[col / 8] >> (7 - col % 8)) & 0x1;
+                    let index = (coord_x + col) % width + ((coord_y + row) % height) * width;
+                    if byte == 1 {
+                        if self.buffer[index] & plane != 0 {
+                            collision = true;
+                        }
+                        self.buffer[index] ^= plane;
+                    }
+                }
+            }
+        }
+        return collision;
+    }
+}
+
+impl Default for Display {
+    fn default() -> Display {
+        return Display::new();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lit(display: &Display) -> Vec<(usize, usize)> {
+        /*  The lit pixels as (x, y), row by row
+         */
+        let width = display.width();
+        return (0..width * display.height())
+            .filter(|&index| display.buffer[index] != 0)
+            .map(|index| (index % width, index / width))
+            .collect();
+    }
+
+    fn with_pixels(hires: bool, pixels: &[(usize, usize)]) -> Display {
+        let mut display = Display::new();
+        display.set_hires(hires);
+        let width = display.width();
+        for &(x, y) in pixels {
+            display.buffer[y * width + x] = 0b01;
+        }
+        return display;
+    }
+
+    #[test]
+    fn hires_doubles_the_resolution_and_clears() {
+        let mut display = with_pixels(false, &[(3, 3)]);
+        assert_eq!((display.width(), display.height()), (64, 32));
+        display.set_hires(true);
+        assert_eq!((display.width(), display.height()), (128, 64));
+        assert!(lit(&display).is_empty());
+    }
+
+    #[test]
+    fn scrolling_moves_pixels_and_drops_them_off_the_edge() {
+        let mut display = with_pixels(false, &[(0, 0), (10, 5), (63, 31)]);
+        display.scroll_down(2);
+        assert_eq!(lit(&display), [(0, 2), (10, 7)]);
+        display.scroll_up(3);
+        assert_eq!(lit(&display), [(10, 4)]);
+        display.scroll_right(4);
+        assert_eq!(lit(&display), [(14, 4)]);
+        display.scroll_left(15);
+        assert!(lit(&display).is_empty());
+    }
+
+    #[test]
+    fn scrolling_in_hires_uses_the_hires_width() {
+        let mut display = with_pixels(true, &[(100, 60)]);
+        display.scroll_right(4);
+        display.scroll_down(3);
+        assert_eq!(lit(&display), [(104, 63)]);
+    }
+
+    #[test]
+    fn scrolling_only_moves_the_selected_planes() {
+        let mut display = Display::new();
+        display.buffer[0] = 0b11;
+        display.planes = 0b10;
+        display.scroll_right(4);
+        assert_eq!(display.buffer[0], 0b01);
+        assert_eq!(display.buffer[4], 0b10);
+    }
+
+    #[test]
+    fn wide_sprites_are_16_by_16() {
+        let mut display = Display::new();
+        display.set_hires(true);
+        let mut sprite = [0x0; 32];
+        sprite[0] = 0x80;
+        sprite[1] = 0x01;
+        sprite[31] = 0x01;
+        assert!(!display.render(8, 4, &sprite, true, true));
+        assert_eq!(lit(&display), [(8, 4), (23, 4), (23, 19)]);
+        assert!(display.render(8, 4, &sprite, true, true));
+        assert!(lit(&display).is_empty());
+    }
+
+    #[test]
+    fn wide_sprites_clip_at_the_bottom_right() {
+        let mut display = Display::new();
+        display.set_hires(true);
+        let sprite = [0xFF; 32];
+        display.render(120, 60, &sprite, true, true);
+        assert_eq!(lit(&display).len(), 8 * 4);
+    }
+}
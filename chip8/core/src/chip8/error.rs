@@ -0,0 +1,181 @@
+use std::error::Error;
+use std::fmt;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StepOutcome {
+    /* What happened on a step of the machine that did not fail
+     */
+    // An instruction ran
+    Executed,
+
+    // Nothing ran, the display wait quirk is holding the cpu until the next timer tick
+    WaitingForVblank,
+
+    // FX0A is holding the cpu until a key is pressed and released
+    WaitingForKey,
+
+    // The program has ended through 00FD
+    Exited,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Chip8Error {
+    /* Faults raised by a program, each with the address of the instruction
+       that caused it, or by a program that cannot be loaded at all
+    */
+    InvalidOpcode { address: u16, opcode: u16 },
+    StackOverflow { address: u16 },
+    StackUnderflow { address: u16 },
+
+    // target is the first address the instruction tried to reach outside of memory
+    MemoryOutOfBounds { address: u16, target: usize },
+
+    // The program counter points outside of memory, so no instruction can be fetched
+    PcOutOfRange { address: u16 },
+
+    // The program is longer than the memory from 0x200 up, capacity is how many bytes fit
+    ProgramTooLarge { len: usize, capacity: usize },
+}
+
+impl fmt::Display for Chip8Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Chip8Error::InvalidOpcode { address, opcode } => {
+                write!(
+                    f,
+                    "unrecognized instruction {:#06X} at {:#05X}",
+                    opcode, address
+                )
+            }
+            Chip8Error::StackOverflow { address } => {
+                write!(f, "stack overflow at {:#05X}", address)
+            }
+            Chip8Error::StackUnderflow { address } => {
+                write!(f, "stack underflow at {:#05X}", address)
+            }
+            Chip8Error::MemoryOutOfBounds { address, target } => write!(
+                f,
+                "memory access to {:#05X} out of bounds at {:#05X}",
+                target, address
+            ),
+            Chip8Error::PcOutOfRange { address } => {
+                write!(f, "program counter out of range at {:#05X}", address)
+            }
+            Chip8Error::ProgramTooLarge { len, capacity } => write!(
+                f,
+                "program of {} bytes does not fit in the {} bytes of memory",
+                len, capacity
+            ),
+        }
+    }
+}
+
+impl Error for Chip8Error {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chip8::{Chip8, Mode, Quirks};
+
+    fn run(program: &[u8], steps: usize) -> (Chip8, Result<StepOutcome, Chip8Error>) {
+        /*  Runs the program until it faults or has taken the given steps
+         */
+        let mut c8 = Chip8::new(Mode::Chip8, Quirks::modern());
+        c8.load_program(program).unwrap();
+        let mut outcome = Ok(StepOutcome::Executed);
+        for _ in 0..steps {
+            outcome = c8.emulate_cycle();
+            if outcome.is_err() {
+                break;
+            }
+        }
+        return (c8, outcome);
+    }
+
+    #[test]
+    fn invalid_opcode() {
+        let (c8, outcome) = run(&[0x60, 0x01, 0x80, 0x1F], 2);
+        let error = Chip8Error::InvalidOpcode {
+            address: 0x202,
+            opcode: 0x801F,
+        };
+        assert_eq!(outcome, Err(error));
+        assert_eq!(c8.cpu.program_counter, 0x202);
+        assert_eq!(
+            error.to_string(),
+            "unrecognized instruction 0x801F at 0x202"
+        );
+    }
+
+    #[test]
+    fn stack_overflow() {
+        // A subroutine that calls itself
+        let (c8, outcome) = run(&[0x22, 0x00], 100);
+        assert_eq!(outcome, Err(Chip8Error::StackOverflow { address: 0x200 }));
+        assert_eq!(c8.cpu.stack_pointer as usize, c8.memory.stack.len() - 1);
+    }
+
+    #[test]
+    fn stack_underflow() {
+        let (_, outcome) = run(&[0x00, 0xE0, 0x00, 0xEE], 2);
+        assert_eq!(outcome, Err(Chip8Error::StackUnderflow { address: 0x202 }));
+    }
+
+    #[test]
+    fn memory_out_of_bounds() {
+        // i := 0xFFE, then the opcode reaching past the end of the 4K memory,
+        // which is the first address out of bounds
+        for (opcode, target) in [
+            ([0xF0, 0x33], 0x1000),
+            ([0xF2, 0x55], 0x1000),
+            ([0xF2, 0x65], 0x1000),
+            ([0xD0, 0x03], 0x1000),
+        ] {
+            let (c8, outcome) = run(&[0xAF, 0xFE, opcode[0], opcode[1]], 2);
+            assert_eq!(
+                outcome,
+                Err(Chip8Error::MemoryOutOfBounds {
+                    address: 0x202,
+                    target
+                }),
+                "{:02X}{:02X}",
+                opcode[0],
+                opcode[1]
+            );
+            assert_eq!(c8.cpu.i, 0xFFE);
+            assert_eq!(c8.last_write, None);
+        }
+
+        // Starting past the end reports where it started
+        let (_, outcome) = run(&[0x60, 0xFF, 0xAF, 0xFF, 0xF0, 0x1E, 0xF0, 0x33], 4);
+        assert_eq!(
+            outcome,
+            Err(Chip8Error::MemoryOutOfBounds {
+                address: 0x206,
+                target: 0x10FE
+            })
+        );
+    }
+
+    #[test]
+    fn pc_out_of_range() {
+        let (c8, outcome) = run(&[0x1F, 0xFF], 2);
+        assert_eq!(outcome, Err(Chip8Error::PcOutOfRange { address: 0xFFF }));
+        assert_eq!(c8.cpu.program_counter, 0xFFF);
+    }
+
+    #[test]
+    fn program_too_large() {
+        let mut c8 = Chip8::new(Mode::Chip8, Quirks::modern());
+        let hash = c8.rom_hash;
+        assert_eq!(
+            c8.load_program(&[0x0; 4096 - 0x200 + 1]),
+            Err(Chip8Error::ProgramTooLarge {
+                len: 3585,
+                capacity: 3584
+            })
+        );
+        assert_eq!(c8.rom_hash, hash);
+        assert_eq!(c8.load_program(&[0x0; 4096 - 0x200]), Ok(()));
+    }
+}
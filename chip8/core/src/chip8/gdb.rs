@@ -0,0 +1,531 @@
+use std::io::{self, ErrorKind, Read, Write};
+use std::net::{TcpListener, TcpStream};
+
+use super::{Chip8, Chip8Error, Debugger, Stop};
+
+/*  Registers as numbered by the stub, in the order of the g packet:
+        0-15    V0-VF, 8 bits
+        16      I, 16 bits
+        17      PC, 16 bits
+        18      SP, 8 bits
+        19      DT, 8 bits
+        20      ST, 8 bits
+    Multi-byte registers are sent little endian
+*/
+const REGISTER_COUNT: usize = 21;
+
+const TARGET_XML: &str = r#"<?xml version="1.0"?>
+<!DOCTYPE target SYSTEM "gdb-target.dtd">
+<target version="1.0">
+  <feature name="org.rusty-retro.chip8">
+    <reg name="v0" bitsize="8" regnum="0"/>
+    <reg name="v1" bitsize="8"/>
+    <reg name="v2" bitsize="8"/>
+    <reg name="v3" bitsize="8"/>
+    <reg name="v4" bitsize="8"/>
+    <reg name="v5" bitsize="8"/>
+    <reg name="v6" bitsize="8"/>
+    <reg name="v7" bitsize="8"/>
+    <reg name="v8" bitsize="8"/>
+    <reg name="v9" bitsize="8"/>
+    <reg name="va" bitsize="8"/>
+    <reg name="vb" bitsize="8"/>
+    <reg name="vc" bitsize="8"/>
+    <reg name="vd" bitsize="8"/>
+    <reg name="ve" bitsize="8"/>
+    <reg name="vf" bitsize="8"/>
+    <reg name="i" bitsize="16" type="data_ptr"/>
+    <reg name="pc" bitsize="16" type="code_ptr"/>
+    <reg name="sp" bitsize="8"/>
+    <reg name="dt" bitsize="8"/>
+    <reg name="st" bitsize="8"/>
+  </feature>
+</target>
+"#;
+
+pub struct GdbStub {
+    /* GDB remote serial protocol server on a local TCP port, serving one
+       client at a time. Polled from the frontend loop so it never blocks
+       the emulation
+    */
+    listener: TcpListener,
+    client: Option<TcpStream>,
+
+    // Bytes received that do not make up a whole packet yet
+    received: Vec<u8>,
+
+    // The client continued or stepped and is waiting for a stop reply
+    running: bool,
+}
+
+fn hex(bytes: &[u8]) -> String {
+    return bytes.iter().map(|byte| format!("{:02x}", byte)).collect();
+}
+
+fn unhex(text: &str) -> Option<Vec<u8>> {
+    if !text.len().is_multiple_of(2) {
+        return None;
+    }
+    return (0..text.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(text.get(i..i + 2)?, 16).ok())
+        .collect();
+}
+
+fn register_bytes(c8: &Chip8, register: usize) -> Vec<u8> {
+    match register {
+        0..=15 => vec![c8.cpu.registers[register]],
+        16 => c8.cpu.i.to_le_bytes().to_vec(),
+        17 => c8.cpu.program_counter.to_le_bytes().to_vec(),
+        18 => vec![c8.cpu.stack_pointer],
+        19 => vec![c8.cpu.delay_timer],
+        _ => vec![c8.cpu.sound_timer],
+    }
+}
+
+fn set_register(c8: &mut Chip8, register: usize, bytes: &[u8]) -> Option<usize> {
+    /*  Writes a register from the front of bytes, returning how many bytes it took
+     */
+    let word = || Some(u16::from_le_bytes([*bytes.first()?, *bytes.get(1)?]));
+    match register {
+        0..=15 => c8.cpu.registers[register] = *bytes.first()?,
+        16 => c8.cpu.i = word()?,
+        17 => c8.cpu.program_counter = word()?,
+        18 => c8.cpu.stack_pointer = *bytes.first()?,
+        19 => c8.cpu.delay_timer = *bytes.first()?,
+        20 => c8.cpu.sound_timer = *bytes.first()?,
+        _ => return None,
+    }
+    return Some(if register == 16 || register == 17 {
+        2
+    } else {
+        1
+    });
+}
+
+fn stop_reply(stop: &Option<Stop>) -> String {
+    match stop {
+        Some(Stop::Watchpoint { write, .. }) => format!("T05watch:{:x};", write.start),
+        Some(Stop::Breakpoint(_)) => "T05swbreak:;".to_string(),
+        _ => "S05".to_string(),
+    }
+}
+
+impl GdbStub {
+    pub fn bind(port: u16) -> io::Result<GdbStub> {
+        let listener = TcpListener::bind(("127.0.0.1", port))?;
+        listener.set_nonblocking(true)?;
+        return Ok(GdbStub {
+            listener,
+            client: None,
+            received: Vec::new(),
+            running: false,
+        });
+    }
+
+    fn send(&mut self, packet: &str) {
+        /*  Frames the packet with its checksum. A client that cannot be written to is dropped
+         */
+        let checksum = packet.bytes().fold(0u8, |sum, byte| sum.wrapping_add(byte));
+        let framed = format!("${}#{:02x}", packet, checksum);
+        if let Some(client) = &mut self.client {
+            let sent = client
+                .set_nonblocking(false)
+                .and_then(|_| client.write_all(framed.as_bytes()))
+                .and_then(|_| client.set_nonblocking(true));
+            if sent.is_err() {
+                self.client = None;
+            }
+        }
+    }
+
+    pub fn poll(
+        &mut self,
+        c8: &mut Chip8,
+        debugger: &mut Debugger,
+        halted: bool,
+    ) -> Result<(), Chip8Error> {
+        /*  Accepts a client, answers its packets and reports when the machine
+            stops after a continue
+        */
+        if self.client.is_none() {
+            if let Ok((client, _)) = self.listener.accept() {
+                if client.set_nonblocking(true).is_ok() {
+                    self.client = Some(client);
+                    self.received.clear();
+                    self.running = false;
+                    debugger.pause();
+                }
+            }
+        }
+        let Some(client) = &mut self.client else {
+            return Ok(());
+        };
+
+        let mut chunk = [0; 1024];
+        loop {
+            match client.read(&mut chunk) {
+                Ok(0) => {
+                    self.disconnect(c8, debugger);
+                    return Ok(());
+                }
+                Ok(count) => self.received.extend_from_slice(&chunk[..count]),
+                Err(error) if error.kind() == ErrorKind::WouldBlock => break,
+                Err(_) => {
+                    self.disconnect(c8, debugger);
+                    return Ok(());
+                }
+            }
+        }
+
+        while let Some(packet) = self.next_packet() {
+            self.handle(&packet, c8, debugger)?;
+        }
+        if self.client.is_none() {
+            // Dropped while answering, e.g. a reply could not be written
+            self.disconnect(c8, debugger);
+            return Ok(());
+        }
+
+        if self.running && (debugger.stopped.is_some() || halted) {
+            self.running = false;
+            let reply = if halted {
+                "S04".to_string()
+            } else {
+                stop_reply(&debugger.stopped)
+            };
+            self.send(&reply);
+        }
+        return Ok(());
+    }
+
+    fn disconnect(&mut self, c8: &Chip8, debugger: &mut Debugger) {
+        /*  Drops the client and lets the machine run again, nothing else
+            could resume it without the debugger's keys
+        */
+        self.client = None;
+        self.running = false;
+        debugger.resume(c8);
+    }
+
+    fn next_packet(&mut self) -> Option<String> {
+        /*  Takes the next whole packet off the received bytes, acknowledging
+            it. A ^C interrupt is returned as the packet "\x03"
+        */
+        loop {
+            let start = self
+                .received
+                .iter()
+                .position(|&byte| byte == b'$' || byte == 0x03)?;
+            if self.received[start] == 0x03 {
+                self.received.drain(..=start);
+                return Some("\x03".to_string());
+            }
+            let end = self.received[start..]
+                .iter()
+                .position(|&byte| byte == b'#')?
+                + start;
+            if self.received.len() < end + 3 {
+                return None;
+            }
+            let body = String::from_utf8_lossy(&self.received[start + 1..end]).to_string();
+            let checksum = std::str::from_utf8(&self.received[end + 1..end + 3])
+                .ok()
+                .and_then(|text| u8::from_str_radix(text, 16).ok());
+            self.received.drain(..end + 3);
+            let sum = body.bytes().fold(0u8, |sum, byte| sum.wrapping_add(byte));
+            if let Some(client) = &mut self.client {
+                let ack: &[u8] = if checksum == Some(sum) { b"+" } else { b"-" };
+                let _ = client.write_all(ack);
+            }
+            if checksum == Some(sum) {
+                return Some(body);
+            }
+        }
+    }
+
+    fn handle(
+        &mut self,
+        packet: &str,
+        c8: &mut Chip8,
+        debugger: &mut Debugger,
+    ) -> Result<(), Chip8Error> {
+        let memory_size = c8.memory.primary_memory.len();
+        let (command, args) = packet.split_at(packet.len().min(1));
+        let reply = match command {
+            "\x03" => {
+                debugger.pause();
+                return Ok(());
+            }
+            "?" => stop_reply(&debugger.stopped),
+            "g" => (0..REGISTER_COUNT)
+                .map(|register| hex(&register_bytes(c8, register)))
+                .collect(),
+            "G" => match unhex(args) {
+                Some(bytes) => {
+                    let mut offset = 0;
+                    for register in 0..REGISTER_COUNT {
+                        offset += set_register(c8, register, &bytes[offset.min(bytes.len())..])
+                            .unwrap_or(0);
+                    }
+                    "OK".to_string()
+                }
+                None => "E01".to_string(),
+            },
+            "p" => match usize::from_str_radix(args, 16) {
+                Ok(register) if register < REGISTER_COUNT => hex(&register_bytes(c8, register)),
+                _ => "E01".to_string(),
+            },
+            "P" => {
+                let written = args.split_once('=').and_then(|(register, value)| {
+                    let register = usize::from_str_radix(register, 16).ok()?;
+                    set_register(c8, register, &unhex(value)?)
+                });
+                if written.is_some() { "OK" } else { "E01" }.to_string()
+            }
+            "m" | "M" => {
+                let (range, data) = args.split_once(':').unwrap_or((args, ""));
+                let range = range.split_once(',').and_then(|(address, len)| {
+                    let address = usize::from_str_radix(address, 16).ok()?;
+                    let len = usize::from_str_radix(len, 16).ok()?;
+                    let end = address.checked_add(len)?;
+                    (end <= memory_size).then_some(address..end)
+                });
+                match (command, range, unhex(data)) {
+                    ("m", Some(range), _) => hex(&c8.memory.primary_memory[range]),
+                    ("M", Some(range), Some(bytes)) if bytes.len() == range.len() => {
+                        c8.memory.primary_memory[range].copy_from_slice(&bytes);
+                        "OK".to_string()
+                    }
+                    _ => "E01".to_string(),
+                }
+            }
+            "c" => {
+                debugger.resume(c8);
+                self.running = true;
+                return Ok(());
+            }
+            "s" => {
+                let stepped = debugger.step(c8);
+                self.send(if stepped.is_ok() { "S05" } else { "S04" });
+                return stepped.map(|_| ());
+            }
+            "Z" | "z" => {
+                // Z0 software breakpoints, Z2 write watchpoints
+                let fields: Vec<&str> = args.split(',').collect();
+                let address = fields
+                    .get(1)
+                    .and_then(|a| usize::from_str_radix(a, 16).ok());
+                let end = fields
+                    .get(2)
+                    .and_then(|l| usize::from_str_radix(l, 16).ok())
+                    .zip(address)
+                    .and_then(|(len, address)| address.checked_add(len));
+                match (fields[0], address, end) {
+                    ("0", Some(address), _) => match u16::try_from(address) {
+                        Ok(address) => {
+                            if command == "Z" {
+                                debugger.breakpoints.insert(address);
+                            } else {
+                                debugger.breakpoints.remove(&address);
+                            }
+                            "OK".to_string()
+                        }
+                        // Past the 16-bit program counter, no instruction can be there
+                        Err(_) => "E01".to_string(),
+                    },
+                    ("2", Some(address), Some(end)) => {
+                        let range = address..end;
+                        if command == "Z" {
+                            debugger.watchpoints.push(range);
+                        } else {
+                            debugger.watchpoints.retain(|watched| *watched != range);
+                        }
+                        "OK".to_string()
+                    }
+                    ("2", Some(_), None) => "E01".to_string(),
+                    _ => String::new(),
+                }
+            }
+            "D" => {
+                self.send("OK");
+                self.disconnect(c8, debugger);
+                return Ok(());
+            }
+            "k" => {
+                self.disconnect(c8, debugger);
+                return Ok(());
+            }
+            "H" => "OK".to_string(),
+            "q" if packet.starts_with("qSupported") => {
+                "PacketSize=1000;qXfer:features:read+;swbreak+".to_string()
+            }
+            "q" if packet == "qAttached" => "1".to_string(),
+            "q" if packet.starts_with("qXfer:features:read:target.xml:") => {
+                let window = packet.rsplit(':').next().unwrap_or("");
+                let window = window.split_once(',').and_then(|(offset, len)| {
+                    let offset = usize::from_str_radix(offset, 16).ok()?;
+                    let len = usize::from_str_radix(len, 16).ok()?;
+                    Some((offset, offset.checked_add(len)?))
+                });
+                match window {
+                    Some((offset, end)) => {
+                        let start = offset.min(TARGET_XML.len());
+                        let end = end.min(TARGET_XML.len());
+                        let more = if end < TARGET_XML.len() { 'm' } else { 'l' };
+                        format!("{}{}", more, &TARGET_XML[start..end])
+                    }
+                    None => "E01".to_string(),
+                }
+            }
+            _ => String::new(),
+        };
+        self.send(&reply);
+        return Ok(());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chip8::{Mode, Quirks};
+    use std::time::Duration;
+
+    fn framed(packet: &str) -> String {
+        let checksum = packet.bytes().fold(0u8, |sum, byte| sum.wrapping_add(byte));
+        return format!("${}#{:02x}", packet, checksum);
+    }
+
+    struct Session {
+        stub: GdbStub,
+        client: TcpStream,
+        c8: Chip8,
+        debugger: Debugger,
+    }
+
+    impl Session {
+        fn connect() -> Session {
+            /*  A stub on a free port with a client attached and the handshake done
+             */
+            let mut stub = GdbStub::bind(0).unwrap();
+            let port = stub.listener.local_addr().unwrap().port();
+            let client = TcpStream::connect(("127.0.0.1", port)).unwrap();
+            client
+                .set_read_timeout(Some(Duration::from_secs(5)))
+                .unwrap();
+            let mut c8 = Chip8::new(Mode::Chip8, Quirks::modern());
+            let mut debugger = Debugger::new();
+            while stub.client.is_none() {
+                stub.poll(&mut c8, &mut debugger, false).unwrap();
+            }
+            return Session {
+                stub,
+                client,
+                c8,
+                debugger,
+            };
+        }
+
+        fn request(&mut self, packet: &str) -> String {
+            /*  Sends a packet and returns the body of the reply, after checking
+                the acknowledgement and the reply's checksum
+            */
+            self.client.write_all(framed(packet).as_bytes()).unwrap();
+            let mut received = Vec::new();
+            loop {
+                self.stub
+                    .poll(&mut self.c8, &mut self.debugger, false)
+                    .unwrap();
+                let mut byte = [0];
+                self.client.read_exact(&mut byte).unwrap();
+                received.push(byte[0]);
+                if received.len() >= 3 && received[received.len() - 3] == b'#' {
+                    break;
+                }
+            }
+            let text = String::from_utf8(received).unwrap();
+            let body = text
+                .strip_prefix("+$")
+                .unwrap_or_else(|| panic!("no ack before {}", text));
+            let (body, checksum) = body.split_at(body.len() - 3);
+            assert_eq!(framed(body)[body.len() + 1..], *checksum);
+            return body.to_string();
+        }
+    }
+
+    #[test]
+    fn packets_are_unframed_and_checked() {
+        let mut stub = GdbStub::bind(0).unwrap();
+        stub.received = format!("+{}$g#00{}\x03", framed("m200,2"), framed("?")).into_bytes();
+        assert_eq!(stub.next_packet().as_deref(), Some("m200,2"));
+        // The corrupted g is dropped, the client resends it after the nak
+        assert_eq!(stub.next_packet().as_deref(), Some("?"));
+        assert_eq!(stub.next_packet().as_deref(), Some("\x03"));
+        assert_eq!(stub.next_packet(), None);
+
+        stub.received = b"$m200,2#1".to_vec();
+        assert_eq!(stub.next_packet(), None);
+        stub.received.push(b'5');
+        assert_eq!(stub.next_packet(), None);
+        stub.received = framed("m200,2").into_bytes();
+        assert_eq!(stub.next_packet().as_deref(), Some("m200,2"));
+    }
+
+    #[test]
+    fn g_sends_every_register_little_endian() {
+        let mut session = Session::connect();
+        session.c8.cpu.registers[0] = 0x12;
+        session.c8.cpu.registers[15] = 0xAB;
+        session.c8.cpu.i = 0x0345;
+        session.c8.cpu.program_counter = 0x0210;
+        let zeros = "00".repeat(14);
+        assert_eq!(session.request("g"), format!("12{}ab45031002ff0000", zeros));
+    }
+
+    #[test]
+    fn m_reads_and_upper_m_writes_memory() {
+        let mut session = Session::connect();
+        assert_eq!(session.request("M300,3:a1b2c3"), "OK");
+        assert_eq!(
+            session.c8.memory.primary_memory[0x300..0x303],
+            [0xA1, 0xB2, 0xC3]
+        );
+        assert_eq!(session.request("m2ff,5"), "00a1b2c300");
+
+        assert_eq!(session.request("mfff,2"), "E01");
+        assert_eq!(session.request("mffffffffffffffff,2"), "E01");
+        assert_eq!(session.request("M300,3:a1b2"), "E01");
+    }
+
+    #[test]
+    fn z0_sets_and_clears_breakpoints_within_16_bits() {
+        let mut session = Session::connect();
+        assert_eq!(session.request("Z0,204,2"), "OK");
+        assert!(session.debugger.breakpoints.contains(&0x204));
+        assert_eq!(session.request("z0,204,2"), "OK");
+        assert!(session.debugger.breakpoints.is_empty());
+
+        assert_eq!(session.request("Z0,10200,2"), "E01");
+        assert!(session.debugger.breakpoints.is_empty());
+        assert_eq!(session.request("Z1,204,2"), "");
+    }
+
+    #[test]
+    fn a_client_going_away_resumes_the_machine() {
+        let mut session = Session::connect();
+        assert!(session.debugger.stopped.is_some());
+        drop(session.client);
+        for _ in 0..100 {
+            session
+                .stub
+                .poll(&mut session.c8, &mut session.debugger, false)
+                .unwrap();
+            if session.stub.client.is_none() {
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        assert!(session.stub.client.is_none());
+        assert!(session.debugger.stopped.is_none());
+    }
+}
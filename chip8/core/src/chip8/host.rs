@@ -0,0 +1,24 @@
+use super::display::Display;
+
+/*  What a host provides to run the machine. The speaker's SampleSink is the
+    audio output, these are the video output and the input
+*/
+
+pub trait VideoOutput {
+    /*  Somewhere the emulated screen is shown, e.g. a window, a terminal or an image
+     */
+    fn present(&mut self, display: &Display);
+}
+
+pub trait InputSource {
+    /*  Where the hex keypad is read from, e.g. a keyboard, a gamepad or a script.
+        Returns the keys held down, bit n set while hex key n is
+    */
+    fn poll_keys(&mut self) -> u16;
+}
+
+pub struct NullVideo;
+
+impl VideoOutput for NullVideo {
+    fn present(&mut self, _display: &Display) {}
+}
@@ -0,0 +1,139 @@
+pub struct Keyboard {
+    // Bit n is set while hex key n is held down
+    pub pressed: u16,
+
+    // FX0A is waiting for a key to be pressed and released
+    pub waiting: bool,
+
+    // Keys pressed since FX0A started waiting
+    pub(super) armed: u16,
+
+    // The first armed key to be released, which completes the wait
+    pub(super) released: Option<u8>,
+}
+
+impl Keyboard {
+    pub fn new() -> Keyboard {
+        return Keyboard {
+            pressed: 0x0,
+            waiting: false,
+            armed: 0x0,
+            released: None,
+        };
+    }
+
+    pub fn is_key_pressed(&self, key_code: u8) -> bool {
+        return key_code < 16 && self.pressed & (1 << key_code) != 0;
+    }
+
+    pub fn set_pressed(&mut self, pressed: u16) {
+        /*  Updates the held keys from the bitset of every key currently down
+         */
+        for key_code in 0..16 {
+            let bit = 1 << key_code;
+            let was_down = self.pressed & bit != 0;
+            let is_down = pressed & bit != 0;
+            if self.waiting && is_down && !was_down {
+                self.armed |= bit;
+            }
+            if self.waiting && was_down && !is_down && self.armed & bit != 0 {
+                self.released.get_or_insert(key_code);
+            }
+        }
+        self.pressed = pressed;
+    }
+
+    pub fn wait_for_key(&mut self) -> Option<u8> {
+        /*  Polled by FX0A. Returns the key once one has been pressed and
+            released after the wait started, None while still waiting
+        */
+        if !self.waiting {
+            self.waiting = true;
+            self.armed = 0x0;
+            self.released = None;
+        }
+        let released = self.released.take();
+        if released.is_some() {
+            self.waiting = false;
+        }
+        return released;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::chip8::{Chip8, Mode, Quirks, StepOutcome};
+
+    fn waiting_machine(held: u16) -> Chip8 {
+        /*  A machine about to run FX0A into V3, with keys already held
+         */
+        let mut c8 = Chip8::new(Mode::Chip8, Quirks::modern());
+        c8.load_program(&[0xF3, 0x0A]).unwrap();
+        c8.keyboard.set_pressed(held);
+        return c8;
+    }
+
+    #[test]
+    fn press_then_release_completes_the_wait() {
+        let mut c8 = waiting_machine(0x0);
+        assert_eq!(c8.emulate_cycle(), Ok(StepOutcome::WaitingForKey));
+        c8.keyboard.set_pressed(1 << 0x7);
+        assert_eq!(c8.emulate_cycle(), Ok(StepOutcome::WaitingForKey));
+        assert_eq!(c8.cpu.program_counter, 0x200);
+
+        c8.keyboard.set_pressed(0x0);
+        assert_eq!(c8.emulate_cycle(), Ok(StepOutcome::Executed));
+        assert_eq!(c8.cpu.registers[3], 0x7);
+        assert_eq!(c8.cpu.program_counter, 0x202);
+        assert!(!c8.keyboard.waiting);
+    }
+
+    #[test]
+    fn a_key_held_before_the_wait_does_not_count() {
+        let mut c8 = waiting_machine(1 << 0x2);
+        assert_eq!(c8.emulate_cycle(), Ok(StepOutcome::WaitingForKey));
+        c8.keyboard.set_pressed(0x0);
+        assert_eq!(c8.emulate_cycle(), Ok(StepOutcome::WaitingForKey));
+
+        c8.keyboard.set_pressed(1 << 0x2);
+        assert_eq!(c8.emulate_cycle(), Ok(StepOutcome::WaitingForKey));
+        c8.keyboard.set_pressed(0x0);
+        assert_eq!(c8.emulate_cycle(), Ok(StepOutcome::Executed));
+        assert_eq!(c8.cpu.registers[3], 0x2);
+    }
+
+    #[test]
+    fn the_first_key_released_wins() {
+        let mut c8 = waiting_machine(0x0);
+        c8.emulate_cycle().unwrap();
+        c8.keyboard.set_pressed(1 << 0x4 | 1 << 0x9);
+        c8.keyboard.set_pressed(1 << 0x4);
+        c8.keyboard.set_pressed(0x0);
+        assert_eq!(c8.emulate_cycle(), Ok(StepOutcome::Executed));
+        assert_eq!(c8.cpu.registers[3], 0x9);
+    }
+
+    #[test]
+    fn skips_see_every_key_held() {
+        let mut c8 = Chip8::new(Mode::Chip8, Quirks::modern());
+        c8.keyboard.set_pressed(1 << 0x1 | 1 << 0xA);
+        c8.cpu.registers[0] = 0x1;
+        c8.cpu.registers[1] = 0xA;
+        c8.cpu.registers[2] = 0x5;
+        let skips = |c8: &mut Chip8, opcode| {
+            c8.cpu.program_counter = 0x200;
+            c8.execute(opcode).unwrap();
+            c8.cpu.program_counter == 0x204
+        };
+        assert!(skips(&mut c8, 0xE09E));
+        assert!(skips(&mut c8, 0xE19E));
+        assert!(!skips(&mut c8, 0xE29E));
+        assert!(!skips(&mut c8, 0xE0A1));
+        assert!(!skips(&mut c8, 0xE1A1));
+        assert!(skips(&mut c8, 0xE2A1));
+
+        // Values past 0xF name no key, so are never held
+        c8.cpu.registers[2] = 0x11;
+        assert!(!skips(&mut c8, 0xE29E));
+    }
+}
@@ -0,0 +1,130 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+
+// The usual layout, the left hand side of a QWERTY keyboard standing in for the hex keypad
+//      1 2 3 C         1 2 3 4
+//      4 5 6 D   <-    Q W E R
+//      7 8 9 E         A S D F
+//      A 0 B F         Z X C V
+const DEFAULT_LAYOUT: [(&str, u8); 16] = [
+    ("1", 0x1),
+    ("2", 0x2),
+    ("3", 0x3),
+    ("4", 0xC),
+    ("Q", 0x4),
+    ("W", 0x5),
+    ("E", 0x6),
+    ("R", 0xD),
+    ("A", 0x7),
+    ("S", 0x8),
+    ("D", 0x9),
+    ("F", 0xE),
+    ("Z", 0xA),
+    ("X", 0x0),
+    ("C", 0xB),
+    ("V", 0xF),
+];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct InvalidKey {
+    /* A key code outside the 16 keys of the hex keypad
+     */
+    pub key_code: u8,
+}
+
+impl fmt::Display for InvalidKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:#X} is not a hex key", self.key_code)
+    }
+}
+
+impl Error for InvalidKey {}
+
+pub struct Keymap {
+    /* Which hex key each host key stands for. Host keys are named by the
+       frontend, so any windowing library or gamepad can drive the keypad
+    */
+    bindings: HashMap<String, u8>,
+}
+
+impl Keymap {
+    pub fn new() -> Keymap {
+        return Keymap {
+            bindings: DEFAULT_LAYOUT
+                .iter()
+                .map(|&(name, key_code)| (name.to_string(), key_code))
+                .collect(),
+        };
+    }
+
+    pub fn bind(&mut self, key_code: u8, host_keys: &[&str]) -> Result<(), InvalidKey> {
+        /*  Makes host_keys the only ones standing for the hex key, taking them
+            away from any other hex key they were bound to
+        */
+        if key_code > 0xF {
+            return Err(InvalidKey { key_code });
+        }
+        self.bindings.retain(|_, bound| *bound != key_code);
+        for &name in host_keys {
+            self.bindings.insert(name.to_string(), key_code);
+        }
+        return Ok(());
+    }
+
+    pub fn key_code(&self, host_key: &str) -> Option<u8> {
+        return self.bindings.get(host_key).copied();
+    }
+
+    pub fn pressed<'a>(&self, host_keys: impl IntoIterator<Item = &'a str>) -> u16 {
+        /*  The hex keys held down, as the bitset Keyboard::set_pressed takes,
+            from the names of the host keys held down
+        */
+        return host_keys
+            .into_iter()
+            .filter_map(|name| self.key_code(name))
+            .fold(0x0, |pressed, key_code| pressed | 1 << key_code);
+    }
+}
+
+impl Default for Keymap {
+    fn default() -> Keymap {
+        return Keymap::new();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_layout_maps_the_left_hand_keys() {
+        let keymap = Keymap::new();
+        assert_eq!(keymap.key_code("1"), Some(0x1));
+        assert_eq!(keymap.key_code("V"), Some(0xF));
+        assert_eq!(keymap.key_code("Space"), None);
+        assert_eq!(keymap.pressed(["X", "4", "Space"]), 1 << 0x0 | 1 << 0xC);
+    }
+
+    #[test]
+    fn bind_replaces_the_keys_of_that_hex_key_only() {
+        let mut keymap = Keymap::new();
+        keymap.bind(0x5, &["Up", "W"]).unwrap();
+        keymap.bind(0x8, &["Down"]).unwrap();
+        assert_eq!(keymap.key_code("Up"), Some(0x5));
+        assert_eq!(keymap.key_code("W"), Some(0x5));
+        assert_eq!(keymap.key_code("S"), None);
+        assert_eq!(keymap.key_code("Q"), Some(0x4));
+    }
+
+    #[test]
+    fn bind_rejects_key_codes_past_the_keypad() {
+        let mut keymap = Keymap::new();
+        assert_eq!(
+            keymap.bind(0x10, &["Up"]),
+            Err(InvalidKey { key_code: 0x10 })
+        );
+        assert_eq!(keymap.key_code("Up"), None);
+        assert_eq!(keymap.pressed(["Up"]), 0);
+    }
+}
@@ -0,0 +1,74 @@
+use super::quirks::Quirks;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Mode {
+    /* Which CHIP-8 dialect is being emulated, each one a superset of the
+       one before it
+    */
+    Chip8,
+    SuperChip,
+    XoChip,
+}
+
+impl Mode {
+    pub fn from_name(name: &str) -> Option<Mode> {
+        /*  Looks up a mode by the name used on the command line
+         */
+        match name {
+            "chip8" => Some(Mode::Chip8),
+            "schip" => Some(Mode::SuperChip),
+            "xochip" => Some(Mode::XoChip),
+            _ => None,
+        }
+    }
+
+    pub fn superchip(&self) -> bool {
+        /*  Whether the SUPER-CHIP opcodes are available
+         */
+        return *self != Mode::Chip8;
+    }
+
+    pub fn xochip(&self) -> bool {
+        /*  Whether the XO-CHIP opcodes are available
+         */
+        return *self == Mode::XoChip;
+    }
+
+    pub fn memory_size(&self) -> usize {
+        /*  Bytes of addressable memory
+         */
+        match self {
+            Mode::Chip8 | Mode::SuperChip => 4 * 1024,
+            Mode::XoChip => 64 * 1024,
+        }
+    }
+
+    pub fn rpl_flag_count(&self) -> usize {
+        /*  How many RPL user flags FX75/FX85 can reach
+         */
+        match self {
+            Mode::Chip8 | Mode::SuperChip => 8,
+            Mode::XoChip => 16,
+        }
+    }
+
+    pub fn default_instructions_per_frame(&self) -> usize {
+        /*  A speed most programs written for this mode are playable at
+         */
+        match self {
+            Mode::Chip8 => 10,
+            Mode::SuperChip => 30,
+            Mode::XoChip => 200,
+        }
+    }
+
+    pub fn default_quirks(&self) -> Quirks {
+        /*  The quirks programs written for this mode usually expect
+         */
+        match self {
+            Mode::Chip8 => Quirks::modern(),
+            Mode::SuperChip => Quirks::superchip(),
+            Mode::XoChip => Quirks::xochip(),
+        }
+    }
+}
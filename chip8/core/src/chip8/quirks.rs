@@ -0,0 +1,236 @@
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum IndexIncrement {
+    /* How far FX55/FX65 move i after storing or loading V0 through Vx
+     */
+    // i is left where it was
+    None,
+
+    // i ends on the last address used, the CHIP-48 bug
+    ByX,
+
+    // i ends just past the last address used, as on the COSMAC VIP
+    ByXPlusOne,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Quirks {
+    /* The behaviours of the ambiguous opcodes, which differ between the
+       interpreters CHIP-8 programs were written for
+    */
+    // 8XY6/8XYE shift Vy into Vx instead of shifting Vx in place
+    pub shift_uses_vy: bool,
+
+    // How far FX55/FX65 advance i
+    pub load_store_increment: IndexIncrement,
+
+    // BNNN jumps to XNN + Vx instead of NNN + V0
+    pub jump_uses_vx: bool,
+
+    // 8XY1/8XY2/8XY3 reset VF to 0
+    pub vf_reset: bool,
+
+    // DXYN waits for the vertical blank, limiting draws to one per frame
+    pub display_wait: bool,
+
+    // Sprites are clipped at the screen edges instead of wrapping around
+    pub clip_sprites: bool,
+}
+
+impl Quirks {
+    pub fn modern() -> Quirks {
+        /*  The behaviour this emulator has always had, which most
+            modern CHIP-8 programs expect
+        */
+        return Quirks {
+            shift_uses_vy: false,
+            load_store_increment: IndexIncrement::ByXPlusOne,
+            jump_uses_vx: false,
+            vf_reset: false,
+            display_wait: false,
+            clip_sprites: true,
+        };
+    }
+
+    pub fn cosmac_vip() -> Quirks {
+        /*  The original interpreter on the RCA COSMAC VIP
+         */
+        return Quirks {
+            shift_uses_vy: true,
+            load_store_increment: IndexIncrement::ByXPlusOne,
+            jump_uses_vx: false,
+            vf_reset: true,
+            display_wait: true,
+            clip_sprites: true,
+        };
+    }
+
+    pub fn chip48() -> Quirks {
+        /*  CHIP-48 on the HP48 graphing calculators, which leaves i one short
+            after FX55/FX65 where the VIP moved it past the last register
+        */
+        return Quirks {
+            shift_uses_vy: false,
+            load_store_increment: IndexIncrement::ByX,
+            jump_uses_vx: true,
+            vf_reset: false,
+            display_wait: false,
+            clip_sprites: true,
+        };
+    }
+
+    pub fn superchip() -> Quirks {
+        /*  SUPER-CHIP 1.1 on the HP48 graphing calculators
+         */
+        return Quirks {
+            shift_uses_vy: false,
+            load_store_increment: IndexIncrement::None,
+            jump_uses_vx: true,
+            vf_reset: false,
+            display_wait: false,
+            clip_sprites: true,
+        };
+    }
+
+    pub fn xochip() -> Quirks {
+        /*  XO-CHIP as specified by Octo
+         */
+        return Quirks {
+            shift_uses_vy: true,
+            load_store_increment: IndexIncrement::ByXPlusOne,
+            jump_uses_vx: false,
+            vf_reset: false,
+            display_wait: false,
+            clip_sprites: false,
+        };
+    }
+
+    pub fn from_name(name: &str) -> Option<Quirks> {
+        /*  Looks up a preset by the name used on the command line
+         */
+        match name {
+            "modern" => Some(Quirks::modern()),
+            "vip" => Some(Quirks::cosmac_vip()),
+            "chip48" => Some(Quirks::chip48()),
+            "schip" => Some(Quirks::superchip()),
+            "xochip" => Some(Quirks::xochip()),
+            _ => None,
+        }
+    }
+}
+
+impl Default for Quirks {
+    fn default() -> Quirks {
+        return Quirks::modern();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chip8::{Chip8, Mode};
+
+    const PRESETS: [&str; 5] = ["modern", "vip", "chip48", "schip", "xochip"];
+
+    fn run(preset: &str, setup: impl Fn(&mut Chip8), opcode: u16) -> Chip8 {
+        /*  Runs one opcode under the preset, on a machine that has every
+            dialect's opcodes so only the quirks differ
+        */
+        let mut c8 = Chip8::new(Mode::XoChip, Quirks::from_name(preset).unwrap());
+        c8.cpu.program_counter = 0x200;
+        setup(&mut c8);
+        c8.execute(opcode).unwrap();
+        return c8;
+    }
+
+    fn each_preset<T: std::fmt::Debug + PartialEq>(expected: [T; 5], result: impl Fn(&str) -> T) {
+        for (preset, expected) in PRESETS.iter().zip(expected) {
+            assert_eq!(result(preset), expected, "under {}", preset);
+        }
+    }
+
+    #[test]
+    fn load_store_advance_i_by_the_preset_increment() {
+        let setup = |c8: &mut Chip8| c8.cpu.i = 0x300;
+        let expected = [0x303, 0x303, 0x302, 0x300, 0x303];
+        each_preset(expected, |preset| run(preset, setup, 0xF255).cpu.i);
+        each_preset(expected, |preset| run(preset, setup, 0xF265).cpu.i);
+    }
+
+    #[test]
+    fn shifts_take_vy_under_the_shift_quirk() {
+        let setup = |c8: &mut Chip8| {
+            c8.cpu.registers[1] = 0x10;
+            c8.cpu.registers[2] = 0x81;
+        };
+        let result = |preset: &str, opcode| {
+            let c8 = run(preset, setup, opcode);
+            (c8.cpu.registers[1], c8.cpu.registers[0xF])
+        };
+        let vx = (0x08, 0);
+        let vy = (0x40, 1);
+        each_preset([vx, vy, vx, vx, vy], |preset| result(preset, 0x8126));
+        let vx = (0x20, 0);
+        let vy = (0x02, 1);
+        each_preset([vx, vy, vx, vx, vy], |preset| result(preset, 0x812E));
+    }
+
+    #[test]
+    fn jump_takes_vx_under_the_jump_quirk() {
+        let setup = |c8: &mut Chip8| {
+            c8.cpu.registers[0] = 0x01;
+            c8.cpu.registers[2] = 0x05;
+        };
+        each_preset([0x211, 0x211, 0x215, 0x215, 0x211], |preset| {
+            run(preset, setup, 0xB210).cpu.program_counter
+        });
+    }
+
+    #[test]
+    fn logic_resets_vf_under_the_vf_reset_quirk() {
+        let setup = |c8: &mut Chip8| c8.cpu.registers[0xF] = 0x01;
+        for opcode in [0x8121, 0x8122, 0x8123] {
+            each_preset([1, 0, 1, 1, 1], |preset| {
+                run(preset, setup, opcode).cpu.registers[0xF]
+            });
+        }
+    }
+
+    #[test]
+    fn sprites_clip_or_wrap_at_the_edge() {
+        let setup = |c8: &mut Chip8| {
+            c8.cpu.registers[0] = 60;
+            c8.cpu.i = 0x300;
+            c8.memory.primary_memory[0x300] = 0xFF;
+        };
+        each_preset([0, 0, 0, 0, 1], |preset| {
+            run(preset, setup, 0xD011).display.buffer[0]
+        });
+        each_preset([1, 1, 1, 1, 1], |preset| {
+            run(preset, setup, 0xD011).display.buffer[63]
+        });
+    }
+
+    #[test]
+    fn drawing_waits_for_vblank_under_the_display_wait_quirk() {
+        let setup = |c8: &mut Chip8| c8.cpu.i = 0x300;
+        each_preset([false, true, false, false, false], |preset| {
+            run(preset, setup, 0xD011).vblank_wait
+        });
+    }
+
+    #[test]
+    fn presets_are_distinct() {
+        for (index, a) in PRESETS.iter().enumerate() {
+            for b in &PRESETS[index + 1..] {
+                assert_ne!(
+                    Quirks::from_name(a),
+                    Quirks::from_name(b),
+                    "{} and {}",
+                    a,
+                    b
+                );
+            }
+        }
+        assert_eq!(Quirks::from_name("nope"), None);
+    }
+}
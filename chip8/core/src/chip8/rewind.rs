@@ -0,0 +1,203 @@
+use std::collections::VecDeque;
+
+pub struct Rewind {
+    /* Ring buffer of per-frame save states for running the machine backwards.
+       Only the newest state is kept whole, every older frame is stored as the
+       difference from the frame after it, which is mostly zeros since few
+       bytes of memory change in a frame
+    */
+    // The most recent state, which the deltas are applied to
+    latest: Option<Vec<u8>>,
+
+    // Run-length encoded XOR of each frame with the one after it, newest last
+    deltas: VecDeque<Vec<u8>>,
+
+    // How many frames back can be reached
+    capacity: usize,
+}
+
+impl Rewind {
+    pub fn new(frames: usize) -> Rewind {
+        return Rewind {
+            latest: None,
+            deltas: VecDeque::with_capacity(frames),
+            capacity: frames,
+        };
+    }
+
+    pub fn push(&mut self, state: Vec<u8>) {
+        /*  Records the state at the end of a frame, dropping the oldest frame when full
+         */
+        if let Some(latest) = &self.latest {
+            if latest.len() == state.len() {
+                if self.deltas.len() == self.capacity {
+                    self.deltas.pop_front();
+                }
+                if self.capacity > 0 {
+                    self.deltas.push_back(encode(latest, &state));
+                }
+            } else {
+                // States of another size cannot be diffed, start over from this one
+                self.deltas.clear();
+            }
+        }
+        self.latest = Some(state);
+    }
+
+    pub fn pop(&mut self) -> Option<Vec<u8>> {
+        /*  Steps back one frame, returning the state to load, or None when there is no history left
+         */
+        let delta = self.deltas.pop_back()?;
+        let latest = self.latest.as_mut()?;
+        decode(&delta, latest);
+        return Some(latest.clone());
+    }
+}
+
+fn encode(previous: &[u8], next: &[u8]) -> Vec<u8> {
+    /*  XORs the two states and encodes the result as runs of
+        (zero count: u16, literal count: u16, literals)
+    */
+    let mut delta = Vec::new();
+    let mut index = 0;
+    while index < next.len() {
+        let zeros_start = index;
+        while index < next.len()
+            && index - zeros_start < u16::MAX as usize
+            && previous[index] == next[index]
+        {
+            index += 1;
+        }
+        let literals_start = index;
+        while index < next.len()
+            && index - literals_start < u16::MAX as usize
+            && previous[index] != next[index]
+        {
+            index += 1;
+        }
+        delta.extend_from_slice(&((literals_start - zeros_start) as u16).to_le_bytes());
+        delta.extend_from_slice(&((index - literals_start) as u16).to_le_bytes());
+        let changed = literals_start..index;
+        delta.extend(
+            previous[changed.clone()]
+                .iter()
+                .zip(&next[changed])
+                .map(|(a, b)| a ^ b),
+        );
+    }
+    return delta;
+}
+
+fn decode(delta: &[u8], state: &mut [u8]) {
+    /*  Applies an encoded XOR to the state in place, turning it into the frame before it
+     */
+    let mut position = 0;
+    let mut index = 0;
+    while position + 4 <= delta.len() {
+        let zeros = u16::from_le_bytes([delta[position], delta[position + 1]]) as usize;
+        let literals = u16::from_le_bytes([delta[position + 2], delta[position + 3]]) as usize;
+        position += 4;
+        index += zeros;
+        for byte in &delta[position..position + literals] {
+            state[index] ^= byte;
+            index += 1;
+        }
+        position += literals;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frames(count: usize, len: usize) -> Vec<Vec<u8>> {
+        /*  States that each change a few scattered bytes of the one before,
+            the way a frame of emulation does
+        */
+        let mut seed = 0x1234_5678u32;
+        let mut state = vec![0; len];
+        let mut frames = Vec::new();
+        for _ in 0..count {
+            for _ in 0..8 {
+                seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12345);
+                state[(seed >> 8) as usize % len] = (seed >> 24) as u8;
+            }
+            frames.push(state.clone());
+        }
+        return frames;
+    }
+
+    #[test]
+    fn decode_undoes_encode() {
+        let previous = frames(1, 4096).remove(0);
+        let mut next = previous.clone();
+        next[0] ^= 0xFF;
+        next[100..200].iter_mut().for_each(|byte| *byte ^= 0x5A);
+        next[4095] ^= 0x01;
+
+        let mut state = next.clone();
+        decode(&encode(&previous, &next), &mut state);
+        assert_eq!(state, previous);
+    }
+
+    #[test]
+    fn runs_longer_than_a_u16_are_split() {
+        let previous = vec![0; 200_000];
+        let mut next = previous.clone();
+        next[150_000..].iter_mut().for_each(|byte| *byte = 0xAA);
+
+        let mut state = next.clone();
+        decode(&encode(&previous, &next), &mut state);
+        assert_eq!(state, previous);
+    }
+
+    #[test]
+    fn pop_returns_each_frame_newest_first() {
+        let frames = frames(20, 512);
+        let mut rewind = Rewind::new(32);
+        for frame in frames.iter() {
+            rewind.push(frame.clone());
+        }
+        for frame in frames[..frames.len() - 1].iter().rev() {
+            assert_eq!(rewind.pop().as_ref(), Some(frame));
+        }
+        assert_eq!(rewind.pop(), None);
+    }
+
+    #[test]
+    fn oldest_frames_are_evicted_when_full() {
+        let frames = frames(20, 512);
+        let mut rewind = Rewind::new(5);
+        for frame in frames.iter() {
+            rewind.push(frame.clone());
+        }
+        for frame in frames[14..19].iter().rev() {
+            assert_eq!(rewind.pop().as_ref(), Some(frame));
+        }
+        assert_eq!(rewind.pop(), None);
+    }
+
+    #[test]
+    fn frames_pushed_after_popping_continue_from_there() {
+        let frames = frames(6, 512);
+        let mut rewind = Rewind::new(8);
+        for frame in frames[..4].iter() {
+            rewind.push(frame.clone());
+        }
+        assert_eq!(rewind.pop().as_ref(), Some(&frames[2]));
+        rewind.push(frames[5].clone());
+
+        assert_eq!(rewind.pop().as_ref(), Some(&frames[2]));
+        assert_eq!(rewind.pop().as_ref(), Some(&frames[1]));
+    }
+
+    #[test]
+    fn a_state_of_another_size_drops_the_history() {
+        let mut rewind = Rewind::new(8);
+        for frame in frames(4, 512) {
+            rewind.push(frame);
+        }
+        rewind.push(vec![0; 1024]);
+        assert_eq!(rewind.pop(), None);
+    }
+}
@@ -0,0 +1,93 @@
+use std::io;
+
+pub const SAMPLE_RATE: u32 = 44100;
+
+// The timers, and so the speaker, are clocked at 60 Hz
+const TICK_RATE: u32 = 60;
+
+const AMPLITUDE: i16 = 8000;
+
+pub trait SampleSink {
+    /*  Somewhere the speaker sends its signed 16-bit mono PCM samples,
+        e.g. an audio device or a file
+    */
+    fn write(&mut self, samples: &[i16]);
+
+    fn finish(&mut self) -> io::Result<()> {
+        /*  Called once the emulation is over, to complete the output and
+            report any error writing it hit along the way
+        */
+        return Ok(());
+    }
+}
+
+pub struct NullSink;
+
+impl SampleSink for NullSink {
+    fn write(&mut self, _samples: &[i16]) {}
+}
+
+pub struct Speaker {
+    is_playing: bool,
+
+    // XO-CHIP 1-bit audio pattern, played back MSB first.
+    // Defaults to a square wave for plain CHIP-8 and SUPER-CHIP
+    pub pattern: [u8; 16],
+
+    // XO-CHIP playback rate of the pattern, 4000 * 2^((pitch - 64) / 48) Hz
+    pub pitch: u8,
+
+    // Position in the pattern, in bits
+    phase: f64,
+
+    // Fraction of a sample left over from the previous tick
+    remainder: u32,
+
+    sink: Box<dyn SampleSink>,
+}
+
+impl Speaker {
+    pub fn new() -> Speaker {
+        return Speaker {
+            is_playing: false,
+            pattern: [0xF0; 16],
+            pitch: 64,
+            phase: 0.0,
+            remainder: 0,
+            sink: Box::new(NullSink),
+        };
+    }
+
+    pub fn set_sink(&mut self, sink: Box<dyn SampleSink>) {
+        self.sink = sink;
+    }
+
+    pub fn finish(&mut self) -> io::Result<()> {
+        return self.sink.finish();
+    }
+
+    pub fn tick(&mut self, sound_on: bool) {
+        /*  Generates one timer tick worth of samples, the pattern while the
+            sound timer is running and silence otherwise
+        */
+        self.is_playing = sound_on;
+        self.remainder += SAMPLE_RATE;
+        let count = self.remainder / TICK_RATE;
+        self.remainder %= TICK_RATE;
+
+        let mut samples = vec![0; count as usize];
+        if sound_on {
+            let rate = 4000.0 * 2f64.powf((self.pitch as f64 - 64.0) / 48.0);
+            let step = rate / SAMPLE_RATE as f64;
+            for sample in samples.iter_mut() {
+                let bit = self.phase as usize % 128;
+                let set = self.pattern[bit / 8] & (0b1000_0000 >> (bit % 8)) != 0;
+                *sample = if set { AMPLITUDE } else { -AMPLITUDE };
+                self.phase = (self.phase + step) % 128.0;
+            }
+        } else {
+            self.phase = 0.0;
+        }
+        self.sink.write(&samples);
+    }
+}
@@ -0,0 +1,297 @@
+use std::error::Error;
+use std::fmt;
+
+use super::Chip8;
+
+/*  Save state layout, all integers little endian:
+        magic       4 bytes "C8ST"
+        version     u16
+        mode        u8
+        rom hash    u64, FNV-1a of the loaded program
+        length      u32, bytes of payload
+        checksum    u32, CRC-32 of the payload
+        payload     the machine, in the order written by write_payload
+*/
+const MAGIC: &[u8; 4] = b"C8ST";
+const VERSION: u16 = 1;
+const HEADER_LEN: usize = 4 + 2 + 1 + 8 + 4 + 4;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StateError {
+    /* Reasons a save state cannot be loaded into the running machine
+     */
+    // Not a save state, or cut short
+    BadHeader,
+    UnsupportedVersion(u16),
+
+    // The state was taken with a different program loaded
+    RomMismatch,
+
+    // The state was taken under a different mode, whose memory size differs
+    ModeMismatch,
+
+    // The payload does not match its checksum or length
+    Corrupt,
+}
+
+impl fmt::Display for StateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StateError::BadHeader => write!(f, "not a save state"),
+            StateError::UnsupportedVersion(version) => {
+                write!(f, "unsupported save state version {}", version)
+            }
+            StateError::RomMismatch => write!(f, "save state is for a different rom"),
+            StateError::ModeMismatch => write!(f, "save state is for a different mode"),
+            StateError::Corrupt => write!(f, "save state is corrupt"),
+        }
+    }
+}
+
+impl Error for StateError {}
+
+pub fn rom_hash(program: &[u8]) -> u64 {
+    /*  64-bit FNV-1a hash identifying a program
+     */
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in program {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    return hash;
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    /*  CRC-32 as used by zip and png, computed bit by bit
+     */
+    let mut crc = 0xFFFF_FFFFu32;
+    for byte in data {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xEDB8_8320 & mask);
+        }
+    }
+    return !crc;
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    /*  Reads from a payload whose length has already been checked, so reads never run out
+     */
+    fn bytes(&mut self, len: usize) -> &'a [u8] {
+        let bytes = &self.data[self.position..self.position + len];
+        self.position += len;
+        return bytes;
+    }
+
+    fn u8(&mut self) -> u8 {
+        return self.bytes(1)[0];
+    }
+
+    fn u16(&mut self) -> u16 {
+        return u16::from_le_bytes([self.u8(), self.u8()]);
+    }
+}
+
+impl Chip8 {
+    fn write_payload(&self, out: &mut Vec<u8>) {
+        /*  The machine state, excluding the mode and quirks, which come from the command line
+         */
+        out.extend_from_slice(&self.cpu.current_instruction.to_le_bytes());
+        out.push(self.cpu.sound_timer);
+        out.push(self.cpu.delay_timer);
+        out.extend_from_slice(&self.cpu.registers);
+        out.extend_from_slice(&self.cpu.i.to_le_bytes());
+        out.extend_from_slice(&self.cpu.program_counter.to_le_bytes());
+        out.push(self.cpu.stack_pointer);
+
+        out.extend_from_slice(&self.memory.primary_memory);
+        for address in self.memory.stack.iter() {
+            out.extend_from_slice(&address.to_le_bytes());
+        }
+
+        out.extend_from_slice(&self.display.buffer);
+        out.push(self.display.hires as u8);
+        out.push(self.display.planes);
+
+        out.extend_from_slice(&self.keyboard.pressed.to_le_bytes());
+        out.push(self.keyboard.waiting as u8);
+        out.extend_from_slice(&self.keyboard.armed.to_le_bytes());
+        out.push(self.keyboard.released.map_or(0xFF, |key_code| key_code));
+
+        out.extend_from_slice(&self.speaker.pattern);
+        out.push(self.speaker.pitch);
+
+        out.push(self.vblank_wait as u8);
+        out.push(self.exited as u8);
+        out.extend_from_slice(&self.rpl_flags);
+    }
+
+    fn read_payload(&mut self, reader: &mut Reader) {
+        self.cpu.current_instruction = reader.u16();
+        self.cpu.sound_timer = reader.u8();
+        self.cpu.delay_timer = reader.u8();
+        self.cpu.registers.copy_from_slice(reader.bytes(16));
+        self.cpu.i = reader.u16();
+        self.cpu.program_counter = reader.u16();
+        self.cpu.stack_pointer = reader.u8();
+
+        let size = self.memory.primary_memory.len();
+        self.memory
+            .primary_memory
+            .copy_from_slice(reader.bytes(size));
+        for address in self.memory.stack.iter_mut() {
+            *address = reader.u16();
+        }
+
+        let size = self.display.buffer.len();
+        self.display.buffer.copy_from_slice(reader.bytes(size));
+        self.display.hires = reader.u8() != 0;
+        self.display.planes = reader.u8();
+        self.display.dirty = true;
+
+        self.keyboard.pressed = reader.u16();
+        self.keyboard.waiting = reader.u8() != 0;
+        self.keyboard.armed = reader.u16();
+        self.keyboard.released = match reader.u8() {
+            0xFF => None,
+            key_code => Some(key_code),
+        };
+
+        self.speaker.pattern.copy_from_slice(reader.bytes(16));
+        self.speaker.pitch = reader.u8();
+
+        self.vblank_wait = reader.u8() != 0;
+        self.exited = reader.u8() != 0;
+        self.rpl_flags.copy_from_slice(reader.bytes(16));
+    }
+
+    pub fn save_state(&self) -> Vec<u8> {
+        /*  Snapshots the whole machine into a versioned save state
+         */
+        let mut payload = Vec::new();
+        self.write_payload(&mut payload);
+
+        let mut state = Vec::with_capacity(HEADER_LEN + payload.len());
+        state.extend_from_slice(MAGIC);
+        state.extend_from_slice(&VERSION.to_le_bytes());
+        state.push(self.mode as u8);
+        state.extend_from_slice(&self.rom_hash.to_le_bytes());
+        state.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+        state.extend_from_slice(&crc32(&payload).to_le_bytes());
+        state.extend_from_slice(&payload);
+        return state;
+    }
+
+    pub fn load_state(&mut self, state: &[u8]) -> Result<(), StateError> {
+        /*  Restores a snapshot taken by save_state.
+            The state is fully checked first, so on failure the machine is left untouched
+        */
+        if state.len() < HEADER_LEN || &state[0..4] != MAGIC {
+            return Err(StateError::BadHeader);
+        }
+        let field = |start: usize, len: usize| &state[start..start + len];
+        let version = u16::from_le_bytes(field(4, 2).try_into().unwrap());
+        if version != VERSION {
+            return Err(StateError::UnsupportedVersion(version));
+        }
+        if state[6] != self.mode as u8 {
+            return Err(StateError::ModeMismatch);
+        }
+        if u64::from_le_bytes(field(7, 8).try_into().unwrap()) != self.rom_hash {
+            return Err(StateError::RomMismatch);
+        }
+        let len = u32::from_le_bytes(field(15, 4).try_into().unwrap()) as usize;
+        let checksum = u32::from_le_bytes(field(19, 4).try_into().unwrap());
+        let payload = &state[HEADER_LEN..];
+
+        // Every field has a fixed size for a given mode, so the expected length is our own
+        let mut expected = Vec::new();
+        self.write_payload(&mut expected);
+        if len != payload.len() || len != expected.len() || crc32(payload) != checksum {
+            return Err(StateError::Corrupt);
+        }
+
+        self.read_payload(&mut Reader {
+            data: payload,
+            position: 0,
+        });
+        return Ok(());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chip8::{Mode, Quirks};
+
+    const PROGRAM: &[u8] = include_bytes!("../../../roms/IBM");
+
+    fn running_machine() -> Chip8 {
+        let mut c8 = Chip8::new(Mode::Chip8, Quirks::modern());
+        c8.load_program(PROGRAM).unwrap();
+        for _ in 0..10 {
+            c8.run_frame(10).unwrap();
+        }
+        return c8;
+    }
+
+    #[test]
+    fn load_restores_a_saved_state() {
+        let mut c8 = running_machine();
+        let state = c8.save_state();
+        c8.cpu.registers[3] ^= 0x42;
+        c8.cpu.program_counter = 0x300;
+        c8.memory.primary_memory[0x300] ^= 0xFF;
+        c8.display.buffer[0] ^= 1;
+        assert_ne!(c8.save_state(), state);
+
+        c8.load_state(&state).unwrap();
+        assert_eq!(c8.save_state(), state);
+    }
+
+    #[test]
+    fn corrupt_payload_is_rejected() {
+        let mut c8 = running_machine();
+        let mut state = c8.save_state();
+        let before = state.clone();
+        state[HEADER_LEN + 40] ^= 0x01;
+
+        assert_eq!(c8.load_state(&state), Err(StateError::Corrupt));
+        assert_eq!(c8.save_state(), before);
+    }
+
+    #[test]
+    fn truncated_state_is_rejected() {
+        let mut c8 = running_machine();
+        let state = c8.save_state();
+
+        assert_eq!(
+            c8.load_state(&state[..HEADER_LEN - 1]),
+            Err(StateError::BadHeader)
+        );
+        assert_eq!(
+            c8.load_state(&state[..state.len() - 1]),
+            Err(StateError::Corrupt)
+        );
+    }
+
+    #[test]
+    fn state_for_another_rom_is_rejected() {
+        let state = running_machine().save_state();
+        let mut other = Chip8::new(Mode::Chip8, Quirks::modern());
+        other.load_program(&PROGRAM[..PROGRAM.len() - 2]).unwrap();
+
+        assert_eq!(other.load_state(&state), Err(StateError::RomMismatch));
+    }
+
+    #[test]
+    fn crc32_matches_the_standard_check_value() {
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+    }
+}
@@ -0,0 +1,197 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::ops::RangeInclusive;
+
+use super::disassembler::{disassemble_at, Syntax};
+use super::Chip8;
+
+pub struct Tracer {
+    /* Writes one line per instruction fetched, with the machine state
+       before it runs, e.g.
+       00000012 0208 A30C i := 0x30C        V 05 00 .. 00 I 0000 SP FF DT 00 ST 00
+    */
+    writer: Box<dyn Write>,
+
+    // The first write that failed, which turns tracing off until finish reports it
+    error: Option<io::Error>,
+
+    // Only instructions at these addresses are written
+    pub pc_range: RangeInclusive<u16>,
+
+    // Stop writing after this many lines
+    pub line_limit: Option<u64>,
+
+    // Instructions fetched so far, including the ones filtered out
+    cycles: u64,
+    lines: u64,
+}
+
+impl Tracer {
+    pub fn create(path: &str) -> io::Result<Tracer> {
+        return Ok(Tracer::new(Box::new(BufWriter::new(File::create(path)?))));
+    }
+
+    pub fn new(writer: Box<dyn Write>) -> Tracer {
+        return Tracer {
+            writer,
+            error: None,
+            pc_range: 0x0000..=0xFFFF,
+            line_limit: None,
+            cycles: 0,
+            lines: 0,
+        };
+    }
+
+    pub fn finish(&mut self) -> io::Result<()> {
+        /*  Flushes the trace, or reports the error that stopped it
+         */
+        if let Some(error) = self.error.take() {
+            return Err(error);
+        }
+        return self.writer.flush();
+    }
+
+    pub fn record(&mut self, c8: &Chip8) {
+        /*  Called with the instruction about to run in cpu.current_instruction
+         */
+        self.cycles += 1;
+        let pc = c8.cpu.program_counter;
+        if self.error.is_some()
+            || !self.pc_range.contains(&pc)
+            || self.line_limit.is_some_and(|limit| self.lines >= limit)
+        {
+            return;
+        }
+        self.lines += 1;
+        let mnemonic = disassemble_at(&c8.memory.primary_memory, pc, c8.mode, Syntax::Octo)
+            .map_or("??".to_string(), |(text, _)| text);
+        let registers: Vec<String> = c8
+            .cpu
+            .registers
+            .iter()
+            .map(|register| format!("{:02X}", register))
+            .collect();
+        let written = writeln!(
+            self.writer,
+            "{:08} {:04X} {:04X} {:<24} V {} I {:04X} SP {:02X} DT {:02X} ST {:02X}",
+            self.cycles,
+            pc,
+            c8.cpu.current_instruction,
+            mnemonic,
+            registers.join(" "),
+            c8.cpu.i,
+            c8.cpu.stack_pointer,
+            c8.cpu.delay_timer,
+            c8.cpu.sound_timer
+        );
+        if let Err(error) = written {
+            self.error = Some(error);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chip8::{Mode, Quirks};
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    // A writer the test keeps a handle on after handing it to the tracer
+    #[derive(Clone, Default)]
+    struct Shared(Rc<RefCell<Vec<u8>>>);
+
+    impl Write for Shared {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.borrow_mut().extend_from_slice(buf);
+            return Ok(buf.len());
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            return Ok(());
+        }
+    }
+
+    struct Closed;
+
+    impl Write for Closed {
+        fn write(&mut self, _buf: &[u8]) -> io::Result<usize> {
+            return Err(io::Error::new(io::ErrorKind::BrokenPipe, "pipe closed"));
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            return Ok(());
+        }
+    }
+
+    // v0 := 0x05, i := 0x30C, v1 += 0x01, jump 0x204
+    const PROGRAM: [u8; 8] = [0x60, 0x05, 0xA3, 0x0C, 0x71, 0x01, 0x12, 0x04];
+
+    fn traced(tracer: Tracer, steps: usize) -> Chip8 {
+        let mut c8 = Chip8::new(Mode::Chip8, Quirks::modern());
+        c8.load_program(&PROGRAM).unwrap();
+        c8.tracer = Some(tracer);
+        for _ in 0..steps {
+            c8.emulate_cycle().unwrap();
+        }
+        return c8;
+    }
+
+    fn lines(output: &Shared) -> Vec<String> {
+        let text = String::from_utf8(output.0.borrow().clone()).unwrap();
+        return text.lines().map(str::to_string).collect();
+    }
+
+    #[test]
+    fn each_instruction_is_a_line_with_the_state_before_it() {
+        let output = Shared::default();
+        traced(Tracer::new(Box::new(output.clone())), 2);
+        let zeros = ["00"; 15].join(" ");
+        assert_eq!(
+            lines(&output),
+            [
+                format!(
+                    "00000001 0200 6005 v0 := 0x05               V 00 {} I 0000 SP FF DT 00 ST 00",
+                    zeros
+                ),
+                format!(
+                    "00000002 0202 A30C i := 0x30C               V 05 {} I 0000 SP FF DT 00 ST 00",
+                    zeros
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn only_pcs_in_range_are_written() {
+        let output = Shared::default();
+        let mut tracer = Tracer::new(Box::new(output.clone()));
+        tracer.pc_range = 0x0204..=0x0204;
+        traced(tracer, 8);
+        let lines = lines(&output);
+        assert_eq!(lines.len(), 3);
+        assert!(lines.iter().all(|line| line[9..13] == *"0204"));
+        assert!(lines[1].starts_with("00000005 "));
+    }
+
+    #[test]
+    fn writing_stops_at_the_line_limit() {
+        let output = Shared::default();
+        let mut tracer = Tracer::new(Box::new(output.clone()));
+        tracer.line_limit = Some(3);
+        traced(tracer, 10);
+        assert_eq!(lines(&output).len(), 3);
+    }
+
+    #[test]
+    fn a_failed_write_turns_tracing_off_and_is_reported() {
+        let mut c8 = traced(Tracer::new(Box::new(Closed)), 4);
+        let tracer = c8.tracer.as_mut().unwrap();
+        assert_eq!(tracer.lines, 1);
+        assert_eq!(
+            tracer.finish().unwrap_err().kind(),
+            io::ErrorKind::BrokenPipe
+        );
+        assert!(tracer.finish().is_ok());
+    }
+}
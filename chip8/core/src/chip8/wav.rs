@@ -0,0 +1,188 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Seek, SeekFrom, Write};
+
+use super::speaker::{SampleSink, SAMPLE_RATE};
+
+pub struct WavSink<W: Write + Seek = BufWriter<File>> {
+    /* Records the speaker output as a 16-bit mono PCM .wav file
+     */
+    writer: W,
+
+    // Bytes of sample data written so far, patched into the header by finish
+    data_bytes: u32,
+
+    // The first write that failed, nothing more is written after it
+    error: Option<io::Error>,
+
+    // Set once finish has run, so dropping the sink does not patch the header again
+    finished: bool,
+}
+
+impl WavSink {
+    pub fn create(path: &str) -> io::Result<WavSink> {
+        return WavSink::new(BufWriter::new(File::create(path)?));
+    }
+}
+
+impl<W: Write + Seek> WavSink<W> {
+    pub fn new(writer: W) -> io::Result<WavSink<W>> {
+        let mut sink = WavSink {
+            writer,
+            data_bytes: 0,
+            error: None,
+            finished: false,
+        };
+        sink.write_header()?;
+        return Ok(sink);
+    }
+
+    fn write_header(&mut self) -> io::Result<()> {
+        /*  RIFF header followed by the fmt chunk and the data chunk header
+         */
+        let w = &mut self.writer;
+        w.write_all(b"RIFF")?;
+        w.write_all(&(36 + self.data_bytes).to_le_bytes())?;
+        w.write_all(b"WAVE")?;
+        w.write_all(b"fmt ")?;
+        w.write_all(&16u32.to_le_bytes())?;
+        w.write_all(&1u16.to_le_bytes())?; // PCM
+        w.write_all(&1u16.to_le_bytes())?; // mono
+        w.write_all(&SAMPLE_RATE.to_le_bytes())?;
+        w.write_all(&(SAMPLE_RATE * 2).to_le_bytes())?; // byte rate
+        w.write_all(&2u16.to_le_bytes())?; // block align
+        w.write_all(&16u16.to_le_bytes())?; // bits per sample
+        w.write_all(b"data")?;
+        w.write_all(&self.data_bytes.to_le_bytes())?;
+        return Ok(());
+    }
+
+    fn write_samples(&mut self, samples: &[i16]) -> io::Result<()> {
+        for sample in samples {
+            self.writer.write_all(&sample.to_le_bytes())?;
+        }
+        self.data_bytes += samples.len() as u32 * 2;
+        return Ok(());
+    }
+}
+
+impl<W: Write + Seek> SampleSink for WavSink<W> {
+    fn write(&mut self, samples: &[i16]) {
+        /*  Keeps the first error for finish to report, the emulation carries on without sound
+         */
+        if self.error.is_none() && !self.finished {
+            if let Err(error) = self.write_samples(samples) {
+                self.error = Some(error);
+            }
+        }
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        /*  Rewrites the header now that the length of the data is known, or
+            reports the error that stopped the recording
+        */
+        if self.finished {
+            return Ok(());
+        }
+        self.finished = true;
+        if let Some(error) = self.error.take() {
+            return Err(error);
+        }
+        self.writer.seek(SeekFrom::Start(0))?;
+        self.write_header()?;
+        self.writer.flush()?;
+        return Ok(());
+    }
+}
+
+impl<W: Write + Seek> Drop for WavSink<W> {
+    fn drop(&mut self) {
+        /*  A sink nobody finished is finished as well as possible, errors are
+            dropped as there is no one left to report them to
+        */
+        let _ = self.finish();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    const HEADER_LEN: usize = 44;
+
+    fn u32_at(bytes: &[u8], offset: usize) -> u32 {
+        return u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap());
+    }
+
+    #[test]
+    fn finish_patches_the_lengths_into_the_header() {
+        let mut file = Vec::new();
+        let mut sink = WavSink::new(Cursor::new(&mut file)).unwrap();
+        sink.write(&[1, -1, 2]);
+        sink.write(&[0x1234; 735]);
+        sink.finish().unwrap();
+        drop(sink);
+
+        let data_bytes = 738 * 2;
+        assert_eq!(file.len(), HEADER_LEN + data_bytes);
+        assert_eq!(&file[0..4], b"RIFF");
+        assert_eq!(u32_at(&file, 4), 36 + data_bytes as u32);
+        assert_eq!(&file[36..40], b"data");
+        assert_eq!(u32_at(&file, 40), data_bytes as u32);
+        assert_eq!(
+            &file[HEADER_LEN..HEADER_LEN + 6],
+            [0x01, 0x00, 0xFF, 0xFF, 0x02, 0x00]
+        );
+    }
+
+    #[test]
+    fn dropping_an_unfinished_sink_finishes_it() {
+        let mut file = Vec::new();
+        let mut sink = WavSink::new(Cursor::new(&mut file)).unwrap();
+        sink.write(&[0; 10]);
+        drop(sink);
+
+        assert_eq!(u32_at(&file, 40), 20);
+    }
+
+    struct FailingWriter {
+        written: usize,
+        capacity: usize,
+    }
+
+    impl Write for FailingWriter {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            if self.written + buf.len() > self.capacity {
+                return Err(io::Error::new(io::ErrorKind::StorageFull, "disk full"));
+            }
+            self.written += buf.len();
+            return Ok(buf.len());
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            return Ok(());
+        }
+    }
+
+    impl Seek for FailingWriter {
+        fn seek(&mut self, _pos: SeekFrom) -> io::Result<u64> {
+            self.written = 0;
+            return Ok(0);
+        }
+    }
+
+    #[test]
+    fn a_failed_write_is_reported_by_finish() {
+        let writer = FailingWriter {
+            written: 0,
+            capacity: HEADER_LEN + 4,
+        };
+        let mut sink = WavSink::new(writer).unwrap();
+        sink.write(&[0; 4]);
+        sink.write(&[0; 4]);
+
+        let error = sink.finish().unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::StorageFull);
+        assert!(sink.finish().is_ok());
+    }
+}
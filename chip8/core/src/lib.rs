@@ -0,0 +1,5 @@
+#![allow(clippy::needless_return)]
+
+mod chip8;
+
+pub use crate::chip8::*;
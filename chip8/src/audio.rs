@@ -0,0 +1,148 @@
+use std::io::{self, Write};
+
+#[cfg(feature = "audio")]
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+#[cfg(feature = "audio")]
+use std::collections::VecDeque;
+#[cfg(feature = "audio")]
+use std::sync::{Arc, Mutex};
+
+// Samples queued for the device beyond this are dropped, about 100 ms, so a
+// device clock running slower than the emulation cannot build up lag
+#[cfg(feature = "audio")]
+const MAX_QUEUED: usize = chip8::SAMPLE_RATE as usize / 10;
+
+pub struct BellSink {
+    /* The terminal bell, rung once each time the sound starts. For when
+       there is no audio device
+    */
+    ringing: bool,
+}
+
+impl BellSink {
+    pub fn new() -> BellSink {
+        return BellSink { ringing: false };
+    }
+}
+
+impl Default for BellSink {
+    fn default() -> BellSink {
+        return BellSink::new();
+    }
+}
+
+impl chip8::SampleSink for BellSink {
+    fn write(&mut self, samples: &[i16]) {
+        let sounding = samples.iter().any(|&sample| sample != 0);
+        if sounding && !self.ringing {
+            eprint!("\x07");
+            let _ = io::stderr().flush();
+        }
+        self.ringing = sounding;
+    }
+}
+
+#[cfg(feature = "audio")]
+pub struct DeviceSink {
+    /* Plays the speaker on the default output device. The emulation queues
+       samples, the device's callback thread takes them off at its own rate
+    */
+    queue: Arc<Mutex<VecDeque<i16>>>,
+
+    // Sound stops when this is dropped
+    _stream: cpal::Stream,
+}
+
+#[cfg(feature = "audio")]
+impl DeviceSink {
+    pub fn open() -> Result<DeviceSink, String> {
+        /*  Starts a stream on the default output device, in whatever format,
+            rate and channel count it prefers
+        */
+        let device = cpal::default_host()
+            .default_output_device()
+            .ok_or("no audio output device")?;
+        let supported = device
+            .default_output_config()
+            .map_err(|error| error.to_string())?;
+        let format = supported.sample_format();
+        let config: cpal::StreamConfig = supported.into();
+        let queue = Arc::new(Mutex::new(VecDeque::new()));
+        let stream = match format {
+            cpal::SampleFormat::F32 => build_stream::<f32>(&device, &config, &queue),
+            cpal::SampleFormat::I16 => build_stream::<i16>(&device, &config, &queue),
+            cpal::SampleFormat::U16 => build_stream::<u16>(&device, &config, &queue),
+            format => return Err(format!("unsupported sample format {}", format)),
+        }?;
+        stream.play().map_err(|error| error.to_string())?;
+        return Ok(DeviceSink {
+            queue,
+            _stream: stream,
+        });
+    }
+}
+
+#[cfg(feature = "audio")]
+fn build_stream<T>(
+    device: &cpal::Device,
+    config: &cpal::StreamConfig,
+    queue: &Arc<Mutex<VecDeque<i16>>>,
+) -> Result<cpal::Stream, String>
+where
+    T: cpal::SizedSample + cpal::FromSample<i16>,
+{
+    /*  The callback resamples the queued mono samples to the device's rate,
+        nearest neighbour, and copies each one to every channel. Running dry
+        plays silence
+    */
+    let channels = config.channels as usize;
+    let step = chip8::SAMPLE_RATE as f64 / config.sample_rate.0 as f64;
+    let queue = Arc::clone(queue);
+    let mut position = 0.0;
+    let mut current = 0;
+    return device
+        .build_output_stream(
+            config,
+            move |data: &mut [T], _| {
+                let mut queue = queue
+                    .lock()
+                    .unwrap_or_else(|poisoned| poisoned.into_inner());
+                for frame in data.chunks_mut(channels) {
+                    position += step;
+                    while position >= 1.0 {
+                        current = queue.pop_front().unwrap_or(0);
+                        position -= 1.0;
+                    }
+                    frame.fill(T::from_sample(current));
+                }
+            },
+            |error| eprintln!("audio stream error: {}", error),
+            None,
+        )
+        .map_err(|error| error.to_string());
+}
+
+#[cfg(feature = "audio")]
+impl chip8::SampleSink for DeviceSink {
+    fn write(&mut self, samples: &[i16]) {
+        let mut queue = self
+            .queue
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        queue.extend(samples);
+        let excess = queue.len().saturating_sub(MAX_QUEUED);
+        queue.drain(..excess);
+    }
+}
+
+pub fn default_sink() -> Box<dyn chip8::SampleSink> {
+    /*  The audio device, or the terminal bell when there is none or the
+        emulator was built without the audio feature
+    */
+    #[cfg(feature = "audio")]
+    match DeviceSink::open() {
+        Ok(sink) => return Box::new(sink),
+        Err(error) => eprintln!("No audio ({}), falling back to the terminal bell", error),
+    }
+    return Box::new(BellSink::new());
+}
@@ -0,0 +1,53 @@
+#![allow(clippy::needless_return)]
+
+use std::env;
+use std::fs;
+use std::process::ExitCode;
+
+/*  Assembles a source file into a rom, and a symbol map next to it for the debugger.
+
+    chip8-asm --source=<path> [--out=<path>] [--symbols=<path>]
+
+    The rom defaults to the source with a .ch8 extension and the symbol map
+    to the rom with .sym appended
+*/
+
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().collect();
+
+    let mut source_path = None;
+    let mut out_path = None;
+    let mut symbols_path = None;
+
+    for arg in args.iter() {
+        if let Some(path) = arg.strip_prefix("--source=") {
+            source_path = Some(path.to_string());
+        }
+        if let Some(path) = arg.strip_prefix("--out=") {
+            out_path = Some(path.to_string());
+        }
+        if let Some(path) = arg.strip_prefix("--symbols=") {
+            symbols_path = Some(path.to_string());
+        }
+    }
+
+    let source_path = source_path.expect("no source given, pass --source=<path>");
+    let out_path = out_path.unwrap_or_else(|| {
+        let stem = source_path
+            .rsplit_once('.')
+            .map_or(source_path.as_str(), |(stem, _)| stem);
+        format!("{}.ch8", stem)
+    });
+    let symbols_path = symbols_path.unwrap_or(format!("{}.sym", out_path));
+
+    let assembly = match chip8::assemble(&source_path) {
+        Ok(assembly) => assembly,
+        Err(error) => {
+            eprintln!("{}", error);
+            return ExitCode::FAILURE;
+        }
+    };
+    fs::write(&out_path, &assembly.program).expect("unable to write rom");
+    fs::write(&symbols_path, assembly.symbol_map()).expect("unable to write symbol map");
+    return ExitCode::SUCCESS;
+}
@@ -0,0 +1,32 @@
+#![allow(clippy::needless_return)]
+
+use std::env;
+use std::fs;
+
+/*  Prints a listing of a rom.
+
+    chip8-dis --rom=<path> [--mode=chip8|schip|xochip] [--syntax=octo|cowgod]
+*/
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+
+    let mut rom_path = None;
+    let mut mode = chip8::Mode::Chip8;
+    let mut syntax = chip8::Syntax::Octo;
+
+    for arg in args.iter() {
+        if let Some(path) = arg.strip_prefix("--rom=") {
+            rom_path = Some(path);
+        }
+        if let Some(name) = arg.strip_prefix("--mode=") {
+            mode = chip8::Mode::from_name(name).expect("unknown mode");
+        }
+        if let Some(name) = arg.strip_prefix("--syntax=") {
+            syntax = chip8::Syntax::from_name(name).expect("unknown syntax");
+        }
+    }
+
+    let rom = fs::read(rom_path.expect("no rom given, pass --rom=<path>")).expect("no file found");
+    print!("{}", chip8::disassemble(&rom, mode, syntax));
+}
@@ -0,0 +1,355 @@
+#![allow(clippy::needless_return)]
+
+use std::env;
+use std::fs::{self, File};
+use std::io::BufWriter;
+use std::ops::RangeInclusive;
+use std::process::ExitCode;
+
+/*  Runs a rom without a window, for CI and batch testing.
+
+    chip8-headless --rom=<path> [--mode=] [--quirks=] [--ipf=] [--frames=<count>]
+                   [--input=<script>] [--pbm=<path>] [--png=<path>] [--json=<path>]
+                   [--trace=<path>] [--trace-range=<start>-<end>] [--trace-limit=<lines>]
+
+    The run succeeds once the program exits through 00FD or parks itself on a
+    jump to its own address, which is how most test roms end. Running out of
+    frames first is a timeout.
+*/
+
+const EXIT_SUCCESS: u8 = 0;
+const EXIT_EMULATOR_ERROR: u8 = 1;
+const EXIT_TIMEOUT: u8 = 2;
+const EXIT_USAGE: u8 = 3;
+
+const DEFAULT_FRAMES: usize = 600;
+
+// Same colors as the window, indexed by the bitplanes set in a pixel
+const PALETTE: [u8; 12] = [
+    0x00, 0x00, 0x00, // off
+    0x31, 0xfe, 0x65, // plane 1
+    0xfe, 0x65, 0x31, // plane 2
+    0xff, 0xff, 0xff, // both planes
+];
+
+struct KeyEvent {
+    frame: usize,
+    key_code: u8,
+    down: bool,
+}
+
+struct ScriptInput {
+    /* Replays the key script, one frame per poll
+     */
+    events: Vec<KeyEvent>,
+    frame: usize,
+    pressed: u16,
+}
+
+impl chip8::InputSource for ScriptInput {
+    fn poll_keys(&mut self) -> u16 {
+        for event in self.events.iter().filter(|event| event.frame == self.frame) {
+            if event.down {
+                self.pressed |= 1 << event.key_code;
+            } else {
+                self.pressed &= !(1 << event.key_code);
+            }
+        }
+        self.frame += 1;
+        return self.pressed;
+    }
+}
+
+fn parse_script(script: &str) -> Result<Vec<KeyEvent>, String> {
+    /*  One event per line, `<frame> press <key>` or `<frame> release <key>`
+        with the key in hex. Blank lines and lines starting with # are ignored
+    */
+    let mut events = Vec::new();
+    for (number, line) in script.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let error = || {
+            format!(
+                "line {}: expected `<frame> press|release <key>`",
+                number + 1
+            )
+        };
+        let words: Vec<&str> = line.split_whitespace().collect();
+        if words.len() != 3 {
+            return Err(error());
+        }
+        let frame = words[0].parse().map_err(|_| error())?;
+        let down = match words[1] {
+            "press" => true,
+            "release" => false,
+            _ => return Err(error()),
+        };
+        let key_code = u8::from_str_radix(words[2], 16)
+            .ok()
+            .filter(|&key_code| key_code < 16)
+            .ok_or_else(error)?;
+        events.push(KeyEvent {
+            frame,
+            key_code,
+            down,
+        });
+    }
+    return Ok(events);
+}
+
+fn parse_range(text: &str) -> Option<RangeInclusive<u16>> {
+    /*  Two hexadecimal addresses, with or without the 0x prefix, joined by -
+     */
+    let address = |text: &str| u16::from_str_radix(text.strip_prefix("0x").unwrap_or(text), 16);
+    let (start, end) = text.split_once('-')?;
+    return Some(address(start).ok()?..=address(end).ok()?);
+}
+
+fn parked(c8: &chip8::Chip8) -> bool {
+    /*  Whether the next instruction is a jump to itself
+     */
+    let pc = c8.cpu.program_counter as usize;
+    let memory = &c8.memory.primary_memory;
+    if pc + 1 >= memory.len() {
+        return false;
+    }
+    let instruction = (memory[pc] as u16) << 8 | memory[pc + 1] as u16;
+    return instruction == 0x1000 | pc as u16;
+}
+
+fn write_pbm(c8: &chip8::Chip8, path: &str) -> std::io::Result<()> {
+    /*  Plain PBM, a pixel is black when any of its planes is set
+     */
+    let (width, height) = (c8.display.width(), c8.display.height());
+    let mut pbm = format!("P1\n{} {}\n", width, height);
+    for row in c8.display.buffer[..width * height].chunks(width) {
+        let pixels: Vec<&str> = row
+            .iter()
+            .map(|&pixel| if pixel != 0 { "1" } else { "0" })
+            .collect();
+        pbm.push_str(&pixels.join(" "));
+        pbm.push('\n');
+    }
+    return fs::write(path, pbm);
+}
+
+fn write_png(c8: &chip8::Chip8, path: &str) -> Result<(), png::EncodingError> {
+    let (width, height) = (c8.display.width(), c8.display.height());
+    let mut encoder = png::Encoder::new(
+        BufWriter::new(File::create(path)?),
+        width as u32,
+        height as u32,
+    );
+    encoder.set_color(png::ColorType::Indexed);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.set_palette(&PALETTE[..]);
+    let mut writer = encoder.write_header()?;
+    let pixels: Vec<u8> = c8.display.buffer[..width * height]
+        .iter()
+        .map(|&pixel| pixel & 0b11)
+        .collect();
+    writer.write_image_data(&pixels)?;
+    return Ok(());
+}
+
+fn cpu_json(c8: &chip8::Chip8, frames: usize, status: &str) -> String {
+    let join = |values: Vec<String>| values.join(", ");
+    let registers = c8
+        .cpu
+        .registers
+        .iter()
+        .map(|register| register.to_string())
+        .collect();
+    // The stack pointer starts at 255 and wraps to 0 on the first call
+    let depth = c8.cpu.stack_pointer.wrapping_add(1) as usize;
+    let stack = c8.memory.stack[..depth.min(c8.memory.stack.len())]
+        .iter()
+        .map(|address| address.to_string())
+        .collect();
+    return format!(
+        "{{\n  \"status\": \"{}\",\n  \"frames\": {},\n  \"pc\": {},\n  \"i\": {},\n  \"sp\": {},\n  \"instruction\": {},\n  \"delay_timer\": {},\n  \"sound_timer\": {},\n  \"registers\": [{}],\n  \"stack\": [{}]\n}}\n",
+        status,
+        frames,
+        c8.cpu.program_counter,
+        c8.cpu.i,
+        c8.cpu.stack_pointer,
+        c8.cpu.current_instruction,
+        c8.cpu.delay_timer,
+        c8.cpu.sound_timer,
+        join(registers),
+        join(stack),
+    );
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().collect();
+
+    let mut rom_path = None;
+    let mut mode = chip8::Mode::Chip8;
+    let mut quirks = None;
+    let mut instructions_per_frame = None;
+    let mut frames = DEFAULT_FRAMES;
+    let mut script_path = None;
+    let mut pbm_path = None;
+    let mut png_path = None;
+    let mut json_path = None;
+    let mut trace_path = None;
+    let mut trace_range = None;
+    let mut trace_limit = None;
+
+    for arg in args.iter().skip(1) {
+        let parsed = if let Some(path) = arg.strip_prefix("--rom=") {
+            rom_path = Some(path);
+            Ok(())
+        } else if let Some(name) = arg.strip_prefix("--mode=") {
+            chip8::Mode::from_name(name)
+                .map(|m| mode = m)
+                .ok_or("unknown mode")
+        } else if let Some(name) = arg.strip_prefix("--quirks=") {
+            chip8::Quirks::from_name(name)
+                .map(|q| quirks = Some(q))
+                .ok_or("unknown quirks profile")
+        } else if let Some(ipf) = arg.strip_prefix("--ipf=") {
+            ipf.parse()
+                .map(|ipf| instructions_per_frame = Some(ipf))
+                .map_err(|_| "invalid instructions per frame")
+        } else if let Some(count) = arg.strip_prefix("--frames=") {
+            count
+                .parse()
+                .map(|count| frames = count)
+                .map_err(|_| "invalid frame count")
+        } else if let Some(path) = arg.strip_prefix("--input=") {
+            script_path = Some(path);
+            Ok(())
+        } else if let Some(path) = arg.strip_prefix("--pbm=") {
+            pbm_path = Some(path);
+            Ok(())
+        } else if let Some(path) = arg.strip_prefix("--png=") {
+            png_path = Some(path);
+            Ok(())
+        } else if let Some(path) = arg.strip_prefix("--json=") {
+            json_path = Some(path);
+            Ok(())
+        } else if let Some(path) = arg.strip_prefix("--trace=") {
+            trace_path = Some(path);
+            Ok(())
+        } else if let Some(range) = arg.strip_prefix("--trace-range=") {
+            parse_range(range)
+                .map(|range| trace_range = Some(range))
+                .ok_or("invalid trace range, expected <start>-<end>")
+        } else if let Some(lines) = arg.strip_prefix("--trace-limit=") {
+            lines
+                .parse()
+                .map(|lines| trace_limit = Some(lines))
+                .map_err(|_| "invalid trace line limit")
+        } else {
+            Err("unknown argument")
+        };
+        if let Err(error) = parsed {
+            eprintln!("{}: {}", error, arg);
+            return ExitCode::from(EXIT_USAGE);
+        }
+    }
+
+    let Some(rom_path) = rom_path else {
+        eprintln!("no rom given, pass --rom=<path>");
+        return ExitCode::from(EXIT_USAGE);
+    };
+    let rom = match fs::read(rom_path) {
+        Ok(rom) => rom,
+        Err(error) => {
+            eprintln!("unable to read {}: {}", rom_path, error);
+            return ExitCode::from(EXIT_USAGE);
+        }
+    };
+    let events = match script_path.map(fs::read_to_string).transpose() {
+        Ok(script) => match parse_script(&script.unwrap_or_default()) {
+            Ok(events) => events,
+            Err(error) => {
+                eprintln!("{}: {}", script_path.unwrap(), error);
+                return ExitCode::from(EXIT_USAGE);
+            }
+        },
+        Err(error) => {
+            eprintln!("unable to read {}: {}", script_path.unwrap(), error);
+            return ExitCode::from(EXIT_USAGE);
+        }
+    };
+
+    let quirks = quirks.unwrap_or(mode.default_quirks());
+    let instructions_per_frame =
+        instructions_per_frame.unwrap_or(mode.default_instructions_per_frame());
+    let mut c8 = chip8::Chip8::new(mode, quirks);
+    c8.memory.load_sprites();
+    if let Err(error) = c8.load_program(&rom) {
+        eprintln!("unable to load {}: {}", rom_path, error);
+        return ExitCode::from(EXIT_USAGE);
+    }
+    if let Some(path) = trace_path {
+        match chip8::Tracer::create(path) {
+            Ok(mut tracer) => {
+                if let Some(range) = trace_range {
+                    tracer.pc_range = range;
+                }
+                tracer.line_limit = trace_limit;
+                c8.tracer = Some(tracer);
+            }
+            Err(error) => {
+                eprintln!("unable to create {}: {}", path, error);
+                return ExitCode::from(EXIT_USAGE);
+            }
+        }
+    }
+
+    let mut input = ScriptInput {
+        events,
+        frame: 0,
+        pressed: 0x0,
+    };
+    let mut frame = 0;
+    let (status, code) = loop {
+        if c8.exited || parked(&c8) {
+            break ("success".to_string(), EXIT_SUCCESS);
+        }
+        if frame == frames {
+            break ("timeout".to_string(), EXIT_TIMEOUT);
+        }
+        if let Err(error) =
+            c8.run_host_frame(&mut input, &mut chip8::NullVideo, instructions_per_frame)
+        {
+            eprintln!("Emulation halted: {}", error);
+            break (format!("error: {}", error), EXIT_EMULATOR_ERROR);
+        }
+        frame += 1;
+    };
+
+    if let Some(Err(error)) = c8.tracer.as_mut().map(chip8::Tracer::finish) {
+        eprintln!("unable to write {}: {}", trace_path.unwrap(), error);
+        return ExitCode::from(EXIT_USAGE);
+    }
+    if let Some(path) = pbm_path {
+        if let Err(error) = write_pbm(&c8, path) {
+            eprintln!("unable to write {}: {}", path, error);
+            return ExitCode::from(EXIT_USAGE);
+        }
+    }
+    if let Some(path) = png_path {
+        if let Err(error) = write_png(&c8, path) {
+            eprintln!("unable to write {}: {}", path, error);
+            return ExitCode::from(EXIT_USAGE);
+        }
+    }
+    let json = cpu_json(&c8, frame, &status);
+    match json_path {
+        Some(path) => {
+            if let Err(error) = fs::write(path, json) {
+                eprintln!("unable to write {}: {}", path, error);
+                return ExitCode::from(EXIT_USAGE);
+            }
+        }
+        None => print!("{}", json),
+    }
+    return ExitCode::from(code);
+}
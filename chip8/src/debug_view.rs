@@ -0,0 +1,249 @@
+use std::collections::BTreeMap;
+use std::fs;
+
+use minifb::Key;
+
+use crate::StatusText;
+
+// Rows of the panel under the screen, each TEXT_ROW pixels tall
+const TEXT_ROW: usize = 12;
+pub const PANEL_HEIGHT: usize = 20 * TEXT_ROW + 10;
+
+// Narrowest window the panel fits in
+pub const PANEL_WIDTH: usize = 640;
+
+// Instructions shown in the disassembly view, and rows of 8 bytes in the memory view
+const DISASSEMBLY_ROWS: usize = 12;
+const MEMORY_ROWS: usize = 6;
+const MEMORY_ROW_BYTES: usize = 8;
+
+// Characters that fit on a row spanning the panel, 8 pixels each
+const ROW_CHARS: usize = (PANEL_WIDTH - 20) / 8;
+
+// Where the right hand column of the panel starts
+const RIGHT_COLUMN: usize = 330;
+
+pub struct DebugView {
+    /* The debugger panel drawn under the screen with -d, and its keybindings
+     */
+    // Labels from the assembler's symbol map, by address
+    symbols: BTreeMap<u16, String>,
+
+    // Where the disassembly view is centered, follows the pc unless moved while paused
+    cursor: u16,
+
+    // First address shown in the memory view
+    memory_view: usize,
+    text: StatusText,
+}
+
+impl DebugView {
+    pub fn new(rom_path: &str, width: usize, height: usize) -> DebugView {
+        /*  Picks up the symbol map chip8-asm writes next to the rom, if there is one
+         */
+        let mut symbols = BTreeMap::new();
+        if let Ok(map) = fs::read_to_string(format!("{}.sym", rom_path)) {
+            for line in map.lines() {
+                if let Some((address, name)) = line.split_once(' ') {
+                    let address = address.trim_start_matches("0x").trim_start_matches("0X");
+                    if let Ok(address) = u16::from_str_radix(address, 16) {
+                        symbols.insert(address, name.trim().to_string());
+                    }
+                }
+            }
+        }
+        return DebugView {
+            symbols,
+            cursor: 0x200,
+            memory_view: 0x200,
+            text: StatusText::new(width, height, 1),
+        };
+    }
+
+    fn symbolize(&self, address: u16) -> String {
+        /*  The address relative to the nearest label before it, e.g. main+0x1A
+         */
+        match self.symbols.range(..=address).next_back() {
+            Some((&label, name)) if label == address => name.clone(),
+            Some((&label, name)) => format!("{}+{:#X}", name, address - label),
+            None => format!("{:#05X}", address),
+        }
+    }
+
+    pub fn handle_key(
+        &mut self,
+        key: Key,
+        c8: &mut chip8::Chip8,
+        debugger: &mut chip8::Debugger,
+    ) -> Result<(), chip8::Chip8Error> {
+        match key {
+            Key::F9 => {
+                if debugger.stopped.is_some() {
+                    debugger.resume(c8);
+                } else {
+                    debugger.pause();
+                }
+            }
+            Key::F10 => {
+                debugger.step_over(c8)?;
+            }
+            Key::F11 => {
+                debugger.step(c8)?;
+            }
+            Key::Up => self.cursor = self.cursor.saturating_sub(2),
+            Key::Down => self.cursor = self.cursor.saturating_add(2),
+            Key::B => debugger.toggle_breakpoint(self.cursor),
+            Key::PageUp => self.memory_view = self.memory_view.saturating_sub(MEMORY_ROW_BYTES),
+            Key::PageDown => {
+                let last = c8.memory.primary_memory.len() - MEMORY_ROW_BYTES * MEMORY_ROWS;
+                self.memory_view = (self.memory_view + MEMORY_ROW_BYTES).min(last);
+            }
+            Key::I => {
+                let last = c8.memory.primary_memory.len() - MEMORY_ROW_BYTES * MEMORY_ROWS;
+                self.memory_view =
+                    (c8.cpu.i as usize / MEMORY_ROW_BYTES * MEMORY_ROW_BYTES).min(last);
+            }
+            Key::M => {
+                debugger.toggle_watchpoint(self.memory_view..self.memory_view + MEMORY_ROW_BYTES)
+            }
+            _ => {}
+        }
+        return Ok(());
+    }
+
+    pub fn draw(
+        &mut self,
+        buffer: &mut [u32],
+        top: usize,
+        c8: &chip8::Chip8,
+        debugger: &chip8::Debugger,
+        halted: bool,
+    ) {
+        if debugger.stopped.is_none() {
+            self.cursor = c8.cpu.program_counter;
+        }
+        let row = |index: usize| top + 10 + index * TEXT_ROW;
+
+        let status = match &debugger.stopped {
+            _ if halted => "HALTED".to_string(),
+            None => "RUNNING".to_string(),
+            Some(chip8::Stop::User) => "PAUSED".to_string(),
+            Some(chip8::Stop::Breakpoint(address)) => {
+                format!("PAUSED: breakpoint at {}", self.symbolize(*address))
+            }
+            Some(chip8::Stop::Watchpoint { address, write }) => format!(
+                "PAUSED: {} wrote {:#05X}-{:#05X}",
+                self.symbolize(*address),
+                write.start,
+                write.end - 1
+            ),
+        };
+        self.text.draw(buffer, (10, row(0)), &status);
+        let registers = format!(
+            "PC {:#05X}  I {:#05X}  SP {:#04X}  DT {:#04X}  ST {:#04X}  Keys {}",
+            c8.cpu.program_counter,
+            c8.cpu.i,
+            c8.cpu.stack_pointer,
+            c8.cpu.delay_timer,
+            c8.cpu.sound_timer,
+            (0..16)
+                .filter(|&key_code| c8.keyboard.is_key_pressed(key_code))
+                .map(|key_code| format!("{:X}", key_code))
+                .collect::<Vec<String>>()
+                .join(" ")
+        );
+        // With many keys held the row is wider than the panel
+        let registers: String = registers.chars().take(ROW_CHARS).collect();
+        self.text.draw(buffer, (10, row(1)), &registers);
+        for (line, registers) in c8.cpu.registers.chunks(8).enumerate() {
+            let values: Vec<String> = registers.iter().map(|v| format!("{:02X}", v)).collect();
+            let first = line * 8;
+            self.text.draw(
+                buffer,
+                (10, row(2 + line)),
+                &format!("V{:X}-V{:X}  {}", first, first + 7, values.join(" ")),
+            );
+        }
+
+        // Disassembly around the cursor, with > on the pc and * on breakpoints
+        self.text.draw(buffer, (10, row(5)), "Disassembly");
+        let memory = &c8.memory.primary_memory;
+        let mut address = self
+            .cursor
+            .saturating_sub(2 * (DISASSEMBLY_ROWS as u16 / 3));
+        let mut line = 0;
+        while line < DISASSEMBLY_ROWS && (address as usize) < memory.len() {
+            if let Some(name) = self.symbols.get(&address) {
+                let label: String = format!("{}:", name).chars().take(40).collect();
+                self.text.draw(buffer, (10, row(6 + line)), &label);
+                line += 1;
+                if line == DISASSEMBLY_ROWS {
+                    break;
+                }
+            }
+            let (text, len) = chip8::disassemble_at(memory, address, c8.mode, chip8::Syntax::Octo)
+                .unwrap_or(("??".to_string(), 2));
+            let marker = if address == c8.cpu.program_counter {
+                '>'
+            } else if address == self.cursor {
+                '-'
+            } else {
+                ' '
+            };
+            let breakpoint = if debugger.breakpoints.contains(&address) {
+                '*'
+            } else {
+                ' '
+            };
+            let text: String = text.chars().take(28).collect();
+            self.text.draw(
+                buffer,
+                (10, row(6 + line)),
+                &format!("{}{} {:#05X} {}", breakpoint, marker, address, text),
+            );
+            address = address.wrapping_add(len);
+            line += 1;
+        }
+
+        // Return addresses on the stack, innermost first
+        self.text.draw(buffer, (RIGHT_COLUMN, row(5)), "Call stack");
+        let depth = (c8.cpu.stack_pointer.wrapping_add(1) as usize).min(c8.memory.stack.len());
+        for (line, &address) in c8.memory.stack[..depth].iter().rev().take(4).enumerate() {
+            let frame = format!("{:#05X} {}", address, self.symbolize(address));
+            let frame: String = frame.chars().take(38).collect();
+            self.text
+                .draw(buffer, (RIGHT_COLUMN, row(6 + line)), &frame);
+        }
+
+        // Memory, with w on rows that are watched
+        self.text.draw(buffer, (RIGHT_COLUMN, row(11)), "Memory");
+        for line in 0..MEMORY_ROWS {
+            let start = self.memory_view + line * MEMORY_ROW_BYTES;
+            let end = (start + MEMORY_ROW_BYTES).min(memory.len());
+            let values: Vec<String> = memory[start..end]
+                .iter()
+                .map(|v| format!("{:02X}", v))
+                .collect();
+            let watched = debugger
+                .watchpoints
+                .iter()
+                .any(|range| range.start < end && start < range.end);
+            self.text.draw(
+                buffer,
+                (RIGHT_COLUMN, row(12 + line)),
+                &format!(
+                    "{:#06X} {} {}",
+                    start,
+                    values.join(" "),
+                    if watched { 'w' } else { ' ' }
+                ),
+            );
+        }
+
+        self.text.draw(
+            buffer,
+            (10, row(19)),
+            "F9 run/pause F10 over F11 step Up/Down B break PgUp/PgDn I M watch",
+        );
+    }
+}
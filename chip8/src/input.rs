@@ -0,0 +1,138 @@
+use std::fs;
+
+use minifb::Key;
+use toml::{Table, Value};
+
+pub fn key_name(key: Key) -> String {
+    /*  The name a minifb key goes by in the keymap, its variant name with
+        the number row shortened to the digit, e.g. Key1 is 1
+    */
+    let name = format!("{:?}", key);
+    return match name.strip_prefix("Key") {
+        Some(digit) if digit.len() == 1 => digit.to_string(),
+        _ => name,
+    };
+}
+
+pub struct WindowInput<'a> {
+    /* The keys held down in the window, through the keymap
+     */
+    pub window: &'a minifb::Window,
+    pub keymap: &'a chip8::Keymap,
+}
+
+impl chip8::InputSource for WindowInput<'_> {
+    fn poll_keys(&mut self) -> u16 {
+        let held: Vec<String> = self
+            .window
+            .get_keys()
+            .unwrap_or_default()
+            .into_iter()
+            .map(key_name)
+            .collect();
+        return self.keymap.pressed(held.iter().map(String::as_str));
+    }
+}
+
+fn apply_bindings(keymap: &mut chip8::Keymap, keys: &Value) -> Result<(), String> {
+    /*  Binds each hex key in the table to the host key, or list of host keys, given for it
+     */
+    let Value::Table(keys) = keys else {
+        return Err("keys must be a table".to_string());
+    };
+    for (hex, host_keys) in keys {
+        let not_hex_key = format!("{} is not a hex key", hex);
+        let key_code = u8::from_str_radix(hex, 16).map_err(|_| not_hex_key.clone())?;
+        let names: Option<Vec<&str>> = match host_keys {
+            Value::String(name) => Some(vec![name.as_str()]),
+            Value::Array(names) => names.iter().map(Value::as_str).collect(),
+            _ => None,
+        };
+        let names = names.ok_or(format!("key {} must be a key name or a list of them", hex))?;
+        keymap.bind(key_code, &names).map_err(|_| not_hex_key)?;
+    }
+    return Ok(());
+}
+
+pub fn load_keymap(path: &str, rom_hash: u64, keymap: &mut chip8::Keymap) -> Result<(), String> {
+    /*  Applies the [keys] table of a config file, then the overrides for the
+        rom in [roms.<hash>.keys], with the rom hash as 16 hex digits, e.g.
+            [keys]
+            7 = "Q"
+            A = ["W", "NumPad0"]
+
+            [roms.0123456789abcdef.keys]
+            4 = "Left"
+            6 = "Right"
+        Hex keys left out keep their bindings
+    */
+    let text = fs::read_to_string(path).map_err(|error| error.to_string())?;
+    return apply_config(&text, rom_hash, keymap);
+}
+
+fn apply_config(text: &str, rom_hash: u64, keymap: &mut chip8::Keymap) -> Result<(), String> {
+    let config: Table = text
+        .parse()
+        .map_err(|error: toml::de::Error| error.to_string())?;
+    if let Some(keys) = config.get("keys") {
+        apply_bindings(keymap, keys)?;
+    }
+    let rom_keys = config
+        .get("roms")
+        .and_then(|roms| roms.get(format!("{:016x}", rom_hash)))
+        .and_then(|rom| rom.get("keys"));
+    if let Some(keys) = rom_keys {
+        apply_bindings(keymap, keys)?;
+    }
+    return Ok(());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = r#"
+        [keys]
+        7 = "Up"
+        A = ["W", "NumPad0"]
+
+        [roms.00000000000000ff.keys]
+        7 = "Left"
+    "#;
+
+    #[test]
+    fn keys_table_rebinds_only_the_keys_listed() {
+        let mut keymap = chip8::Keymap::new();
+        apply_config(CONFIG, 0x1, &mut keymap).unwrap();
+        assert_eq!(keymap.key_code("Up"), Some(0x7));
+        assert_eq!(keymap.key_code("A"), None);
+        assert_eq!(keymap.key_code("W"), Some(0xA));
+        assert_eq!(keymap.key_code("NumPad0"), Some(0xA));
+        assert_eq!(keymap.key_code("Z"), None);
+        assert_eq!(keymap.key_code("Q"), Some(0x4));
+    }
+
+    #[test]
+    fn rom_table_overrides_for_that_rom_hash() {
+        let mut keymap = chip8::Keymap::new();
+        apply_config(CONFIG, 0xFF, &mut keymap).unwrap();
+        assert_eq!(keymap.key_code("Left"), Some(0x7));
+        assert_eq!(keymap.key_code("Up"), None);
+        assert_eq!(keymap.key_code("W"), Some(0xA));
+    }
+
+    #[test]
+    fn invalid_tables_are_reported() {
+        let mut keymap = chip8::Keymap::new();
+        let error = apply_config("[keys]\n10 = \"Up\"", 0, &mut keymap);
+        assert_eq!(error, Err("10 is not a hex key".to_string()));
+        let error = apply_config("[keys]\nG = \"Up\"", 0, &mut keymap);
+        assert_eq!(error, Err("G is not a hex key".to_string()));
+        let error = apply_config("[keys]\n1 = 2", 0, &mut keymap);
+        assert_eq!(
+            error,
+            Err("key 1 must be a key name or a list of them".to_string())
+        );
+        assert!(apply_config("keys = 1", 0, &mut keymap).is_err());
+    }
+}
@@ -1,21 +1,50 @@
-use minifb;
+#![allow(clippy::needless_return)]
+
 use std::env;
 use std::fs;
-use std::io::Read;
+use std::process;
+use std::thread;
 use std::time::{Duration, Instant};
 
-mod chip8;
-
-const PIXEL_ON: u32 = 0x31fe65;
-const PIXEL_OFF: u32 = 0x000000;
+mod audio;
+mod debug_view;
+mod input;
+mod phosphor;
+mod screen;
 
 const FONT_ON: u32 = 0xFFA500;
 const FONT_OFF: u32 = 0x000000;
 
-const SCALE: usize = 2;
+// The emulated machine runs, and ticks its timers, at 60 Hz
+const FRAME_DURATION: Duration = Duration::from_micros(16_667);
+
+// Further behind than this, e.g. after the window was dragged, the emulation
+// skips ahead instead of racing to catch up
+const MAX_LAG: Duration = Duration::from_millis(250);
+
+// F1 to F4 save to slots 1 to 4, F5 to F8 load them back
+const SAVE_KEYS: [minifb::Key; 4] = [
+    minifb::Key::F1,
+    minifb::Key::F2,
+    minifb::Key::F3,
+    minifb::Key::F4,
+];
+const LOAD_KEYS: [minifb::Key; 4] = [
+    minifb::Key::F5,
+    minifb::Key::F6,
+    minifb::Key::F7,
+    minifb::Key::F8,
+];
+
+// Held to run the emulation backwards
+const REWIND_KEY: minifb::Key = minifb::Key::Backspace;
+const DEFAULT_REWIND_SECONDS: usize = 10;
+
+// Cycles through the flicker filters
+const FILTER_KEY: minifb::Key = minifb::Key::F12;
 
-const WIDTH: usize = 640;
-const HEIGHT: usize = 320;
+// Read for the display options when --config is not given, if it exists
+const DEFAULT_CONFIG: &str = "chip8.toml";
 
 fn color_from_bit(bit: u8) -> u32 {
     if bit == 0 {
@@ -55,13 +84,19 @@ impl StatusText {
     }
 
     pub fn draw(&self, screen: &mut [u32], pos: (usize, usize), text: &str) {
+        /*  Characters that would run past the right edge are dropped, any
+            without a glyph are drawn as a space
+        */
         let mut x = pos.0;
         let y = pos.1;
         for c in text.chars() {
-            let mut index = c as usize - ' ' as usize;
-            if index > MICROKNIGHT_LAYOUT.len() as usize {
-                index = 0;
+            if x + 8 * self.scale > self.width || screen.len() < (y + 8 * self.scale) * self.width {
+                break;
             }
+            let index = (c as usize)
+                .checked_sub(' ' as usize)
+                .filter(|&index| index < MICROKNIGHT_LAYOUT.len())
+                .unwrap_or(0);
 
             let layout = MICROKNIGHT_LAYOUT[index];
             let texture_offset = (layout.1 as usize * 128) + layout.0 as usize;
@@ -80,163 +115,344 @@ impl StatusText {
         }
     }
 }
+fn parse_address(text: &str) -> Option<usize> {
+    /*  A hexadecimal address, with or without the 0x prefix
+     */
+    let digits = text.strip_prefix("0x").unwrap_or(text);
+    return usize::from_str_radix(digits, 16).ok();
+}
+
 fn main() {
     let args: Vec<String> = env::args().collect();
 
     let mut rom_path = "default";
     let mut debug = false;
+    let mut mode = chip8::Mode::Chip8;
+    let mut quirks = None;
+    let mut wav_path = None;
+    let mut mute = false;
+    let mut rewind_seconds = DEFAULT_REWIND_SECONDS;
+    let mut instructions_per_frame = None;
+    let mut debugger = chip8::Debugger::new();
+    let mut gdb_port = None;
+    let mut trace_path = None;
+    let mut trace_range = None;
+    let mut trace_limit = None;
+
+    // The config file is applied first so the command line can override it
+    let mut screen_options = screen::ScreenOptions::new();
+    let config_path = match args.iter().find_map(|arg| arg.strip_prefix("--config=")) {
+        Some(path) => Some(path),
+        None if fs::metadata(DEFAULT_CONFIG).is_ok() => Some(DEFAULT_CONFIG),
+        None => None,
+    };
+    if let Some(path) = config_path {
+        screen_options
+            .load_config(path)
+            .unwrap_or_else(|error| panic!("invalid config {}: {}", path, error));
+    }
 
     for arg in args.iter() {
-        if arg.starts_with("--rom=") {
-            rom_path = &arg[6..]
+        if let Some(path) = arg.strip_prefix("--rom=") {
+            rom_path = path;
+        }
+        if let Some(name) = arg.strip_prefix("--mode=") {
+            mode = chip8::Mode::from_name(name).expect("unknown mode");
+        }
+        if let Some(name) = arg.strip_prefix("--quirks=") {
+            quirks = Some(chip8::Quirks::from_name(name).expect("unknown quirks profile"));
+        }
+        if let Some(ipf) = arg.strip_prefix("--ipf=") {
+            instructions_per_frame = Some(ipf.parse().expect("invalid instructions per frame"));
+        }
+        if let Some(path) = arg.strip_prefix("--wav=") {
+            wav_path = Some(path);
+        }
+        if let Some(seconds) = arg.strip_prefix("--rewind=") {
+            rewind_seconds = seconds.parse().expect("invalid rewind seconds");
+        }
+        if let Some(address) = arg.strip_prefix("--break=") {
+            let address = parse_address(address).expect("invalid breakpoint address");
+            debugger.breakpoints.insert(address as u16);
+        }
+        if let Some(range) = arg.strip_prefix("--watch=") {
+            let (start, end) = range
+                .split_once('-')
+                .and_then(|(start, end)| Some((parse_address(start)?, parse_address(end)?)))
+                .expect("invalid watchpoint range, expected <start>-<end>");
+            debugger.watchpoints.push(start..end + 1);
+        }
+        if let Some(port) = arg.strip_prefix("--gdb=") {
+            gdb_port = Some(port.parse::<u16>().expect("invalid gdb port"));
+        }
+        if let Some(path) = arg.strip_prefix("--trace=") {
+            trace_path = Some(path);
+        }
+        if let Some(range) = arg.strip_prefix("--trace-range=") {
+            let (start, end) = range
+                .split_once('-')
+                .and_then(|(start, end)| Some((parse_address(start)?, parse_address(end)?)))
+                .expect("invalid trace range, expected <start>-<end>");
+            trace_range = Some(start as u16..=end as u16);
+        }
+        if let Some(lines) = arg.strip_prefix("--trace-limit=") {
+            trace_limit = Some(lines.parse().expect("invalid trace line limit"));
+        }
+        if let Some(color) = arg.strip_prefix("--fg=") {
+            screen_options.palette[1] =
+                screen::parse_color(color).expect("invalid foreground color");
+        }
+        if let Some(color) = arg.strip_prefix("--bg=") {
+            screen_options.palette[0] =
+                screen::parse_color(color).expect("invalid background color");
+        }
+        if let Some(colors) = arg.strip_prefix("--palette=") {
+            screen_options.palette =
+                screen::parse_palette(colors).expect("invalid palette, expected 4 colors");
+        }
+        if let Some(scale) = arg.strip_prefix("--scale=") {
+            screen_options.scale = scale
+                .parse()
+                .ok()
+                .filter(|&scale| scale > 0)
+                .expect("invalid scale");
+        }
+        if let Some(name) = arg.strip_prefix("--filter=") {
+            screen_options.filter = phosphor::FilterMode::from_name(name).expect("unknown filter");
+        }
+        if let Some(frames) = arg.strip_prefix("--phosphor-frames=") {
+            screen_options.phosphor_frames = frames
+                .parse()
+                .ok()
+                .filter(|&frames| frames > 0)
+                .expect("invalid phosphor frames");
+        }
+        if arg == "--mute" {
+            mute = true;
+        }
+        if arg == "--lazy-redraw" {
+            screen_options.lazy_redraw = true;
+        }
+        if arg == "--fullscreen" {
+            screen_options.fullscreen = true;
         }
         if arg == "-d" {
             debug = true;
         }
     }
-    let mut c8 = chip8::Chip8::new();
+    let quirks = quirks.unwrap_or(mode.default_quirks());
+    let instructions_per_frame =
+        instructions_per_frame.unwrap_or(mode.default_instructions_per_frame());
+    let mut c8 = chip8::Chip8::new(mode, quirks);
+
+    // Recording to a file replaces playing it
+    if let Some(path) = wav_path {
+        let sink = chip8::WavSink::create(path).expect("unable to create wav file");
+        c8.speaker.set_sink(Box::new(sink));
+    } else if !mute {
+        c8.speaker.set_sink(audio::default_sink());
+    }
+
+    if let Some(path) = trace_path {
+        let mut tracer = chip8::Tracer::create(path).expect("unable to create trace file");
+        if let Some(range) = trace_range {
+            tracer.pc_range = range;
+        }
+        tracer.line_limit = trace_limit;
+        c8.tracer = Some(tracer);
+    }
 
     c8.memory.load_sprites();
 
-    if rom_path == "default" {
-        c8.memory.load_program(&C8_ROM);
+    let loaded = if rom_path == "default" {
+        c8.load_program(&C8_ROM)
     } else {
-        let mut f = fs::File::open(&rom_path).expect("no file found");
-        let metadata = fs::metadata(&rom_path).expect("unable to read metadata");
-        let mut buffer = vec![0; metadata.len() as usize];
-        f.read(&mut buffer).expect("buffer overflow");
-        c8.memory.load_program(&buffer);
+        let buffer = fs::read(rom_path).expect("no file found");
+        c8.load_program(&buffer)
+    };
+    if let Err(error) = loaded {
+        eprintln!("Unable to load {}: {}", rom_path, error);
+        process::exit(1);
     }
+    println!("Loaded {} (hash {:016x})", rom_path, c8.rom_hash);
 
-    let mut last_key_update_time = Instant::now();
-    let mut last_instruction_run_time = Instant::now();
-    let mut last_display_time = Instant::now();
+    // Key bindings can be overridden per rom, so they are read once the rom hash is known
+    let mut keymap = chip8::Keymap::new();
+    if let Some(path) = config_path {
+        input::load_keymap(path, c8.rom_hash, &mut keymap)
+            .unwrap_or_else(|error| panic!("invalid config {}: {}", path, error));
+    }
 
+    // The RPL user flags live next to the rom so they survive restarts
+    let rpl_path = format!("{}.rpl", rom_path);
+    if let Ok(flags) = fs::read(&rpl_path) {
+        let count = flags.len().min(c8.rpl_flags.len());
+        c8.rpl_flags[..count].copy_from_slice(&flags[..count]);
+    }
+    let saved_rpl_flags = c8.rpl_flags;
+
+    // Frames the player can rewind through, starting from the freshly loaded program
+    let mut rewind = chip8::Rewind::new(rewind_seconds * 60);
+    rewind.push(c8.save_state());
+
+    // Debugging over the GDB remote protocol, the machine pauses whenever a client attaches
+    let mut gdb = gdb_port.map(|port| {
+        let stub = chip8::GdbStub::bind(port).expect("unable to listen for gdb");
+        println!("Waiting for gdb on 127.0.0.1:{}", port);
+        stub
+    });
+
+    // Set when the program faults, the last screen stays up but nothing more runs
+    let mut halted = false;
+
+    // The debugger panel needs a fixed layout, so the window only resizes without it
+    let (screen_width, screen_height) = screen_options.window_size();
+    let (window_width, window_height) = if debug {
+        (
+            screen_width.max(debug_view::PANEL_WIDTH),
+            screen_height + debug_view::PANEL_HEIGHT,
+        )
+    } else {
+        (screen_width, screen_height)
+    };
     let mut window = minifb::Window::new(
         "rusty-retro: Chip8",
-        WIDTH,
-        if debug == true {
-            HEIGHT * 2 - 90
-        } else {
-            HEIGHT
+        window_width,
+        window_height,
+        minifb::WindowOptions {
+            borderless: screen_options.fullscreen,
+            title: !screen_options.fullscreen,
+            resize: !debug,
+            ..minifb::WindowOptions::default()
         },
-        minifb::WindowOptions::default(),
     )
     .unwrap();
 
-    let status_text = StatusText::new(WIDTH, HEIGHT, SCALE);
+    let mut debug_view = debug_view::DebugView::new(rom_path, window_width, window_height);
 
-    while window.is_open() && !window.is_key_down(minifb::Key::Escape) {
-        let keys_pressed = window.get_keys_pressed(minifb::KeyRepeat::Yes);
-        let key = match keys_pressed {
-            Some(keys) => {
-                if !keys.is_empty() {
-                    Some(keys[0])
-                } else {
-                    None
-                }
-            }
-            None => None,
-        };
+    let mut screen = screen::Screen::new(&screen_options);
+    let mut buffer: Vec<u32> = Vec::new();
+
+    // Set when something besides the display changes what the window shows
+    let mut redraw = true;
 
-        if c8.keyboard.get_key_code(key).is_some()
-            || Instant::now() - last_key_update_time >= Duration::from_millis(100)
+    /*  Each pass runs one 60 Hz frame of emulation and presents it, then sleeps
+        until the next one is due. minifb 0.13 has no vsync, so this is also
+        the rate the window is refreshed at
+    */
+    let mut next_frame = Instant::now();
+
+    while window.is_open() && !window.is_key_down(minifb::Key::Escape) && !c8.exited {
+        c8.poll_input(&mut input::WindowInput {
+            window: &window,
+            keymap: &keymap,
+        });
+
+        for key in window
+            .get_keys_pressed(minifb::KeyRepeat::No)
+            .unwrap_or_default()
         {
-            c8.keyboard.key_code = c8.keyboard.get_key_code(key);
-            c8.keyboard.key = key;
-            last_key_update_time = Instant::now();
-        }
-
-        if Instant::now() - last_instruction_run_time > Duration::from_millis(2) {
-            c8.emulate_cycle();
-            last_instruction_run_time = Instant::now();
-        }
-
-        if Instant::now() - last_display_time > Duration::from_millis(5) {
-            let mut buffer: Vec<u32> = vec![0; WIDTH * HEIGHT * 2 - 90];
-
-            for y in 0..HEIGHT {
-                let y_coord = y / 10;
-                let offset = y * WIDTH;
-                for x in 0..WIDTH {
-                    let index = y_coord * 64 + (x / 10);
-                    let pixel = c8.display.buffer[index];
-                    let color_pixel = match pixel {
-                        0 => PIXEL_OFF,
-                        1 => PIXEL_ON,
-                        _ => unreachable!(),
-                    };
-                    buffer[offset + x] = color_pixel;
+            if let Some(slot) = SAVE_KEYS.iter().position(|&k| k == key) {
+                let state_path = format!("{}.state{}", rom_path, slot + 1);
+                match fs::write(&state_path, c8.save_state()) {
+                    Ok(()) => println!("Saved state to {}", state_path),
+                    Err(error) => eprintln!("Unable to save {}: {}", state_path, error),
                 }
             }
+            if let Some(slot) = LOAD_KEYS.iter().position(|&k| k == key) {
+                let state_path = format!("{}.state{}", rom_path, slot + 1);
+                match fs::read(&state_path) {
+                    Ok(state) => match c8.load_state(&state) {
+                        Ok(()) => {
+                            println!("Loaded state from {}", state_path);
+                            halted = false;
+                        }
+                        Err(error) => eprintln!("Unable to load {}: {}", state_path, error),
+                    },
+                    Err(error) => eprintln!("Unable to read {}: {}", state_path, error),
+                }
+            }
+            if key == FILTER_KEY {
+                screen.filter.mode = screen.filter.mode.next();
+                println!("Filter: {}", screen.filter.mode.name());
+                redraw = true;
+            }
+            if debug && !halted {
+                if let Err(error) = debug_view.handle_key(key, &mut c8, &mut debugger) {
+                    eprintln!("Emulation halted: {}", error);
+                    halted = true;
+                }
+            }
+        }
 
-            if debug == true {
-                status_text.draw(
-                    &mut buffer,
-                    (20, HEIGHT + 20),
-                    &format!("Program counter: {:#X}", c8.cpu.program_counter),
-                );
-
-                status_text.draw(
-                    &mut buffer,
-                    (20, HEIGHT + 50),
-                    &format!("Current instruction: {:#X}", c8.cpu.current_instruction),
-                );
-
-                status_text.draw(
-                    &mut buffer,
-                    (20, HEIGHT + 80),
-                    &format!("Index Register: {:#X}", c8.cpu.i),
-                );
-
-                status_text.draw(
-                    &mut buffer,
-                    (20, HEIGHT + 110),
-                    &format!("Stack pointer: {:#X}", c8.cpu.stack_pointer),
-                );
-
-                status_text.draw(
-                    &mut buffer,
-                    (20, HEIGHT + 140),
-                    &format!(
-                        "{:#X} {:#X} {:#X} {:#X} {:#X} {:#X} {:#X} {:#X}",
-                        c8.cpu.registers[0],
-                        c8.cpu.registers[1],
-                        c8.cpu.registers[2],
-                        c8.cpu.registers[3],
-                        c8.cpu.registers[4],
-                        c8.cpu.registers[5],
-                        c8.cpu.registers[6],
-                        c8.cpu.registers[7]
-                    ),
-                );
-
-                status_text.draw(
-                    &mut buffer,
-                    (20, HEIGHT + 170),
-                    &format!(
-                        "{:#X} {:#X} {:#X} {:#X} {:#X} {:#X} {:#X} {:#X}",
-                        c8.cpu.registers[8],
-                        c8.cpu.registers[9],
-                        c8.cpu.registers[10],
-                        c8.cpu.registers[11],
-                        c8.cpu.registers[12],
-                        c8.cpu.registers[13],
-                        c8.cpu.registers[14],
-                        c8.cpu.registers[15]
-                    ),
-                );
-
-                status_text.draw(
-                    &mut buffer,
-                    (20, HEIGHT + 200),
-                    &format!("Key: {:?}", c8.keyboard.key),
-                );
+        if let Some(gdb) = &mut gdb {
+            if let Err(error) = gdb.poll(&mut c8, &mut debugger, halted) {
+                eprintln!("Emulation halted: {}", error);
+                halted = true;
             }
+        }
+
+        if window.is_key_down(REWIND_KEY) {
+            // Steps back a frame per frame, stopping at the oldest one kept
+            if let Some(state) = rewind.pop() {
+                c8.load_state(&state).expect("unable to rewind");
+                halted = false;
+            }
+        } else if !halted && debugger.stopped.is_none() {
+            match debugger.run_frame(&mut c8, instructions_per_frame) {
+                Ok(_) => rewind.push(c8.save_state()),
+                Err(error) => {
+                    eprintln!("Emulation halted: {}", error);
+                    halted = true;
+                }
+            }
+        }
 
+        let (width, height) = window.get_size();
+        if buffer.len() != width * height {
+            buffer.resize(width * height, 0);
+            redraw = true;
+        }
+        // The debugger panel changes with every step, so it is always drawn
+        if !screen_options.lazy_redraw
+            || debug
+            || redraw
+            || c8.display.dirty
+            || screen.filter.fading()
+        {
+            c8.present(&mut screen);
+            if debug {
+                screen::draw(&mut buffer, width, (width, screen_height), &screen.frame);
+                debug_view.draw(&mut buffer, screen_height, &c8, &debugger, halted);
+            } else {
+                screen::draw(&mut buffer, width, (width, height), &screen.frame);
+            }
             window.update_with_buffer(&buffer).unwrap();
-            last_display_time = Instant::now();
+            redraw = false;
+        } else {
+            window.update();
         }
+
+        next_frame += FRAME_DURATION;
+        let now = Instant::now();
+        if now > next_frame + MAX_LAG {
+            next_frame = now;
+        }
+        thread::sleep(next_frame.saturating_duration_since(now));
+    }
+
+    if c8.rpl_flags != saved_rpl_flags {
+        fs::write(&rpl_path, c8.rpl_flags).expect("unable to save rpl flags");
+    }
+    if let Some(Err(error)) = c8.tracer.as_mut().map(chip8::Tracer::finish) {
+        eprintln!("Unable to write the trace: {}", error);
+    }
+    if let Err(error) = c8.speaker.finish() {
+        eprintln!(
+            "Unable to finish {}: {}",
+            wav_path.unwrap_or("audio"),
+            error
+        );
     }
 }
 
@@ -614,3 +830,42 @@ const C8_ROM: [u8; 288] = [
     80, 95, 64, 127, 0, 84, 84, 212, 20, 244, 4, 252, 0, 42, 42, 43, 40, 47, 32, 63, 0, 42, 42,
     234, 10, 250, 2, 254, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
 ];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn status_text_stops_at_the_right_edge() {
+        /*  Three 8 pixel characters fit from x 10 on a 40 pixel row, a fourth
+            would spill into the next row
+        */
+        let text = StatusText::new(40, 8, 1);
+        let mut clipped = vec![1; 40 * 8];
+        text.draw(&mut clipped, (10, 0), "ABCDEFGH");
+        let mut fitting = vec![1; 40 * 8];
+        text.draw(&mut fitting, (10, 0), "ABC");
+        assert_eq!(clipped, fitting);
+        assert!(clipped.chunks(40).all(|row| row[34..] == [1; 6]));
+    }
+
+    #[test]
+    fn status_text_draws_nothing_below_the_bottom() {
+        let text = StatusText::new(40, 8, 1);
+        let mut screen = vec![0; 40 * 8];
+        text.draw(&mut screen, (0, 4), "A");
+        assert!(screen.iter().all(|&pixel| pixel == 0));
+    }
+
+    #[test]
+    fn characters_without_a_glyph_draw_as_spaces() {
+        let text = StatusText::new(64, 8, 1);
+        let mut space = vec![0; 64 * 8];
+        text.draw(&mut space, (0, 0), "  ");
+        for odd in ["\n\t", "\u{1}\u{2603}"] {
+            let mut screen = vec![0; 64 * 8];
+            text.draw(&mut screen, (0, 0), odd);
+            assert_eq!(screen, space);
+        }
+    }
+}
@@ -0,0 +1,215 @@
+use std::collections::VecDeque;
+
+use crate::screen::Frame;
+
+// Frames a pixel takes to fade out with the phosphor filter unless told otherwise
+pub const DEFAULT_PHOSPHOR_FRAMES: usize = 3;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FilterMode {
+    /* Post-processing that hides the flicker of sprites XORed off and back on
+     */
+    None,
+
+    // Lit pixels fade back to the background over the last few frames
+    Phosphor,
+
+    // A pixel lit in either of the last two frames stays lit
+    Or,
+}
+
+impl FilterMode {
+    pub fn from_name(name: &str) -> Option<FilterMode> {
+        /*  Looks up a filter by the name used on the command line and in the config file
+         */
+        match name {
+            "none" => Some(FilterMode::None),
+            "phosphor" => Some(FilterMode::Phosphor),
+            "or" => Some(FilterMode::Or),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            FilterMode::None => "none",
+            FilterMode::Phosphor => "phosphor",
+            FilterMode::Or => "or",
+        }
+    }
+
+    pub fn next(&self) -> FilterMode {
+        /*  The filter after this one, for cycling through them at runtime
+         */
+        match self {
+            FilterMode::None => FilterMode::Phosphor,
+            FilterMode::Phosphor => FilterMode::Or,
+            FilterMode::Or => FilterMode::None,
+        }
+    }
+}
+
+fn blend(background: u32, color: u32, weight: usize, total: usize) -> u32 {
+    /*  Mixes weight/total of color into background, channel by channel
+     */
+    let mut mixed = 0;
+    for shift in [0, 8, 16] {
+        let from = (background >> shift & 0xFF) as usize;
+        let to = (color >> shift & 0xFF) as usize;
+        let channel = (from * (total - weight) + to * weight) / total;
+        mixed |= (channel as u32) << shift;
+    }
+    return mixed;
+}
+
+pub struct Filter {
+    pub mode: FilterMode,
+
+    // How many frames the phosphor filter blends, including the current one
+    pub phosphor_frames: usize,
+
+    // Unfiltered frames, newest first
+    history: VecDeque<Frame>,
+}
+
+impl Filter {
+    pub fn new(mode: FilterMode, phosphor_frames: usize) -> Filter {
+        return Filter {
+            mode,
+            phosphor_frames,
+            history: VecDeque::new(),
+        };
+    }
+
+    pub fn fading(&self) -> bool {
+        /*  Whether filtering the same frame again would still change it
+         */
+        return self.history.iter().skip(1).any(|old| {
+            self.history
+                .front()
+                .is_some_and(|last| old.pixels != last.pixels)
+        });
+    }
+
+    pub fn apply(&mut self, frame: &mut Frame, background: u32) {
+        /*  Filters the frame in place, remembering the unfiltered one. Every
+            pixel showing the background takes the color it last had within
+            the frames kept, faded towards the background by how long ago that
+            was. The history restarts when the resolution changes
+        */
+        let frames = match self.mode {
+            FilterMode::None => 1,
+            FilterMode::Phosphor => self.phosphor_frames.max(1),
+            FilterMode::Or => 2,
+        };
+        if self
+            .history
+            .front()
+            .is_some_and(|last| (last.width, last.height) != (frame.width, frame.height))
+        {
+            self.history.clear();
+        }
+        self.history.push_front(frame.clone());
+        self.history.truncate(frames);
+
+        for (index, pixel) in frame.pixels.iter_mut().enumerate() {
+            if *pixel != background {
+                continue;
+            }
+            let lit = self
+                .history
+                .iter()
+                .enumerate()
+                .skip(1)
+                .find(|(_, old)| old.pixels[index] != background);
+            if let Some((age, old)) = lit {
+                *pixel = match self.mode {
+                    FilterMode::Phosphor => {
+                        blend(background, old.pixels[index], frames - age, frames)
+                    }
+                    _ => old.pixels[index],
+                };
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BACKGROUND: u32 = 0x000000;
+    const LIT: u32 = 0x90C030;
+
+    fn frame(pixels: &[u32]) -> Frame {
+        return Frame {
+            pixels: pixels.to_vec(),
+            width: pixels.len(),
+            height: 1,
+        };
+    }
+
+    fn filtered(filter: &mut Filter, pixels: &[u32]) -> Vec<u32> {
+        let mut frame = frame(pixels);
+        filter.apply(&mut frame, BACKGROUND);
+        return frame.pixels;
+    }
+
+    #[test]
+    fn blend_mixes_each_channel() {
+        assert_eq!(blend(0x000000, 0x90C030, 1, 3), 0x304010);
+        assert_eq!(blend(0xFFFFFF, 0x000000, 0, 4), 0xFFFFFF);
+        assert_eq!(blend(0xFFFFFF, 0x000000, 4, 4), 0x000000);
+    }
+
+    #[test]
+    fn no_filter_passes_frames_through() {
+        let mut filter = Filter::new(FilterMode::None, 3);
+        filtered(&mut filter, &[LIT, LIT]);
+        assert_eq!(filtered(&mut filter, &[BACKGROUND, LIT]), [BACKGROUND, LIT]);
+        assert!(!filter.fading());
+    }
+
+    #[test]
+    fn phosphor_fades_over_its_frames() {
+        let mut filter = Filter::new(FilterMode::Phosphor, 3);
+        assert_eq!(filtered(&mut filter, &[LIT, BACKGROUND]), [LIT, BACKGROUND]);
+        assert_eq!(
+            filtered(&mut filter, &[BACKGROUND; 2]),
+            [0x608020, BACKGROUND]
+        );
+        assert!(filter.fading());
+        assert_eq!(
+            filtered(&mut filter, &[BACKGROUND; 2]),
+            [0x304010, BACKGROUND]
+        );
+        assert_eq!(filtered(&mut filter, &[BACKGROUND; 2]), [BACKGROUND; 2]);
+        assert!(!filter.fading());
+    }
+
+    #[test]
+    fn phosphor_shows_the_most_recent_color() {
+        let mut filter = Filter::new(FilterMode::Phosphor, 4);
+        filtered(&mut filter, &[0xFFFFFF]);
+        filtered(&mut filter, &[0x400000]);
+        assert_eq!(filtered(&mut filter, &[BACKGROUND]), [0x300000]);
+    }
+
+    #[test]
+    fn or_keeps_pixels_lit_for_one_more_frame() {
+        let mut filter = Filter::new(FilterMode::Or, 3);
+        filtered(&mut filter, &[LIT, BACKGROUND]);
+        assert_eq!(filtered(&mut filter, &[BACKGROUND, LIT]), [LIT, LIT]);
+        assert_eq!(
+            filtered(&mut filter, &[BACKGROUND, BACKGROUND]),
+            [BACKGROUND, LIT]
+        );
+    }
+
+    #[test]
+    fn a_new_resolution_restarts_the_history() {
+        let mut filter = Filter::new(FilterMode::Or, 3);
+        filtered(&mut filter, &[LIT, LIT]);
+        assert_eq!(filtered(&mut filter, &[BACKGROUND; 4]), [BACKGROUND; 4]);
+    }
+}
@@ -0,0 +1,354 @@
+use std::fs;
+
+use toml::{Table, Value};
+
+use crate::phosphor::{Filter, FilterMode, DEFAULT_PHOSPHOR_FRAMES};
+
+// Emulated pixels are scaled by this much unless told otherwise, a 640x320 window
+const DEFAULT_SCALE: usize = 10;
+
+// Pixel colors by bitplane: off, plane 1, plane 2 (XO-CHIP only) and both planes
+const DEFAULT_PALETTE: [u32; 4] = [0x000000, 0x31fe65, 0xfe6531, 0xffffff];
+
+// Fills the bars around the screen when the window's aspect ratio does not match
+const LETTERBOX: u32 = 0x000000;
+
+// Window size in emulated lores pixels
+const LORES_WIDTH: usize = 64;
+const LORES_HEIGHT: usize = 32;
+
+pub struct ScreenOptions {
+    /* How the emulated screen is drawn, from the [display] table of the
+       config file and the command line
+    */
+    // Background, foreground, and the XO-CHIP plane 2 and both-planes colors
+    pub palette: [u32; 4],
+
+    // Window pixels per lores pixel, hires pixels get half as many
+    pub scale: usize,
+
+    // A borderless window without a title bar; minifb 0.13 cannot take over the monitor
+    pub fullscreen: bool,
+
+    // Flicker reduction applied to each frame, and how long phosphor takes to fade
+    pub filter: FilterMode,
+    pub phosphor_frames: usize,
+
+    // Only redraw the window when the display changed, instead of every frame
+    pub lazy_redraw: bool,
+}
+
+#[derive(Clone)]
+pub struct Frame {
+    /* The emulated screen in RGB at its own resolution, between the core's
+       Display and the window
+    */
+    pub pixels: Vec<u32>,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl Frame {
+    pub fn new() -> Frame {
+        return Frame {
+            pixels: Vec::new(),
+            width: 0,
+            height: 0,
+        };
+    }
+
+    pub fn capture(&mut self, display: &chip8::Display, palette: &[u32; 4]) {
+        /*  Colors the active part of the display's buffer
+         */
+        self.width = display.width();
+        self.height = display.height();
+        self.pixels.clear();
+        self.pixels.extend(
+            display.buffer[..self.width * self.height]
+                .iter()
+                .map(|&bits| palette[bits as usize & 0b11]),
+        );
+    }
+}
+
+impl Default for Frame {
+    fn default() -> Frame {
+        return Frame::new();
+    }
+}
+
+pub fn parse_color(text: &str) -> Option<u32> {
+    /*  An RRGGBB color, optionally prefixed with # or 0x
+     */
+    let digits = text
+        .strip_prefix('#')
+        .or_else(|| text.strip_prefix("0x"))
+        .unwrap_or(text);
+    if digits.len() != 6 {
+        return None;
+    }
+    return u32::from_str_radix(digits, 16).ok();
+}
+
+pub fn parse_palette(text: &str) -> Option<[u32; 4]> {
+    /*  Four comma separated colors, in palette order
+     */
+    let colors: Vec<u32> = text.split(',').map(parse_color).collect::<Option<_>>()?;
+    return colors.try_into().ok();
+}
+
+impl ScreenOptions {
+    pub fn new() -> ScreenOptions {
+        return ScreenOptions {
+            palette: DEFAULT_PALETTE,
+            scale: DEFAULT_SCALE,
+            fullscreen: false,
+            filter: FilterMode::None,
+            phosphor_frames: DEFAULT_PHOSPHOR_FRAMES,
+            lazy_redraw: false,
+        };
+    }
+
+    pub fn load_config(&mut self, path: &str) -> Result<(), String> {
+        /*  Applies the [display] table of a config file, e.g.
+                [display]
+                foreground = "#31fe65"
+                background = "#000000"
+                palette = ["#000000", "#31fe65", "#fe6531", "#ffffff"]
+                scale = 8
+                fullscreen = false
+                filter = "phosphor"
+                phosphor_frames = 3
+                lazy_redraw = false
+            foreground and background override the first two palette entries
+        */
+        let text = fs::read_to_string(path).map_err(|error| error.to_string())?;
+        let config: Table = text
+            .parse()
+            .map_err(|error: toml::de::Error| error.to_string())?;
+        let Some(display) = config.get("display") else {
+            return Ok(());
+        };
+        let Value::Table(display) = display else {
+            return Err("display must be a table".to_string());
+        };
+        let color = |key: &str| match display.get(key) {
+            None => Ok(None),
+            Some(Value::String(text)) => parse_color(text)
+                .map(Some)
+                .ok_or(format!("invalid {} color {}", key, text)),
+            Some(_) => Err(format!("{} must be a color string", key)),
+        };
+
+        if let Some(palette) = display.get("palette") {
+            let colors: Option<Vec<u32>> = palette.as_array().and_then(|colors| {
+                colors
+                    .iter()
+                    .map(|color| parse_color(color.as_str()?))
+                    .collect()
+            });
+            self.palette = colors
+                .and_then(|colors| colors.try_into().ok())
+                .ok_or("palette must be a list of 4 colors")?;
+        }
+        if let Some(background) = color("background")? {
+            self.palette[0] = background;
+        }
+        if let Some(foreground) = color("foreground")? {
+            self.palette[1] = foreground;
+        }
+        if let Some(scale) = display.get("scale") {
+            self.scale = scale
+                .as_integer()
+                .filter(|&scale| scale > 0)
+                .ok_or("scale must be a positive integer")? as usize;
+        }
+        if let Some(fullscreen) = display.get("fullscreen") {
+            self.fullscreen = fullscreen
+                .as_bool()
+                .ok_or("fullscreen must be true or false")?;
+        }
+        if let Some(filter) = display.get("filter") {
+            self.filter = filter
+                .as_str()
+                .and_then(FilterMode::from_name)
+                .ok_or("filter must be none, phosphor or or")?;
+        }
+        if let Some(frames) = display.get("phosphor_frames") {
+            self.phosphor_frames = frames
+                .as_integer()
+                .filter(|&frames| frames > 0)
+                .ok_or("phosphor_frames must be a positive integer")?
+                as usize;
+        }
+        if let Some(lazy_redraw) = display.get("lazy_redraw") {
+            self.lazy_redraw = lazy_redraw
+                .as_bool()
+                .ok_or("lazy_redraw must be true or false")?;
+        }
+        return Ok(());
+    }
+
+    pub fn window_size(&self) -> (usize, usize) {
+        return (LORES_WIDTH * self.scale, LORES_HEIGHT * self.scale);
+    }
+}
+
+impl Default for ScreenOptions {
+    fn default() -> ScreenOptions {
+        return ScreenOptions::new();
+    }
+}
+
+pub struct Screen {
+    /* The window's video output: the display in color with the flicker
+       filter applied, ready to be scaled into the window
+    */
+    pub frame: Frame,
+    pub filter: Filter,
+    palette: [u32; 4],
+}
+
+impl Screen {
+    pub fn new(options: &ScreenOptions) -> Screen {
+        return Screen {
+            frame: Frame::new(),
+            filter: Filter::new(options.filter, options.phosphor_frames),
+            palette: options.palette,
+        };
+    }
+}
+
+impl chip8::VideoOutput for Screen {
+    fn present(&mut self, display: &chip8::Display) {
+        self.frame.capture(display, &self.palette);
+        self.filter.apply(&mut self.frame, self.palette[0]);
+    }
+}
+
+pub fn draw(buffer: &mut [u32], stride: usize, area: (usize, usize), frame: &Frame) {
+    /*  Scales the frame into the top left area of the buffer, keeping its
+        aspect ratio. The picture is centered and the bars on either side are
+        filled with the letterbox color. Any resolution scales to any area,
+        each window pixel takes the nearest frame pixel
+    */
+    let (area_width, area_height) = area;
+    let (frame_width, frame_height) = (frame.width, frame.height);
+    if frame_width == 0 || frame_height == 0 {
+        return;
+    }
+    let width = area_width.min(area_height * frame_width / frame_height);
+    let height = width * frame_height / frame_width;
+    let left = (area_width - width) / 2;
+    let top = (area_height - height) / 2;
+
+    for y in 0..area_height {
+        let row = &mut buffer[y * stride..y * stride + area_width];
+        if y < top || y >= top + height {
+            row.fill(LETTERBOX);
+            continue;
+        }
+        let source = (y - top) * frame_height / height * frame_width;
+        row[..left].fill(LETTERBOX);
+        row[left + width..].fill(LETTERBOX);
+        for (x, pixel) in row[left..left + width].iter_mut().enumerate() {
+            *pixel = frame.pixels[source + x * frame_width / width];
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const A: u32 = 0xAAAAAA;
+    const B: u32 = 0xBBBBBB;
+    const L: u32 = LETTERBOX;
+    const UNTOUCHED: u32 = 0x123456;
+
+    fn drawn(area: (usize, usize), stride: usize, frame: &Frame) -> Vec<Vec<u32>> {
+        let mut buffer = vec![UNTOUCHED; stride * (area.1 + 1)];
+        draw(&mut buffer, stride, area, frame);
+        return buffer.chunks(stride).map(<[u32]>::to_vec).collect();
+    }
+
+    fn two_pixels() -> Frame {
+        return Frame {
+            pixels: vec![A, B],
+            width: 2,
+            height: 1,
+        };
+    }
+
+    #[test]
+    fn integer_scales_fill_the_area() {
+        let rows = drawn((4, 2), 4, &two_pixels());
+        assert_eq!(rows[..2], [vec![A, A, B, B], vec![A, A, B, B]]);
+        assert_eq!(rows[2], [UNTOUCHED; 4]);
+    }
+
+    #[test]
+    fn other_sizes_letterbox_and_take_the_nearest_pixel() {
+        // 5 wide keeps the 2:1 ratio at 5x2, centered in 4 rows
+        let rows = drawn((5, 4), 5, &two_pixels());
+        assert_eq!(
+            rows[..4],
+            [
+                vec![L; 5],
+                vec![A, A, A, B, B],
+                vec![A, A, A, B, B],
+                vec![L; 5],
+            ]
+        );
+    }
+
+    #[test]
+    fn tall_areas_are_pillarboxed() {
+        let frame = Frame {
+            pixels: vec![A, B],
+            width: 1,
+            height: 2,
+        };
+        // 1x2 at scale 1, the odd row left over goes below
+        let rows = drawn((5, 3), 5, &frame);
+        assert_eq!(
+            rows[..3],
+            [vec![L, L, A, L, L], vec![L, L, B, L, L], vec![L; 5]]
+        );
+    }
+
+    #[test]
+    fn pixels_past_the_area_are_left_alone() {
+        let rows = drawn((2, 1), 4, &two_pixels());
+        assert_eq!(rows[0], [A, B, UNTOUCHED, UNTOUCHED]);
+    }
+
+    #[test]
+    fn an_empty_frame_draws_nothing() {
+        let rows = drawn((4, 2), 4, &Frame::new());
+        assert!(rows.iter().flatten().all(|&pixel| pixel == UNTOUCHED));
+    }
+
+    #[test]
+    fn capture_colors_each_plane() {
+        let mut display = chip8::Display::new();
+        display.buffer[..4].copy_from_slice(&[0b00, 0b01, 0b10, 0b11]);
+        let mut frame = Frame::new();
+        frame.capture(&display, &[0, 1, 2, 3]);
+        assert_eq!((frame.width, frame.height), (64, 32));
+        assert_eq!(frame.pixels[..4], [0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn colors_parse_with_or_without_a_prefix() {
+        assert_eq!(parse_color("#31fe65"), Some(0x31FE65));
+        assert_eq!(parse_color("0x31fe65"), Some(0x31FE65));
+        assert_eq!(parse_color("31fe65"), Some(0x31FE65));
+        assert_eq!(parse_color("31fe6"), None);
+        assert_eq!(parse_palette("0,1,2,3").map(|_| ()), None);
+        assert_eq!(
+            parse_palette("000000,111111,222222,333333"),
+            Some([0x000000, 0x111111, 0x222222, 0x333333])
+        );
+    }
+}
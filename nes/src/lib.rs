@@ -0,0 +1,5 @@
+#![allow(clippy::needless_return)]
+
+mod nes;
+
+pub use crate::nes::*;
@@ -1,36 +1,49 @@
-mod nes;
 use std::env;
 use std::fs;
-use std::io::Read;
 
 fn main() {
     let args: Vec<String> = env::args().collect();
 
     let mut rom_path = "default";
     let mut debug = false;
+    let mut unstable = nes::UnstablePolicy::Emulate;
 
     for arg in args.iter() {
-        if arg.starts_with("--rom=") {
-            rom_path = &arg[6..]
+        if let Some(path) = arg.strip_prefix("--rom=") {
+            rom_path = path;
         }
         if arg == "-d" {
             debug = true;
         }
+        if let Some(policy) = arg.strip_prefix("--unstable=") {
+            unstable = match policy {
+                "halt" => nes::UnstablePolicy::Halt,
+                "emulate" => nes::UnstablePolicy::Emulate,
+                _ => panic!("--unstable must be halt or emulate"),
+            };
+        }
     }
 
     let mut ns = nes::Nes::new();
-    if debug == true {
+    ns.unstable = unstable;
+    if debug {
         ns.debug();
     }
 
     if rom_path == "default" {
         // c8.memory.load_program(&C8_ROM);
-    } else {
-        let mut f = fs::File::open(&rom_path).expect("no file found");
-        let metadata = fs::metadata(&rom_path).expect("unable to read metadata");
-        let mut buffer = vec![0; metadata.len() as usize];
-        f.read(&mut buffer).expect("buffer overflow");
-        ns.cpu.program_counter = 0xC000;
-        ns.memory.load_program(&buffer);
+        return;
+    }
+    let buffer = fs::read(rom_path).expect("no file found");
+    ns.memory.load_program(&buffer);
+    ns.reset();
+    ns.cpu.program_counter = 0xC000;
+
+    while !ns.cpu.halted {
+        ns.step();
+        if debug {
+            ns.debug();
+        }
     }
+    println!("CPU halted at {:#X}", ns.cpu.program_counter);
 }
@@ -1,1463 +1,930 @@
 mod cpu;
 mod memory;
+mod opcodes;
+mod trace;
+
+pub use cpu::{Cpu, StatusFlags, PPU_DOTS_PER_CYCLE};
+pub use memory::Memory;
+pub use opcodes::{AddressingMode, Opcode, Operation, UnstablePolicy, OPCODES};
+
+// The stack lives in page one, the stack pointer is the low byte
+const STACK_PAGE: u16 = 0x0100;
+
+// What ANE and LXA OR into the accumulator on most chips
+const MAGIC: u8 = 0xEE;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Operand {
+    /* What an instruction operates on, once its addressing mode is resolved
+     */
+    None,
+    Accumulator,
+    // Memory: for immediates the byte after the opcode, for jumps and branches the target
+    Address(u16),
+}
 
 pub struct Nes {
-    pub cpu: cpu::Cpu,
-    pub memory: memory::Memory,
+    pub cpu: Cpu,
+    pub memory: Memory,
+
+    // What to do with the unstable unofficial opcodes
+    pub unstable: UnstablePolicy,
 }
 
 impl Nes {
     pub fn new() -> Nes {
         return Nes {
-            cpu: cpu::Cpu::new(),
-            memory: memory::Memory::new(),
+            cpu: Cpu::new(),
+            memory: Memory::new(),
+            unstable: UnstablePolicy::Emulate,
         };
     }
-    // >>>> Flag operations start >>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>
+    // >>>> Memory access start >>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>
 
-    fn set_carry_flag(&mut self, arg: u8) {
-        if arg & 0x01 == 0x01 {
-            self.cpu.status |= 0b0000_0001;
-        }
+    fn read(&self, address: u16) -> u8 {
+        return self.memory.primary_memory[address as usize];
     }
 
-    fn set_zero_flag(&mut self, arg: u8) {
-        if arg == 0 {
-            self.cpu.status |= 0b0000_0010;
-        }
+    fn write(&mut self, address: u16, value: u8) {
+        self.memory.primary_memory[address as usize] = value;
     }
 
-    fn set_negative_flag(&mut self, arg: u8) {
-        if arg & 0b1000_0000 == 0b1000_0000 {
-            self.cpu.status |= 0b1000_0000;
-        }
+    fn read_u16(&self, address: u16) -> u16 {
+        let lo = self.read(address) as u16;
+        let hi = self.read(address.wrapping_add(1)) as u16;
+        return hi << 8 | lo;
     }
 
-    fn set_overflow_flag(&mut self, arg: u8) {
-        if arg & 0b0100_0000 != 0 {
-            self.cpu.status |= 0b0100_0000;
-        }
+    fn read_u16_zero_page(&self, address: u8) -> u16 {
+        /*  A pointer stored in the zero page, the high byte of a pointer at
+            0xFF comes from 0x00
+        */
+        let lo = self.read(address as u16) as u16;
+        let hi = self.read(address.wrapping_add(1) as u16) as u16;
+        return hi << 8 | lo;
     }
 
-    // <<<< Flag operations end <<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<
-
-    // >>>> Addressing modes start >>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>
-
-    fn immediate(&mut self) -> u8 {
-        self.cpu.program_counter += 1;
-        return self.cpu.program_counter as u8;
+    fn fetch(&mut self) -> u8 {
+        /*  The byte at the program counter, which moves past it
+         */
+        let value = self.read(self.cpu.program_counter);
+        self.cpu.program_counter = self.cpu.program_counter.wrapping_add(1);
+        return value;
     }
 
-    fn zero_page(&mut self) -> u8 {
-        self.cpu.program_counter += 1;
-        self.cpu.program_counter += 1;
-        let zero_page_address: u8 = self.memory.primary_memory[self.cpu.program_counter as usize];
-        return zero_page_address;
+    fn fetch_u16(&mut self) -> u16 {
+        let lo = self.fetch() as u16;
+        let hi = self.fetch() as u16;
+        return hi << 8 | lo;
     }
 
-    fn zero_page_x(&mut self) -> u8 {
-        self.cpu.program_counter += 1;
-        let zero_page_address: u8 =
-            self.memory.primary_memory[self.cpu.program_counter as usize].wrapping_add(self.cpu.x);
+    fn push(&mut self, value: u8) {
+        self.write(STACK_PAGE | self.cpu.stack_pointer as u16, value);
+        self.cpu.stack_pointer = self.cpu.stack_pointer.wrapping_sub(1);
+    }
 
-        return zero_page_address;
+    fn pull(&mut self) -> u8 {
+        self.cpu.stack_pointer = self.cpu.stack_pointer.wrapping_add(1);
+        return self.read(STACK_PAGE | self.cpu.stack_pointer as u16);
     }
 
-    fn zero_page_y(&mut self) -> u8 {
-        self.cpu.program_counter += 1;
-        let zero_page_address: u8 =
-            self.memory.primary_memory[self.cpu.program_counter as usize].wrapping_add(self.cpu.y); // two cpu cycle
+    fn push_u16(&mut self, value: u16) {
+        self.push((value >> 8) as u8);
+        self.push(value as u8);
+    }
 
-        return zero_page_address;
+    fn pull_u16(&mut self) -> u16 {
+        let lo = self.pull() as u16;
+        let hi = self.pull() as u16;
+        return hi << 8 | lo;
     }
 
-    fn absolute(&mut self) -> u16 {
-        self.cpu.program_counter += 1;
-        let lo: u8 = self.memory.primary_memory[self.cpu.program_counter as usize];
-        // one cpu cycle
-        self.cpu.program_counter += 1;
-        let hi: u8 = self.memory.primary_memory[self.cpu.program_counter as usize];
-        // one cpu cycle
-        let absolute_address: u16 = (hi as u16) << 8 | lo as u16;
+    // <<<< Memory access end <<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<
+
+    // >>>> Addressing modes start >>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>
 
-        return absolute_address;
+    fn immediate(&mut self) -> u16 {
+        let address = self.cpu.program_counter;
+        self.cpu.program_counter = self.cpu.program_counter.wrapping_add(1);
+        return address;
     }
 
-    fn absolute_x(&mut self) -> u16 {
-        self.cpu.program_counter += 1;
-        let lo: u8 = self.memory.primary_memory[self.cpu.program_counter as usize];
-        // one cpu cycle
-        self.cpu.program_counter += 1;
-        let hi: u8 = self.memory.primary_memory[self.cpu.program_counter as usize];
-        // one cpu cycle
-        let absolute_address: u16 = ((hi as u16) << 8 | lo as u16).wrapping_add(self.cpu.x as u16);
+    fn zero_page(&mut self) -> u16 {
+        return self.fetch() as u16;
+    }
 
-        return absolute_address;
+    fn zero_page_x(&mut self) -> u16 {
+        // Indexing wraps around within the zero page
+        return self.fetch().wrapping_add(self.cpu.x) as u16;
     }
 
-    fn absolute_y(&mut self) -> u16 {
-        self.cpu.program_counter += 1;
-        let lo: u8 = self.memory.primary_memory[self.cpu.program_counter as usize];
-        // one cpu cycle
-        self.cpu.program_counter += 1;
-        let hi: u8 = self.memory.primary_memory[self.cpu.program_counter as usize];
-        // one cpu cycle
-        let absolute_address: u16 = ((hi as u16) << 8 | lo as u16).wrapping_add(self.cpu.y as u16);
+    fn zero_page_y(&mut self) -> u16 {
+        return self.fetch().wrapping_add(self.cpu.y) as u16;
+    }
 
-        return absolute_address;
+    fn absolute(&mut self) -> u16 {
+        return self.fetch_u16();
     }
 
-    fn indirect(&mut self) -> u16 {
-        self.cpu.program_counter += 1;
-        let mut lo: u8 = self.memory.primary_memory[self.cpu.program_counter as usize];
-        // one cpu cycle
-        self.cpu.program_counter += 1;
-        let mut hi: u8 = self.memory.primary_memory[self.cpu.program_counter as usize];
-        // one cpu cycle
-        let address: u16 = (hi as u16) << 8 | lo as u16;
+    fn indexed(base: u16, index: u8) -> (u16, bool) {
+        /*  base + index, and whether that crossed into another page, which
+            costs reads an extra cycle
+        */
+        let address = base.wrapping_add(index as u16);
+        return (address, address & 0xFF00 != base & 0xFF00);
+    }
 
-        lo = self.memory.primary_memory[address as usize];
-        // one cpu cycle
+    fn absolute_x(&mut self) -> (u16, bool) {
+        return Nes::indexed(self.fetch_u16(), self.cpu.x);
+    }
 
-        hi = self.memory.primary_memory[address as usize + 1];
-        // one cpu cycle
+    fn absolute_y(&mut self) -> (u16, bool) {
+        return Nes::indexed(self.fetch_u16(), self.cpu.y);
+    }
 
-        return (hi as u16) << 8 | lo as u16 - 1;
-        // -1 because the pc is incremented outside the switch block
+    fn indirect(&mut self) -> u16 {
+        /*  JMP's pointer, with the 6502 bug: a pointer at the end of a page
+            takes its high byte from the start of the same page
+        */
+        let pointer = self.fetch_u16();
+        let lo = self.read(pointer) as u16;
+        let hi = self.read(pointer & 0xFF00 | pointer.wrapping_add(1) & 0x00FF) as u16;
+        return hi << 8 | lo;
     }
 
     fn indexed_indirect(&mut self) -> u16 {
-        self.cpu.program_counter += 1;
-        let indirect_address =
-            self.memory.primary_memory[self.cpu.program_counter as usize].wrapping_add(self.cpu.x); // one cpu cycle
-
-        let lo: u8 = self.memory.primary_memory[indirect_address as usize];
-        let hi: u8 = self.memory.primary_memory[indirect_address as usize + 1];
-        // two cpu cycle
-
-        return (hi as u16) << 8 | lo as u16;
+        let pointer = self.fetch().wrapping_add(self.cpu.x);
+        return self.read_u16_zero_page(pointer);
     }
 
-    fn indirect_indexed(&mut self) -> u16 {
-        self.cpu.program_counter += 1;
-        let indirect_address = self.memory.primary_memory[self.cpu.program_counter as usize]; // one cpu cycle
-
-        let lo: u8 = self.memory.primary_memory[indirect_address as usize];
-        let hi: u8 = self.memory.primary_memory[indirect_address as usize + 1];
-        // two cpu cycle
+    fn indirect_indexed(&mut self) -> (u16, bool) {
+        let pointer = self.fetch();
+        return Nes::indexed(self.read_u16_zero_page(pointer), self.cpu.y);
+    }
 
-        return ((hi as u16) << 8 | lo as u16).wrapping_add(self.cpu.y as u16);
+    fn relative(&mut self) -> u16 {
+        /*  Branch target, the offset is signed and counts from the next instruction
+         */
+        let offset = self.fetch() as i8;
+        return self.cpu.program_counter.wrapping_add(offset as u16);
     }
 
     // <<<< Addressing modes end <<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<
 
-    pub fn execute(&mut self, instruction: u8) {
-        match instruction {
-            // >>>> ADC starts >>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>
-            0x69 => {
-                // Immediate
-                let (result, _) = self
-                    .cpu
-                    .accumulator
-                    .overflowing_add(self.memory.primary_memory[self.immediate() as usize]);
-                let carry = if self.cpu.status & 0b0000_0001 != 0 {
-                    1
-                } else {
-                    0
-                };
-                let (result, _) = result.overflowing_add(carry);
-                self.cpu.accumulator = result;
-
-                self.set_carry_flag(result);
-                self.set_zero_flag(result);
-                self.set_overflow_flag(result);
-                self.set_negative_flag(result);
-            }
-            0x65 => {
-                // Zero page
-                let (result, _) = self
-                    .cpu
-                    .accumulator
-                    .overflowing_add(self.memory.primary_memory[self.zero_page() as usize]);
-                let carry = if self.cpu.status & 0b0000_0001 != 0 {
-                    1
-                } else {
-                    0
-                };
-                let (result, _) = result.overflowing_add(carry);
-                self.cpu.accumulator = result;
-
-                self.set_carry_flag(result);
-                self.set_zero_flag(result);
-                self.set_overflow_flag(result);
-                self.set_negative_flag(result);
-            }
-            0x75 => {
-                // Zero page, X
-                let (result, _) = self
-                    .cpu
-                    .accumulator
-                    .overflowing_add(self.memory.primary_memory[self.zero_page_x() as usize]);
-                let carry = if self.cpu.status & 0b0000_0001 != 0 {
-                    1
-                } else {
-                    0
-                };
-                let (result, _) = result.overflowing_add(carry);
-                self.cpu.accumulator = result;
-
-                self.set_carry_flag(result);
-                self.set_zero_flag(result);
-                self.set_overflow_flag(result);
-                self.set_negative_flag(result);
-            }
-            0x6D => {
-                // Absolute
-                let (result, _) = self
-                    .cpu
-                    .accumulator
-                    .overflowing_add(self.memory.primary_memory[self.absolute() as usize]);
-                let carry = if self.cpu.status & 0b0000_0001 != 0 {
-                    1
-                } else {
-                    0
-                };
-                let (result, _) = result.overflowing_add(carry);
-                self.cpu.accumulator = result;
-
-                self.set_carry_flag(result);
-                self.set_zero_flag(result);
-                self.set_overflow_flag(result);
-                self.set_negative_flag(result);
-            }
-            0x7D => {
-                // Absolute, X
-                let (result, _) = self
-                    .cpu
-                    .accumulator
-                    .overflowing_add(self.memory.primary_memory[self.absolute_x() as usize]);
-                let carry = if self.cpu.status & 0b0000_0001 != 0 {
-                    1
-                } else {
-                    0
-                };
-                let (result, _) = result.overflowing_add(carry);
-                self.cpu.accumulator = result;
-
-                self.set_carry_flag(result);
-                self.set_zero_flag(result);
-                self.set_overflow_flag(result);
-                self.set_negative_flag(result);
-            }
-            0x79 => {
-                // Absolute, Y
-                let (result, _) = self
-                    .cpu
-                    .accumulator
-                    .overflowing_add(self.memory.primary_memory[self.absolute_y() as usize]);
-                let carry = if self.cpu.status & 0b0000_0001 != 0 {
-                    1
-                } else {
-                    0
-                };
-                let (result, _) = result.overflowing_add(carry);
-                self.cpu.accumulator = result;
-
-                self.set_carry_flag(result);
-                self.set_zero_flag(result);
-                self.set_overflow_flag(result);
-                self.set_negative_flag(result);
-            }
-            0x61 => {
-                // (Indirect, X)
-                let (result, _) = self
-                    .cpu
-                    .accumulator
-                    .overflowing_add(self.memory.primary_memory[self.indexed_indirect() as usize]);
-                let carry = if self.cpu.status & 0b0000_0001 != 0 {
-                    1
-                } else {
-                    0
-                };
-                let (result, _) = result.overflowing_add(carry);
-                self.cpu.accumulator = result;
-
-                self.set_carry_flag(result);
-                self.set_zero_flag(result);
-                self.set_overflow_flag(result);
-                self.set_negative_flag(result);
-            }
-            0x71 => {
-                // (Indirect), Y
-                let (result, _) = self
-                    .cpu
-                    .accumulator
-                    .overflowing_add(self.memory.primary_memory[self.indirect_indexed() as usize]);
-                let carry = if self.cpu.status & 0b0000_0001 != 0 {
-                    1
-                } else {
-                    0
-                };
-                let (result, _) = result.overflowing_add(carry);
-                self.cpu.accumulator = result;
-
-                self.set_carry_flag(result);
-                self.set_zero_flag(result);
-                self.set_overflow_flag(result);
-                self.set_negative_flag(result);
-            }
-
-            // <<<< ADC ends <<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<
-
-            // >>>> AND starts >>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>
-            0x29 => {
-                // Immediate
-                self.cpu.accumulator &= self.memory.primary_memory[self.immediate() as usize];
-                self.set_zero_flag(self.cpu.accumulator);
-                self.set_negative_flag(self.cpu.accumulator);
-            }
-            0x25 => {
-                // Zero page
-                self.cpu.accumulator &= self.memory.primary_memory[self.zero_page() as usize];
-                self.set_zero_flag(self.cpu.accumulator);
-                self.set_negative_flag(self.cpu.accumulator);
-            }
-            0x35 => {
-                // Zero page, X
-                self.cpu.accumulator &= self.memory.primary_memory[self.zero_page_x() as usize];
-                self.set_zero_flag(self.cpu.accumulator);
-                self.set_negative_flag(self.cpu.accumulator);
-            }
-            0x2D => {
-                // Absolute
-                self.cpu.accumulator &= self.memory.primary_memory[self.absolute() as usize];
-                self.set_zero_flag(self.cpu.accumulator);
-                self.set_negative_flag(self.cpu.accumulator);
-            }
-            0x3D => {
-                // Absolute, X
-                self.cpu.accumulator &= self.memory.primary_memory[self.absolute_x() as usize];
-                self.set_zero_flag(self.cpu.accumulator);
-                self.set_negative_flag(self.cpu.accumulator);
-            }
-            0x39 => {
-                // Absolute, Y
-                self.cpu.accumulator &= self.memory.primary_memory[self.absolute_y() as usize];
-                self.set_zero_flag(self.cpu.accumulator);
-                self.set_negative_flag(self.cpu.accumulator);
-            }
-            0x21 => {
-                // (Indirect, X)
-                self.cpu.accumulator &=
-                    self.memory.primary_memory[self.indexed_indirect() as usize];
-                self.set_zero_flag(self.cpu.accumulator);
-                self.set_negative_flag(self.cpu.accumulator);
-            }
-            0x31 => {
-                // (Indirect), Y
-                self.cpu.accumulator &=
-                    self.memory.primary_memory[self.indirect_indexed() as usize];
-                self.set_zero_flag(self.cpu.accumulator);
-                self.set_negative_flag(self.cpu.accumulator);
-            }
-
-            // <<<< AND ends <<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<
-
-            // >>>> ASL starts >>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>
-            0x0A => {
-                // Accumulator
-                let mut contents: i8 = self.cpu.accumulator as i8;
-                contents <<= 1;
-                self.cpu.accumulator = contents as u8;
-                self.set_carry_flag(self.cpu.accumulator);
-                self.set_zero_flag(self.cpu.accumulator);
-                self.set_negative_flag(self.cpu.accumulator);
-            }
-            0x06 => {
-                // Zero page
-                let memory_location = self.memory.primary_memory[self.zero_page() as usize];
-                let mut contents: i8 = self.memory.primary_memory[memory_location as usize] as i8;
-                contents <<= 1;
-                self.memory.primary_memory[memory_location as usize] = contents as u8;
-                self.set_carry_flag(self.memory.primary_memory[memory_location as usize]);
-                self.set_zero_flag(self.memory.primary_memory[memory_location as usize]);
-                self.set_negative_flag(self.memory.primary_memory[memory_location as usize]);
-            }
-            0x16 => {
-                // Zero page, X
-                let memory_location = self.memory.primary_memory[self.zero_page_x() as usize];
-                let mut contents: i8 = self.memory.primary_memory[memory_location as usize] as i8;
-                contents <<= 1;
-                self.memory.primary_memory[memory_location as usize] = contents as u8;
-                self.set_carry_flag(self.memory.primary_memory[memory_location as usize]);
-                self.set_zero_flag(self.memory.primary_memory[memory_location as usize]);
-                self.set_negative_flag(self.memory.primary_memory[memory_location as usize]);
-            }
-            0x0E => {
-                // Absolute
-                let memory_location = self.memory.primary_memory[self.absolute() as usize];
-                let mut contents: i8 = self.memory.primary_memory[memory_location as usize] as i8;
-                contents <<= 1;
-                self.memory.primary_memory[memory_location as usize] = contents as u8;
-                self.set_carry_flag(self.memory.primary_memory[memory_location as usize]);
-                self.set_zero_flag(self.memory.primary_memory[memory_location as usize]);
-                self.set_negative_flag(self.memory.primary_memory[memory_location as usize]);
-            }
-            0x1E => {
-                // Absolute, X
-                let memory_location = self.memory.primary_memory[self.absolute_x() as usize];
-                let mut contents: i8 = self.memory.primary_memory[memory_location as usize] as i8;
-                contents <<= 1;
-                self.memory.primary_memory[memory_location as usize] = contents as u8;
-                self.set_carry_flag(self.memory.primary_memory[memory_location as usize]);
-                self.set_zero_flag(self.memory.primary_memory[memory_location as usize]);
-                self.set_negative_flag(self.memory.primary_memory[memory_location as usize]);
-            }
-
-            // <<<< ASL ends <<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<
-
-            // >>>> BCC starts >>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>
-            0x90 => {
-                if self.cpu.status & 0b0000_0001 == 0b0000_0000 {
-                    let offset: u16 =
-                        self.memory.primary_memory[self.cpu.program_counter as usize + 1] as u16;
-                    self.cpu.program_counter = self.cpu.program_counter.wrapping_add(offset);
-                    self.cpu.program_counter -= 1; // To cancel out the final incr
-                }
-            }
-
-            // <<<< BCC ends <<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<
-
-            // >>>> BCS starts >>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>
-            0xB0 => {
-                if self.cpu.status & 0b0000_0001 == 0b0000_0001 {
-                    let offset: u8 =
-                        self.memory.primary_memory[self.cpu.program_counter as usize + 1];
-                    self.cpu.program_counter = self.cpu.program_counter.wrapping_add(offset as u16);
-                    self.cpu.program_counter -= 1; // To cancel out the final incr
-                }
-            }
-
-            // <<<< BCS ends <<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<
-
-            // >>>> BEQ starts >>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>
-            0xF0 => {
-                if self.cpu.status & 0b0000_0010 == 0b0000_0010 {
-                    let offset: u16 =
-                        self.memory.primary_memory[self.cpu.program_counter as usize + 1] as u16;
-                    self.cpu.program_counter = self.cpu.program_counter.wrapping_add(offset);
-                    self.cpu.program_counter -= 1; // To cancel out the final incr
-                }
-            }
-
-            // <<<< BEQ ends <<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<
-
-            // >>>> BIT starts >>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>
-            0x24 => {
-                // Zero page
-                let result = self.cpu.program_counter as u8
-                    & self.memory.primary_memory[self.zero_page() as usize];
-                self.set_zero_flag(result);
-                self.set_negative_flag(result);
-                self.set_overflow_flag(result);
-            }
-            0x2C => {
-                // Absolute
-                let result = self.cpu.program_counter as u8
-                    & self.memory.primary_memory[self.absolute() as usize];
-                self.set_zero_flag(result);
-                self.set_negative_flag(result);
-                self.set_overflow_flag(result);
-            }
-
-            // <<<< BIT ends <<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<
-
-            // >>>> BMI starts >>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>
-            0x30 => {
-                if self.cpu.status & 0b1000_0000 == 0b1000_0000 {
-                    let offset: u16 =
-                        self.memory.primary_memory[self.cpu.program_counter as usize + 1] as u16;
-                    self.cpu.program_counter = self.cpu.program_counter.wrapping_add(offset);
-                    self.cpu.program_counter -= 1; // To cancel out the final incr
-                }
-            }
-
-            // <<<< BMI ends <<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<
-
-            // >>>> BNE starts >>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>
-            0xD0 => {
-                // Implied
-                if self.cpu.status & 0b0000_0010 == 0b0000_0000 {
-                    let offset: u16 =
-                        self.memory.primary_memory[self.cpu.program_counter as usize + 1] as u16;
-                    self.cpu.program_counter = self.cpu.program_counter.wrapping_add(offset);
-                    self.cpu.program_counter -= 1; // To cancel out the final incr
-                }
-            }
-
-            // <<<< BNE ends <<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<
-
-            // >>>> BPL starts >>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>
-            0x10 => {
-                if self.cpu.status & 0b1000_0000 == 0b0000_0000 {
-                    let offset: u16 =
-                        self.memory.primary_memory[self.cpu.program_counter as usize + 1] as u16;
-                    self.cpu.program_counter = self.cpu.program_counter.wrapping_add(offset);
-                    self.cpu.program_counter -= 1; // To cancel out the final incr
-                }
-            }
-
-            // <<<< BPL ends <<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<
-
-            // >>>> BRK starts >>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>
-            0x00 => {
-                self.cpu.stack_pointer -= 1;
-
-                self.memory.primary_memory[0x100 + self.cpu.stack_pointer as usize] =
-                    (self.cpu.program_counter >> 8) as u8 & 0xFF;
-                self.cpu.stack_pointer -= 1;
-                self.memory.primary_memory[0x100 + self.cpu.stack_pointer as usize] =
-                    (self.cpu.program_counter & 0xFF) as u8;
-
-                let lo = self.memory.primary_memory[0xFFFE] as u16;
-                let hi = self.memory.primary_memory[0xFFFF] as u16;
-                self.cpu.program_counter = (hi << 8) | lo;
-
-                self.cpu.status |= 0b0010_0000;
-            }
-
-            // <<<< BRK ends <<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<
-
-            // >>>> BVC starts >>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>
-            0x50 => {
-                if self.cpu.status & 0b0100_0000 == 0b0000_0000 {
-                    let offset: u16 =
-                        self.memory.primary_memory[self.cpu.program_counter as usize + 1] as u16;
-                    self.cpu.program_counter = self.cpu.program_counter.wrapping_add(offset);
-                    self.cpu.program_counter -= 1; // To cancel out the final incr
-                }
-            }
-
-            // <<<< BVC ends <<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<
-
-            // >>>> BVS starts >>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>
-            0x70 => {
-                if self.cpu.status & 0b0100_0000 == 0b0100_0000 {
-                    let offset: u16 =
-                        self.memory.primary_memory[self.cpu.program_counter as usize + 1] as u16;
-                    self.cpu.program_counter = self.cpu.program_counter.wrapping_add(offset);
-                    self.cpu.program_counter -= 1; // To cancel out the final incr
-                }
-            }
-
-            // <<<< BVS ends <<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<
-
-            // >>>> CLC starts >>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>
-            0x18 => {
-                self.cpu.status &= 0b1111_1110;
-            }
-
-            // <<<< CLC ends <<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<
-
-            // >>>> CLD starts >>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>
-            0xD8 => {
-                self.cpu.status &= 0b1111_0111;
-            }
-
-            // <<<< CLD ends <<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<
-
-            // >>>> CLI starts >>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>
-            0x58 => {
-                self.cpu.status &= 0b1111_1011;
-            }
-
-            // <<<< CLI ends <<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<
-
-            // >>>> CLV starts >>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>
-            0xB8 => {
-                self.cpu.status &= 0b1011_1111;
-            }
-
-            // <<<< CLV ends <<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<
-
-            // >>>> CMP starts >>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>
-            0xC9 => {
-                // Immediate
-                let result =
-                    self.cpu.accumulator - self.memory.primary_memory[self.immediate() as usize];
-                self.set_carry_flag(result);
-                self.set_zero_flag(result);
-                self.set_negative_flag(result);
-            }
-            0xC5 => {
-                // Zero page
-                let result =
-                    self.cpu.accumulator - self.memory.primary_memory[self.zero_page() as usize];
-                self.set_carry_flag(result);
-                self.set_zero_flag(result);
-                self.set_negative_flag(result);
-            }
-            0xD5 => {
-                // Zero page, X
-                let result =
-                    self.cpu.accumulator - self.memory.primary_memory[self.zero_page_x() as usize];
-                self.set_carry_flag(result);
-                self.set_zero_flag(result);
-                self.set_negative_flag(result);
-            }
-            0xCD => {
-                // Absolute
-                let result =
-                    self.cpu.accumulator - self.memory.primary_memory[self.absolute() as usize];
-                self.set_carry_flag(result);
-                self.set_zero_flag(result);
-                self.set_negative_flag(result);
-            }
-            0xDD => {
-                // Absolute, X
-                let result =
-                    self.cpu.accumulator - self.memory.primary_memory[self.absolute_x() as usize];
-                self.set_carry_flag(result);
-                self.set_zero_flag(result);
-                self.set_negative_flag(result);
-            }
-            0xD9 => {
-                // Absolute, Y
-                let result =
-                    self.cpu.accumulator - self.memory.primary_memory[self.absolute_y() as usize];
-                self.set_carry_flag(result);
-                self.set_zero_flag(result);
-                self.set_negative_flag(result);
-            }
-            0xC1 => {
-                // (Indirect, X)
-                let result = self.cpu.accumulator
-                    - self.memory.primary_memory[self.indexed_indirect() as usize];
-                self.set_carry_flag(result);
-                self.set_zero_flag(result);
-                self.set_negative_flag(result);
-            }
-            0xD1 => {
-                // (Indirect, X)
-                let result = self.cpu.accumulator
-                    - self.memory.primary_memory[self.indirect_indexed() as usize];
-                self.set_carry_flag(result);
-                self.set_zero_flag(result);
-                self.set_negative_flag(result);
-            }
-
-            // <<<< CMP ends <<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<
-
-            // >>>> CPX starts >>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>
-            0xE0 => {
-                // Immediate
-                let result = self.cpu.x - self.memory.primary_memory[self.immediate() as usize];
-                self.set_carry_flag(result);
-                self.set_zero_flag(result);
-                self.set_negative_flag(result);
-            }
-            0xE4 => {
-                // Zero page
-                let result = self.cpu.x - self.memory.primary_memory[self.zero_page() as usize];
-                self.set_carry_flag(result);
-                self.set_zero_flag(result);
-                self.set_negative_flag(result);
-            }
-            0xEC => {
-                // Absolute
-                let result = self.cpu.x - self.memory.primary_memory[self.absolute() as usize];
-                self.set_carry_flag(result);
-                self.set_zero_flag(result);
-                self.set_negative_flag(result);
-            }
-
-            // <<<< CPX ends <<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<
-
-            // >>>> CPY starts >>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>
-            0xC0 => {
-                // Immediate
-                let result = self.cpu.y - self.memory.primary_memory[self.immediate() as usize];
-                self.set_carry_flag(result);
-                self.set_zero_flag(result);
-                self.set_negative_flag(result);
-            }
-            0xC4 => {
-                // Zero page
-                let result = self.cpu.y - self.memory.primary_memory[self.zero_page() as usize];
-                self.set_carry_flag(result);
-                self.set_zero_flag(result);
-                self.set_negative_flag(result);
-            }
-            0xCC => {
-                // Absolute
-                let result = self.cpu.y - self.memory.primary_memory[self.absolute() as usize];
-                self.set_carry_flag(result);
-                self.set_zero_flag(result);
-                self.set_negative_flag(result);
-            }
-
-            // <<<< CPY ends <<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<
-
-            // >>>> DEC starts >>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>
-            0xC6 => {
-                // Zero page
-                let memory_location = self.memory.primary_memory[self.zero_page() as usize];
-                self.memory.primary_memory[memory_location as usize] -= 1;
-                self.set_zero_flag(self.memory.primary_memory[memory_location as usize]);
-                self.set_negative_flag(self.memory.primary_memory[memory_location as usize]);
-            }
-            0xD6 => {
-                // Zero page, X
-                let memory_location = self.memory.primary_memory[self.zero_page_x() as usize];
-                self.memory.primary_memory[memory_location as usize] -= 1;
-                self.set_zero_flag(self.memory.primary_memory[memory_location as usize]);
-                self.set_negative_flag(self.memory.primary_memory[memory_location as usize]);
-            }
-            0xCE => {
-                // Absolute
-                let memory_location = self.memory.primary_memory[self.absolute() as usize];
-                self.memory.primary_memory[memory_location as usize] -= 1;
-                self.set_zero_flag(self.memory.primary_memory[memory_location as usize]);
-                self.set_negative_flag(self.memory.primary_memory[memory_location as usize]);
-            }
-            0xDE => {
-                //Absolute, X
-                let memory_location = self.memory.primary_memory[self.absolute_x() as usize];
-                self.memory.primary_memory[memory_location as usize] -= 1;
-                self.set_zero_flag(self.memory.primary_memory[memory_location as usize]);
-                self.set_negative_flag(self.memory.primary_memory[memory_location as usize]);
-            }
-
-            // <<<< DEC ends <<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<
-
-            // >>>> DEX starts >>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>
-            0xCA => {
-                // Implied
-                self.cpu.x -= 1;
-                self.set_zero_flag(self.cpu.x);
-                self.set_negative_flag(self.cpu.x);
-            }
-
-            // <<<< DEX ends <<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<
-
-            // >>>> DEY starts >>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>
-            0x88 => {
-                // Implied
-                self.cpu.y -= 1;
-                self.set_zero_flag(self.cpu.y);
-                self.set_negative_flag(self.cpu.y);
-            }
-
-            // <<<< DEY ends <<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<
-
-            // >>>> EOR starts >>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>
-            0x49 => {
-                self.cpu.accumulator ^= self.memory.primary_memory
-                    [self.memory.primary_memory[self.immediate() as usize] as usize];
-                // Immediate
-            }
-            0x45 => {
-                self.cpu.accumulator ^= self.memory.primary_memory[self.zero_page() as usize];
-                // Zero page
-            }
-            0x55 => {
-                self.cpu.accumulator ^= self.memory.primary_memory[self.zero_page_x() as usize];
-                // Zero page, X
-            }
-            0x4D => {
-                self.cpu.accumulator ^= self.memory.primary_memory[self.absolute() as usize];
-                // Absolute
-            }
-            0x5D => {
-                self.cpu.accumulator ^= self.memory.primary_memory[self.absolute_x() as usize];
-                // Absolute, X
-            }
-            0x59 => {
-                self.cpu.accumulator ^= self.memory.primary_memory[self.absolute_y() as usize];
-                // Absolute, Y
-            }
-            0x41 => {
-                self.cpu.accumulator ^=
-                    self.memory.primary_memory[self.indexed_indirect() as usize];
-                // Indirect, X
-            }
-            0x51 => {
-                self.cpu.accumulator ^=
-                    self.memory.primary_memory[self.indirect_indexed() as usize];
-                // Indirect, X
-            }
-
-            // <<<< EOR ends <<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<
-
-            // >>>> INC starts >>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>
-            0xE6 => {
-                // Zero page
-                let memory_location = self.memory.primary_memory[self.zero_page() as usize];
-                self.memory.primary_memory[memory_location as usize] += 1;
-                self.set_zero_flag(self.memory.primary_memory[memory_location as usize]);
-                self.set_negative_flag(self.memory.primary_memory[memory_location as usize]);
-            }
-            0xF6 => {
-                // Zero page, X
-                let memory_location = self.memory.primary_memory[self.zero_page_x() as usize];
-                self.memory.primary_memory[memory_location as usize] += 1;
-                self.set_zero_flag(self.memory.primary_memory[memory_location as usize]);
-                self.set_negative_flag(self.memory.primary_memory[memory_location as usize]);
-            }
-            0xEE => {
-                // Absolute
-                let memory_location = self.memory.primary_memory[self.absolute() as usize];
-                self.memory.primary_memory[memory_location as usize] += 1;
-                self.set_zero_flag(self.memory.primary_memory[memory_location as usize]);
-                self.set_negative_flag(self.memory.primary_memory[memory_location as usize]);
-            }
-            0xFE => {
-                //Absolute, X
-                let memory_location = self.memory.primary_memory[self.absolute_x() as usize];
-                self.memory.primary_memory[memory_location as usize] += 1;
-                self.set_zero_flag(self.memory.primary_memory[memory_location as usize]);
-                self.set_negative_flag(self.memory.primary_memory[memory_location as usize]);
-            }
-
-            // <<<< INC ends <<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<
-
-            // >>>> INX starts >>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>
-            0xE8 => {
-                // Implied
-                self.cpu.x += 1;
-                self.set_zero_flag(self.cpu.x);
-                self.set_negative_flag(self.cpu.x);
-            }
-
-            // <<<< INX ends <<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<
-
-            // >>>> INY starts >>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>
-            0xC8 => {
-                // Implied
-                self.cpu.y += 1;
-                self.set_zero_flag(self.cpu.y);
-                self.set_negative_flag(self.cpu.y);
-            }
-
-            // <<<< INY ends <<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<
-
-            // >>>> JMP starts >>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>
-            0x4C => {
-                // Absolute
-                self.cpu.program_counter += 1;
-                let lo: u8 = self.memory.primary_memory[self.cpu.program_counter as usize];
-                // one cpu cycle
-                self.cpu.program_counter += 1;
-                let hi: u8 = self.memory.primary_memory[self.cpu.program_counter as usize];
-                // one cpu cycle
-
-                self.cpu.program_counter = (hi as u16) << 8 | lo as u16;
-            }
-            0x6C => {
-                // Indirect
-                self.cpu.program_counter = self.indirect();
-            }
-
-            // <<<< JMP ends <<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<
-
-            // >>>> JSR starts >>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>
-            0x20 => {
-                // Absolute
-                self.cpu.program_counter += 1;
-                let lo: u8 = self.memory.primary_memory[self.cpu.program_counter as usize];
-                // one cpu cycle
-                self.cpu.program_counter += 1;
-                let hi: u8 = self.memory.primary_memory[self.cpu.program_counter as usize];
-                // one cpu cycle
-
-                self.cpu.stack_pointer -= 1;
-
-                self.memory.primary_memory[0x100 + self.cpu.stack_pointer as usize] =
-                    (self.cpu.program_counter >> 8) as u8 & 0xFF;
-                self.cpu.stack_pointer -= 1;
-
-                self.memory.primary_memory[0x100 + self.cpu.stack_pointer as usize] =
-                    self.cpu.program_counter as u8 & 0xFF;
-                self.cpu.stack_pointer -= 1;
-
-                self.cpu.program_counter = (hi as u16) << 8 | lo as u16;
-            }
-
-            // <<<< JSR ends <<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<
-
-            // >>>> LDA starts >>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>
-            0xA9 => {
-                // Immediate
-                self.cpu.accumulator = self.memory.primary_memory[self.immediate() as usize];
-                self.set_zero_flag(self.cpu.accumulator);
-                self.set_negative_flag(self.cpu.accumulator);
-            }
-            0xA5 => {
-                // Zero page
-                self.cpu.accumulator = self.memory.primary_memory[self.zero_page() as usize];
-                self.set_zero_flag(self.cpu.accumulator);
-                self.set_negative_flag(self.cpu.accumulator);
-            }
-            0xB5 => {
-                // Zero page, X
-                self.cpu.accumulator = self.memory.primary_memory[self.zero_page_x() as usize];
-                self.set_zero_flag(self.cpu.accumulator);
-                self.set_negative_flag(self.cpu.accumulator);
-            }
-            0xAD => {
-                // Absolute
-                self.cpu.accumulator = self.memory.primary_memory[self.absolute() as usize];
-                self.set_zero_flag(self.cpu.accumulator);
-                self.set_negative_flag(self.cpu.accumulator);
-            }
-            0xBD => {
-                // Absolute, X
-                self.cpu.accumulator = self.memory.primary_memory[self.absolute_x() as usize];
-                self.set_zero_flag(self.cpu.accumulator);
-                self.set_negative_flag(self.cpu.accumulator);
-            }
-            0xB9 => {
-                // Absolute, Y
-                self.cpu.accumulator = self.memory.primary_memory[self.absolute_y() as usize];
-                self.set_zero_flag(self.cpu.accumulator);
-                self.set_negative_flag(self.cpu.accumulator);
-            }
-            0xA1 => {
-                // (Indirect, X)
-                self.cpu.accumulator = self.memory.primary_memory[self.indexed_indirect() as usize];
-                self.set_zero_flag(self.cpu.accumulator);
-                self.set_negative_flag(self.cpu.accumulator);
-            }
-            0xB1 => {
-                // (Indirect), Y
-                self.cpu.accumulator = self.memory.primary_memory[self.indirect_indexed() as usize];
-                self.set_zero_flag(self.cpu.accumulator);
-                self.set_negative_flag(self.cpu.accumulator);
-            }
-
-            // <<<< LDA ends <<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<
-
-            // >>>> LDX starts >>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>
-            0xA2 => {
-                // Immediate
-                self.cpu.x = self.memory.primary_memory[self.immediate() as usize];
-                self.set_zero_flag(self.cpu.x);
-                self.set_negative_flag(self.cpu.x);
-            }
-            0xA6 => {
-                // Zero page
-                self.cpu.x = self.memory.primary_memory[self.zero_page() as usize];
-                self.set_zero_flag(self.cpu.x);
-                self.set_negative_flag(self.cpu.x);
-            }
-            0xB6 => {
-                // Zero page, Y
-                self.cpu.x = self.memory.primary_memory[self.zero_page_y() as usize];
-                self.set_zero_flag(self.cpu.x);
-                self.set_negative_flag(self.cpu.x);
-            }
-            0xAE => {
-                // Absolute
-                self.cpu.x = self.memory.primary_memory[self.absolute() as usize];
-                self.set_zero_flag(self.cpu.x);
-                self.set_negative_flag(self.cpu.x);
-            }
-            0xBE => {
-                // Absolute, Y
-                self.cpu.x = self.memory.primary_memory[self.absolute_y() as usize];
-                self.set_zero_flag(self.cpu.x);
-                self.set_negative_flag(self.cpu.x);
-            }
-
-            // <<<< LDX ends <<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<
-
-            // >>>> LDY starts >>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>
-            0xA0 => {
-                // Immediate
-                self.cpu.y = self.memory.primary_memory[self.immediate() as usize];
-                self.set_zero_flag(self.cpu.y);
-                self.set_negative_flag(self.cpu.y);
-            }
-            0xA4 => {
-                // Zero page
-                self.cpu.y = self.memory.primary_memory[self.zero_page() as usize];
-                self.set_zero_flag(self.cpu.y);
-                self.set_negative_flag(self.cpu.y);
-            }
-            0xB4 => {
-                // Zero page, X
-                self.cpu.y = self.memory.primary_memory[self.zero_page_x() as usize];
-                self.set_zero_flag(self.cpu.y);
-                self.set_negative_flag(self.cpu.y);
-            }
-            0xAC => {
-                // Absolute
-                self.cpu.y = self.memory.primary_memory[self.absolute() as usize];
-                self.set_zero_flag(self.cpu.y);
-                self.set_negative_flag(self.cpu.y);
-            }
-            0xBC => {
-                // Absolute, X
-                self.cpu.y = self.memory.primary_memory[self.absolute_x() as usize];
-                self.set_zero_flag(self.cpu.y);
-                self.set_negative_flag(self.cpu.y);
-            }
-
-            // <<<< LDY ends <<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<
-
-            // >>>> LSR starts >>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>
-            0x4A => {
-                // Accumulator
-                self.set_carry_flag(self.cpu.accumulator);
-                self.cpu.accumulator >>= 1;
-                self.set_zero_flag(self.cpu.accumulator);
-                self.set_negative_flag(self.cpu.accumulator);
-            }
-            0x46 => {
-                // Zero page
-                let memory_location = self.memory.primary_memory[self.zero_page() as usize];
-                self.set_carry_flag(self.memory.primary_memory[memory_location as usize]);
-                self.memory.primary_memory[memory_location as usize] >>= 1;
-                self.set_zero_flag(self.memory.primary_memory[memory_location as usize]);
-                self.set_negative_flag(self.memory.primary_memory[memory_location as usize]);
-            }
-            0x56 => {
-                // Zero page, X
-                let memory_location = self.memory.primary_memory[self.zero_page_x() as usize];
-                self.set_carry_flag(self.memory.primary_memory[memory_location as usize]);
-                self.memory.primary_memory[memory_location as usize] >>= 1;
-                self.set_zero_flag(self.memory.primary_memory[memory_location as usize]);
-                self.set_negative_flag(self.memory.primary_memory[memory_location as usize]);
-            }
-            0x4E => {
-                // Absolute
-                let memory_location = self.memory.primary_memory[self.absolute() as usize];
-                self.set_carry_flag(self.memory.primary_memory[memory_location as usize]);
-                self.memory.primary_memory[memory_location as usize] >>= 1;
-                self.set_zero_flag(self.memory.primary_memory[memory_location as usize]);
-                self.set_negative_flag(self.memory.primary_memory[memory_location as usize]);
-            }
-            0x5E => {
-                // Absolute, X
-                let memory_location = self.memory.primary_memory[self.absolute_x() as usize];
-                self.set_carry_flag(self.memory.primary_memory[memory_location as usize]);
-                self.memory.primary_memory[memory_location as usize] >>= 1;
-                self.set_zero_flag(self.memory.primary_memory[memory_location as usize]);
-                self.set_negative_flag(self.memory.primary_memory[memory_location as usize]);
-            }
-
-            // <<<< LSR ends <<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<
+    // >>>> Operands start >>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>
+
+    fn resolve(&mut self, mode: AddressingMode) -> (Operand, bool) {
+        /*  Reads the operand bytes of the instruction and works out what it
+            operates on, and whether indexing crossed a page
+        */
+        let address = match mode {
+            AddressingMode::AbsoluteX => self.absolute_x(),
+            AddressingMode::AbsoluteY => self.absolute_y(),
+            AddressingMode::IndirectIndexed => self.indirect_indexed(),
+            _ => return (self.resolve_unindexed(mode), false),
+        };
+        return (Operand::Address(address.0), address.1);
+    }
 
-            // >>>> NOP starts >>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>
-            0xEA => {
-                /* No operation */                              // Implied
-            }
+    fn resolve_unindexed(&mut self, mode: AddressingMode) -> Operand {
+        return match mode {
+            AddressingMode::Implied => Operand::None,
+            AddressingMode::Accumulator => Operand::Accumulator,
+            AddressingMode::Immediate => Operand::Address(self.immediate()),
+            AddressingMode::ZeroPage => Operand::Address(self.zero_page()),
+            AddressingMode::ZeroPageX => Operand::Address(self.zero_page_x()),
+            AddressingMode::ZeroPageY => Operand::Address(self.zero_page_y()),
+            AddressingMode::Absolute => Operand::Address(self.absolute()),
+            AddressingMode::Indirect => Operand::Address(self.indirect()),
+            AddressingMode::IndexedIndirect => Operand::Address(self.indexed_indirect()),
+            AddressingMode::Relative => Operand::Address(self.relative()),
+            _ => unreachable!("{:?} is indexed", mode),
+        };
+    }
 
-            // <<<< NOP ends <<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<
+    fn address(&self, operand: Operand) -> u16 {
+        return match operand {
+            Operand::Address(address) => address,
+            _ => panic!("{:?} has no address", operand),
+        };
+    }
 
-            // >>>> ORA starts >>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>
-            0x09 => {
-                // Immediate
-                self.cpu.accumulator |= self.memory.primary_memory
-                    [self.memory.primary_memory[self.immediate() as usize] as usize];
-                self.set_zero_flag(self.cpu.accumulator);
-                self.set_negative_flag(self.cpu.accumulator);
-            }
-            0x05 => {
-                // Zero page
+    fn load(&self, operand: Operand) -> u8 {
+        return match operand {
+            Operand::Accumulator => self.cpu.accumulator,
+            _ => self.read(self.address(operand)),
+        };
+    }
 
-                self.cpu.accumulator |= self.memory.primary_memory
-                    [self.memory.primary_memory[self.zero_page() as usize] as usize];
-                self.set_zero_flag(self.cpu.accumulator);
-                self.set_negative_flag(self.cpu.accumulator);
-            }
-            0x15 => {
-                // Zero page, X
+    fn store(&mut self, operand: Operand, value: u8) {
+        match operand {
+            Operand::Accumulator => self.cpu.accumulator = value,
+            _ => self.write(self.address(operand), value),
+        }
+    }
 
-                self.cpu.accumulator |= self.memory.primary_memory
-                    [self.memory.primary_memory[self.zero_page_x() as usize] as usize];
-                self.set_zero_flag(self.cpu.accumulator);
-                self.set_negative_flag(self.cpu.accumulator);
-            }
-            0x0D => {
-                // Absolute
+    fn modify(&mut self, operand: Operand, operation: fn(&mut Nes, u8) -> u8) {
+        /*  Read-modify-write, on memory or the accumulator
+         */
+        let value = self.load(operand);
+        let result = operation(self, value);
+        self.store(operand, result);
+    }
 
-                self.cpu.accumulator |= self.memory.primary_memory
-                    [self.memory.primary_memory[self.absolute() as usize] as usize];
-                self.set_zero_flag(self.cpu.accumulator);
-                self.set_negative_flag(self.cpu.accumulator);
-            }
-            0x1D => {
-                // Absolute, X
+    // <<<< Operands end <<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<
+
+    // >>>> Operations start >>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>
+
+    fn adc(&mut self, value: u8) {
+        /*  A + value + C. C is the carry out of bit 7, V is set when both
+            inputs have the same sign and the result does not. The NES has no
+            decimal mode, D is ignored
+        */
+        let carry = self.cpu.status.contains(StatusFlags::CARRY) as u16;
+        let sum = self.cpu.accumulator as u16 + value as u16 + carry;
+        let result = sum as u8;
+        let overflow = (self.cpu.accumulator ^ result) & (value ^ result) & 0b1000_0000 != 0;
+        self.cpu.status.set(StatusFlags::CARRY, sum > 0xFF);
+        self.cpu.status.set(StatusFlags::OVERFLOW, overflow);
+        self.cpu.accumulator = result;
+        self.cpu.status.set_zero_negative(result);
+    }
 
-                self.cpu.accumulator |= self.memory.primary_memory
-                    [self.memory.primary_memory[self.absolute_x() as usize] as usize];
-                self.set_zero_flag(self.cpu.accumulator);
-                self.set_negative_flag(self.cpu.accumulator);
-            }
-            0x19 => {
-                // Absolute, Y
+    fn sbc(&mut self, value: u8) {
+        /*  A - value - (1 - C), which is A + !value + C. C ends up clear when
+            the subtraction borrowed
+        */
+        self.adc(!value);
+    }
 
-                self.cpu.accumulator |= self.memory.primary_memory
-                    [self.memory.primary_memory[self.absolute_y() as usize] as usize];
-                self.set_zero_flag(self.cpu.accumulator);
-                self.set_negative_flag(self.cpu.accumulator);
-            }
-            0x01 => {
-                // (Indirect, X)
+    fn compare(&mut self, register: u8, value: u8) {
+        /*  CMP, CPX and CPY: C when the register is at least the value, Z and N from the difference
+         */
+        self.cpu.status.set(StatusFlags::CARRY, register >= value);
+        self.cpu
+            .status
+            .set_zero_negative(register.wrapping_sub(value));
+    }
 
-                self.cpu.accumulator |= self.memory.primary_memory
-                    [self.memory.primary_memory[self.indexed_indirect() as usize] as usize];
-                self.set_zero_flag(self.cpu.accumulator);
-                self.set_negative_flag(self.cpu.accumulator);
-            }
-            0x11 => {
-                // (Indirect), Y
+    fn bit(&mut self, value: u8) {
+        /*  Z from A & value, V and N copied from bits 6 and 7 of the value
+         */
+        self.cpu
+            .status
+            .set(StatusFlags::ZERO, self.cpu.accumulator & value == 0);
+        self.cpu
+            .status
+            .set(StatusFlags::OVERFLOW, value & 0b0100_0000 != 0);
+        self.cpu
+            .status
+            .set(StatusFlags::NEGATIVE, value & 0b1000_0000 != 0);
+    }
 
-                self.cpu.accumulator |= self.memory.primary_memory
-                    [self.memory.primary_memory[self.indirect_indexed() as usize] as usize];
-                self.set_zero_flag(self.cpu.accumulator);
-                self.set_negative_flag(self.cpu.accumulator);
-            }
+    fn asl(&mut self, value: u8) -> u8 {
+        let result = value << 1;
+        self.cpu
+            .status
+            .set(StatusFlags::CARRY, value & 0b1000_0000 != 0);
+        self.cpu.status.set_zero_negative(result);
+        return result;
+    }
 
-            // <<<< ORA ends <<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<
+    fn lsr(&mut self, value: u8) -> u8 {
+        let result = value >> 1;
+        self.cpu
+            .status
+            .set(StatusFlags::CARRY, value & 0b0000_0001 != 0);
+        self.cpu.status.set_zero_negative(result);
+        return result;
+    }
 
-            // >>>> PHA starts >>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>
-            0x48 => {
-                // Implied
-                self.memory.primary_memory[0x100 + self.cpu.stack_pointer as usize] =
-                    self.cpu.accumulator; // two cpu cycle
-                self.cpu.stack_pointer -= 1;
-            }
+    fn rol(&mut self, value: u8) -> u8 {
+        let carry = self.cpu.status.contains(StatusFlags::CARRY) as u8;
+        let result = value << 1 | carry;
+        self.cpu
+            .status
+            .set(StatusFlags::CARRY, value & 0b1000_0000 != 0);
+        self.cpu.status.set_zero_negative(result);
+        return result;
+    }
 
-            // <<<< PHA ends <<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<
+    fn ror(&mut self, value: u8) -> u8 {
+        let carry = self.cpu.status.contains(StatusFlags::CARRY) as u8;
+        let result = value >> 1 | carry << 7;
+        self.cpu
+            .status
+            .set(StatusFlags::CARRY, value & 0b0000_0001 != 0);
+        self.cpu.status.set_zero_negative(result);
+        return result;
+    }
 
-            // >>>> PHP starts >>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>
-            0x08 => {
-                // Implied
-                self.memory.primary_memory[0x100 + self.cpu.stack_pointer as usize] =
-                    self.cpu.status; // two cpu cycle
-                self.cpu.stack_pointer -= 1;
-            }
+    fn increment(&mut self, value: u8) -> u8 {
+        let result = value.wrapping_add(1);
+        self.cpu.status.set_zero_negative(result);
+        return result;
+    }
 
-            // <<<< PHP ends <<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<
+    fn decrement(&mut self, value: u8) -> u8 {
+        let result = value.wrapping_sub(1);
+        self.cpu.status.set_zero_negative(result);
+        return result;
+    }
 
-            // >>>> PLA starts >>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>
-            0x68 => {
-                // Implied
-                self.cpu.stack_pointer += 1;
-                self.cpu.accumulator =
-                    self.memory.primary_memory[0x100 + self.cpu.stack_pointer as usize];
-                self.set_zero_flag(self.cpu.accumulator);
-                self.set_negative_flag(self.cpu.accumulator);
-            }
+    fn branch(&mut self, operand: Operand, condition: bool) -> u64 {
+        /*  Jumps when the condition holds. Returns the extra cycles: one for
+            a branch taken, another when it lands in a different page
+        */
+        if !condition {
+            return 0;
+        }
+        let target = self.address(operand);
+        let crossed = target & 0xFF00 != self.cpu.program_counter & 0xFF00;
+        self.cpu.program_counter = target;
+        return 1 + crossed as u64;
+    }
 
-            // <<<< PLA ends <<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<
+    // <<<< Operations end <<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<
 
-            // >>>> PLP starts >>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>
-            0x28 => {
-                // Implied
-                self.cpu.stack_pointer += 1;
-                self.cpu.status =
-                    self.memory.primary_memory[0x100 + self.cpu.stack_pointer as usize];
-            }
+    // >>>> Unofficial operations start >>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>
 
-            // <<<< PLP ends <<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<
+    fn slo(&mut self, value: u8) -> u8 {
+        /*  ASL, then ORA with the result
+         */
+        let result = self.asl(value);
+        self.cpu.accumulator |= result;
+        self.cpu.status.set_zero_negative(self.cpu.accumulator);
+        return result;
+    }
 
-            // >>>> ROL starts >>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>
-            0x2A => {
-                // Accumulator
-            }
-            0x26 => {
-                // Zero page
-            }
-            0x36 => {
-                // Zero page, X
-            }
-            0x2E => {
-                // Absolute
-            }
-            0x3E => {
-                // Absolute, X
-            }
+    fn rla(&mut self, value: u8) -> u8 {
+        /*  ROL, then AND with the result
+         */
+        let result = self.rol(value);
+        self.cpu.accumulator &= result;
+        self.cpu.status.set_zero_negative(self.cpu.accumulator);
+        return result;
+    }
 
-            // <<<< ROL ends <<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<
+    fn sre(&mut self, value: u8) -> u8 {
+        /*  LSR, then EOR with the result
+         */
+        let result = self.lsr(value);
+        self.cpu.accumulator ^= result;
+        self.cpu.status.set_zero_negative(self.cpu.accumulator);
+        return result;
+    }
 
-            // >>>> ROR starts >>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>
-            0x6A => {
-                // Accumulator
-            }
-            0x66 => {
-                // Zero page
-            }
-            0x76 => {
-                // Zero page, X
-            }
-            0x6E => {
-                // Absolute
-            }
-            0x7E => {
-                // Absolute, X
-            }
+    fn rra(&mut self, value: u8) -> u8 {
+        /*  ROR, then ADC the result with the carry ROR left
+         */
+        let result = self.ror(value);
+        self.adc(result);
+        return result;
+    }
 
-            // <<<< ROR ends <<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<
-
-            // >>>> RTI starts >>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>
-            0x40 => {
-                // Implied
-                self.cpu.stack_pointer += 1;
-                self.cpu.status =
-                    self.memory.primary_memory[0x100 + self.cpu.stack_pointer as usize]; // Pulling status flags
-                self.cpu.stack_pointer += 1;
-                let lo: u8 = self.memory.primary_memory[self.cpu.program_counter as usize];
-                // one cpu cycle
-                self.cpu.stack_pointer += 1;
-                let hi: u8 = self.memory.primary_memory[self.cpu.program_counter as usize];
-                // one cpu cycle
-                self.cpu.program_counter = (hi as u16) << 8 | lo as u16;
-                self.cpu.program_counter -= 1; // -1 because the pc is incremented outside the switch block
-            }
+    fn dcp(&mut self, value: u8) -> u8 {
+        /*  DEC, then CMP with the result
+         */
+        let result = value.wrapping_sub(1);
+        self.compare(self.cpu.accumulator, result);
+        return result;
+    }
 
-            // <<<< RTI ends <<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<
+    fn isb(&mut self, value: u8) -> u8 {
+        /*  INC, then SBC the result
+         */
+        let result = value.wrapping_add(1);
+        self.sbc(result);
+        return result;
+    }
 
-            // >>>> RTS starts >>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>
-            0x60 => {
-                // Implied
-                self.cpu.stack_pointer += 1;
-                let lo: u8 = self.memory.primary_memory[self.cpu.stack_pointer as usize];
-                // one cpu cycle
-                self.cpu.stack_pointer += 1;
-                let hi: u8 = self.memory.primary_memory[self.cpu.stack_pointer as usize];
-                // one cpu cycle
+    fn arr(&mut self, value: u8) {
+        /*  AND, then ROR A, with C from bit 6 of the result and V from bit 6 XOR bit 5
+         */
+        self.cpu.accumulator &= value;
+        let result = self.ror(self.cpu.accumulator);
+        self.cpu.accumulator = result;
+        self.cpu
+            .status
+            .set(StatusFlags::CARRY, result & 0b0100_0000 != 0);
+        self.cpu.status.set(
+            StatusFlags::OVERFLOW,
+            (result >> 6 ^ result >> 5) & 0b0000_0001 != 0,
+        );
+    }
 
-                self.cpu.program_counter = (hi as u16) << 8 | lo as u16;
-            }
+    fn store_high_and(&mut self, operand: Operand, page_crossed: bool, value: u8) {
+        /*  SHA, SHX, SHY and TAS store the value ANDed with the high byte of
+            the unindexed address plus one. When indexing crossed a page that
+            result also replaces the high byte of the address
+        */
+        let mut address = self.address(operand);
+        let high = ((address >> 8) as u8).wrapping_add(!page_crossed as u8);
+        let value = value & high;
+        if page_crossed {
+            address = (value as u16) << 8 | address & 0x00FF;
+        }
+        self.write(address, value);
+    }
 
-            // <<<< RTS ends <<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<
-
-            // >>>> SBC starts >>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>
-            0xE9 => {
-                // Immediate
-                let rhs = self.memory.primary_memory[self.immediate() as usize]
-                    .wrapping_sub(1 - self.cpu.status & 0x01);
-                self.cpu.accumulator = self.cpu.accumulator.wrapping_sub(rhs);
-                self.set_carry_flag(self.cpu.accumulator);
-                self.set_zero_flag(self.cpu.accumulator);
-                self.set_negative_flag(self.cpu.accumulator);
-            }
-            0xE5 => {
-                // Zero page
-                let rhs = self.memory.primary_memory[self.zero_page() as usize]
-                    .wrapping_sub(1 - self.cpu.status & 0x01);
-                self.cpu.accumulator = self.cpu.accumulator.wrapping_sub(rhs);
-                self.set_carry_flag(self.cpu.accumulator);
-                self.set_zero_flag(self.cpu.accumulator);
-                self.set_negative_flag(self.cpu.accumulator);
-            }
-            0xF5 => {
-                // Zero page, X
-                let rhs = self.memory.primary_memory[self.zero_page_x() as usize]
-                    .wrapping_sub(1 - self.cpu.status & 0x01);
-                self.cpu.accumulator = self.cpu.accumulator.wrapping_sub(rhs);
-                self.set_carry_flag(self.cpu.accumulator);
-                self.set_zero_flag(self.cpu.accumulator);
-                self.set_negative_flag(self.cpu.accumulator);
-            }
-            0xED => {
-                // Absolute
-                let rhs = self.memory.primary_memory[self.absolute() as usize]
-                    .wrapping_sub(1 - self.cpu.status & 0x01);
-                self.cpu.accumulator = self.cpu.accumulator.wrapping_sub(rhs);
-                self.set_carry_flag(self.cpu.accumulator);
-                self.set_zero_flag(self.cpu.accumulator);
-                self.set_negative_flag(self.cpu.accumulator);
+    // <<<< Unofficial operations end <<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<
+
+    pub fn execute(&mut self, instruction: u8) -> u64 {
+        /*  Runs one instruction, the program counter already points past the
+            opcode. Returns the cycles it took, which are added to the CPU's count
+        */
+        let opcode = OPCODES[instruction as usize];
+        let halts = opcode.operation.unstable() && self.unstable == UnstablePolicy::Halt;
+        if opcode.operation == Operation::Jam || halts {
+            // Stuck on the opcode until reset
+            self.cpu.program_counter = self.cpu.program_counter.wrapping_sub(1);
+            self.cpu.halted = true;
+            self.cpu.cycles += opcode.cycles as u64;
+            return opcode.cycles as u64;
+        }
+        let (operand, page_crossed) = self.resolve(opcode.mode);
+        let mut cycles = opcode.cycles as u64;
+        if page_crossed && opcode.page_cross_penalty {
+            cycles += 1;
+        }
+        let status = self.cpu.status;
+        match opcode.operation {
+            // >>>> Loads and stores start >>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>
+            Operation::Lda => {
+                self.cpu.accumulator = self.load(operand);
+                self.cpu.status.set_zero_negative(self.cpu.accumulator);
+            }
+            Operation::Ldx => {
+                self.cpu.x = self.load(operand);
+                self.cpu.status.set_zero_negative(self.cpu.x);
+            }
+            Operation::Ldy => {
+                self.cpu.y = self.load(operand);
+                self.cpu.status.set_zero_negative(self.cpu.y);
+            }
+            Operation::Sta => self.store(operand, self.cpu.accumulator),
+            Operation::Stx => self.store(operand, self.cpu.x),
+            Operation::Sty => self.store(operand, self.cpu.y),
+            // <<<< Loads and stores end <<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<
+
+            // >>>> Register transfers start >>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>
+            Operation::Tax => {
+                self.cpu.x = self.cpu.accumulator;
+                self.cpu.status.set_zero_negative(self.cpu.x);
             }
-            0xF9 => {
-                // Absolute, Y
-                let rhs = self.memory.primary_memory[self.absolute_y() as usize]
-                    .wrapping_sub(1 - self.cpu.status & 0x01);
-                self.cpu.accumulator = self.cpu.accumulator.wrapping_sub(rhs);
-                self.set_carry_flag(self.cpu.accumulator);
-                self.set_zero_flag(self.cpu.accumulator);
-                self.set_negative_flag(self.cpu.accumulator);
+            Operation::Tay => {
+                self.cpu.y = self.cpu.accumulator;
+                self.cpu.status.set_zero_negative(self.cpu.y);
             }
-            0xE1 => {
-                // (Indirect, X)
-                let rhs = self.memory.primary_memory[self.indexed_indirect() as usize]
-                    .wrapping_sub(1 - self.cpu.status & 0x01);
-                self.cpu.accumulator = self.cpu.accumulator.wrapping_sub(rhs);
-                self.set_carry_flag(self.cpu.accumulator);
-                self.set_zero_flag(self.cpu.accumulator);
-                self.set_negative_flag(self.cpu.accumulator);
+            Operation::Tsx => {
+                self.cpu.x = self.cpu.stack_pointer;
+                self.cpu.status.set_zero_negative(self.cpu.x);
             }
-            0xF1 => {
-                // (Indirect), Y
-                let rhs = self.memory.primary_memory[self.indirect_indexed() as usize]
-                    .wrapping_sub(1 - self.cpu.status & 0x01);
-                self.cpu.accumulator = self.cpu.accumulator.wrapping_sub(rhs);
-                self.set_carry_flag(self.cpu.accumulator);
-                self.set_zero_flag(self.cpu.accumulator);
-                self.set_negative_flag(self.cpu.accumulator);
+            Operation::Txa => {
+                self.cpu.accumulator = self.cpu.x;
+                self.cpu.status.set_zero_negative(self.cpu.accumulator);
             }
+            // The only transfer that leaves the flags alone
+            Operation::Txs => self.cpu.stack_pointer = self.cpu.x,
+            Operation::Tya => {
+                self.cpu.accumulator = self.cpu.y;
+                self.cpu.status.set_zero_negative(self.cpu.accumulator);
+            }
+            // <<<< Register transfers end <<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<
+
+            // >>>> Stack starts >>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>
+            Operation::Pha => self.push(self.cpu.accumulator),
+            Operation::Php => self.push(self.cpu.status.pushed(true)),
+            Operation::Pla => {
+                self.cpu.accumulator = self.pull();
+                self.cpu.status.set_zero_negative(self.cpu.accumulator);
+            }
+            Operation::Plp => self.cpu.status = StatusFlags::from_bits(self.pull()),
+            // <<<< Stack ends <<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<
+
+            // >>>> Logic and arithmetic start >>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>
+            Operation::And => {
+                self.cpu.accumulator &= self.load(operand);
+                self.cpu.status.set_zero_negative(self.cpu.accumulator);
+            }
+            Operation::Eor => {
+                self.cpu.accumulator ^= self.load(operand);
+                self.cpu.status.set_zero_negative(self.cpu.accumulator);
+            }
+            Operation::Ora => {
+                self.cpu.accumulator |= self.load(operand);
+                self.cpu.status.set_zero_negative(self.cpu.accumulator);
+            }
+            Operation::Bit => self.bit(self.load(operand)),
+            Operation::Adc => self.adc(self.load(operand)),
+            Operation::Sbc => self.sbc(self.load(operand)),
+            Operation::Cmp => self.compare(self.cpu.accumulator, self.load(operand)),
+            Operation::Cpx => self.compare(self.cpu.x, self.load(operand)),
+            Operation::Cpy => self.compare(self.cpu.y, self.load(operand)),
+            // <<<< Logic and arithmetic end <<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<
+
+            // >>>> Increments and decrements start >>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>
+            Operation::Inc => self.modify(operand, Nes::increment),
+            Operation::Inx => self.cpu.x = self.increment(self.cpu.x),
+            Operation::Iny => self.cpu.y = self.increment(self.cpu.y),
+            Operation::Dec => self.modify(operand, Nes::decrement),
+            Operation::Dex => self.cpu.x = self.decrement(self.cpu.x),
+            Operation::Dey => self.cpu.y = self.decrement(self.cpu.y),
+            // <<<< Increments and decrements end <<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<
+
+            // >>>> Shifts and rotates start >>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>
+            Operation::Asl => self.modify(operand, Nes::asl),
+            Operation::Lsr => self.modify(operand, Nes::lsr),
+            Operation::Rol => self.modify(operand, Nes::rol),
+            Operation::Ror => self.modify(operand, Nes::ror),
+            // <<<< Shifts and rotates end <<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<
+
+            // >>>> Jumps, calls and interrupts start >>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>
+            Operation::Jmp => self.cpu.program_counter = self.address(operand),
+            Operation::Jsr => {
+                // Pushes the address of the last byte of the instruction, RTS adds one
+                self.push_u16(self.cpu.program_counter.wrapping_sub(1));
+                self.cpu.program_counter = self.address(operand);
+            }
+            Operation::Rts => self.cpu.program_counter = self.pull_u16().wrapping_add(1),
+            Operation::Brk => {
+                // The byte after BRK is padding, the return address skips it
+                self.push_u16(self.cpu.program_counter.wrapping_add(1));
+                self.push(self.cpu.status.pushed(true));
+                self.cpu.status.set(StatusFlags::INTERRUPT_DISABLE, true);
+                self.cpu.program_counter = self.read_u16(0xFFFE);
+            }
+            Operation::Rti => {
+                self.cpu.status = StatusFlags::from_bits(self.pull());
+                self.cpu.program_counter = self.pull_u16();
+            }
+            // <<<< Jumps, calls and interrupts end <<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<
+
+            // >>>> Branches start >>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>
+            Operation::Bcc => cycles += self.branch(operand, !status.contains(StatusFlags::CARRY)),
+            Operation::Bcs => cycles += self.branch(operand, status.contains(StatusFlags::CARRY)),
+            Operation::Beq => cycles += self.branch(operand, status.contains(StatusFlags::ZERO)),
+            Operation::Bmi => {
+                cycles += self.branch(operand, status.contains(StatusFlags::NEGATIVE))
+            }
+            Operation::Bne => cycles += self.branch(operand, !status.contains(StatusFlags::ZERO)),
+            Operation::Bpl => {
+                cycles += self.branch(operand, !status.contains(StatusFlags::NEGATIVE))
+            }
+            Operation::Bvc => {
+                cycles += self.branch(operand, !status.contains(StatusFlags::OVERFLOW))
+            }
+            Operation::Bvs => {
+                cycles += self.branch(operand, status.contains(StatusFlags::OVERFLOW))
+            }
+            // <<<< Branches end <<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<
+
+            // >>>> Flags start >>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>
+            Operation::Clc => self.cpu.status.set(StatusFlags::CARRY, false),
+            Operation::Cld => self.cpu.status.set(StatusFlags::DECIMAL, false),
+            Operation::Cli => self.cpu.status.set(StatusFlags::INTERRUPT_DISABLE, false),
+            Operation::Clv => self.cpu.status.set(StatusFlags::OVERFLOW, false),
+            Operation::Sec => self.cpu.status.set(StatusFlags::CARRY, true),
+            Operation::Sed => self.cpu.status.set(StatusFlags::DECIMAL, true),
+            Operation::Sei => self.cpu.status.set(StatusFlags::INTERRUPT_DISABLE, true),
+            // <<<< Flags end <<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<
+            Operation::Nop => {}
+
+            // >>>> Unofficial start >>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>
+            Operation::Slo => self.modify(operand, Nes::slo),
+            Operation::Rla => self.modify(operand, Nes::rla),
+            Operation::Sre => self.modify(operand, Nes::sre),
+            Operation::Rra => self.modify(operand, Nes::rra),
+            Operation::Dcp => self.modify(operand, Nes::dcp),
+            Operation::Isb => self.modify(operand, Nes::isb),
+            Operation::Lax => {
+                self.cpu.accumulator = self.load(operand);
+                self.cpu.x = self.cpu.accumulator;
+                self.cpu.status.set_zero_negative(self.cpu.x);
+            }
+            Operation::Sax => self.store(operand, self.cpu.accumulator & self.cpu.x),
+            Operation::Anc => {
+                // AND, with bit 7 also going to carry
+                self.cpu.accumulator &= self.load(operand);
+                self.cpu.status.set_zero_negative(self.cpu.accumulator);
+                let negative = self.cpu.status.contains(StatusFlags::NEGATIVE);
+                self.cpu.status.set(StatusFlags::CARRY, negative);
+            }
+            Operation::Alr => {
+                self.cpu.accumulator &= self.load(operand);
+                self.cpu.accumulator = self.lsr(self.cpu.accumulator);
+            }
+            Operation::Arr => self.arr(self.load(operand)),
+            Operation::Axs => {
+                // X = (A & X) - value, flags as CMP sets them
+                let value = self.load(operand);
+                let masked = self.cpu.accumulator & self.cpu.x;
+                self.compare(masked, value);
+                self.cpu.x = masked.wrapping_sub(value);
+            }
+            Operation::Las => {
+                let value = self.load(operand) & self.cpu.stack_pointer;
+                self.cpu.accumulator = value;
+                self.cpu.x = value;
+                self.cpu.stack_pointer = value;
+                self.cpu.status.set_zero_negative(value);
+            }
+            Operation::Ane => {
+                let value = (self.cpu.accumulator | MAGIC) & self.cpu.x & self.load(operand);
+                self.cpu.accumulator = value;
+                self.cpu.status.set_zero_negative(value);
+            }
+            Operation::Lxa => {
+                let value = (self.cpu.accumulator | MAGIC) & self.load(operand);
+                self.cpu.accumulator = value;
+                self.cpu.x = value;
+                self.cpu.status.set_zero_negative(value);
+            }
+            Operation::Sha => {
+                let value = self.cpu.accumulator & self.cpu.x;
+                self.store_high_and(operand, page_crossed, value);
+            }
+            Operation::Shx => self.store_high_and(operand, page_crossed, self.cpu.x),
+            Operation::Shy => self.store_high_and(operand, page_crossed, self.cpu.y),
+            Operation::Tas => {
+                self.cpu.stack_pointer = self.cpu.accumulator & self.cpu.x;
+                self.store_high_and(operand, page_crossed, self.cpu.stack_pointer);
+            }
+            Operation::Jam => unreachable!("JAM halts before its operand is read"),
+            // <<<< Unofficial end <<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<
+        }
+        self.cpu.cycles += cycles;
+        return cycles;
+    }
 
-            // <<<< SBC ends <<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<
-
-            // >>>> SEC starts >>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>
-            0x38 => {
-                // Implied
-                self.cpu.status |= 0b0000_0001;
-            }
+    pub fn step(&mut self) -> u64 {
+        /*  Runs the instruction at the program counter, returns the cycles it
+            took. The PPU runs PPU_DOTS_PER_CYCLE dots for each. A halted CPU
+            runs nothing, but time keeps passing for the PPU and APU
+        */
+        if self.cpu.halted {
+            self.cpu.cycles += 1;
+            return 1;
+        }
+        let instruction = self.fetch();
+        return self.execute(instruction);
+    }
 
-            // <<<< SEC ends <<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<
+    pub fn reset(&mut self) {
+        /*  What the reset line does: jump through the vector at 0xFFFC with
+            interrupts disabled. The stack pointer moves down three bytes
+            without writing, and it all takes 7 cycles
+        */
+        self.cpu.program_counter = self.read_u16(0xFFFC);
+        self.cpu.stack_pointer = self.cpu.stack_pointer.wrapping_sub(3);
+        self.cpu.status.set(StatusFlags::INTERRUPT_DISABLE, true);
+        self.cpu.halted = false;
+        self.cpu.cycles += 7;
+    }
 
-            // >>>> SED starts >>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>
-            0xF8 => {
-                self.cpu.status |= 0b0000_1000;
-            }
+    pub fn debug(&self) {
+        println!(
+            "Accumulator: {:#X}, x: {:#X}, y: {:#X}, status: {:#b}, Program counter: {:#X}",
+            self.cpu.accumulator,
+            self.cpu.x,
+            self.cpu.y,
+            self.cpu.status.bits(),
+            self.cpu.program_counter
+        );
+    }
+}
 
-            // <<<< SED ends <<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<
+impl Default for Nes {
+    fn default() -> Nes {
+        return Nes::new();
+    }
+}
 
-            // >>>> SEI starts >>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>
-            0x78 => {
-                self.cpu.status |= 0b0000_0100;
-            }
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-            // <<<< SEI ends <<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<
+    // Where the test programs are placed and started
+    const START: u16 = 0x8000;
 
-            // >>>> STA starts >>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>
-            0x85 => {
-                // Zero page
-                self.memory.primary_memory[self.zero_page() as usize] = self.cpu.accumulator;
-            }
-            0x95 => {
-                // Zero page, X
-                self.memory.primary_memory[self.zero_page_x() as usize] = self.cpu.accumulator;
-            }
-            0x8D => {
-                // Absolute
-                self.memory.primary_memory[self.absolute() as usize] = self.cpu.accumulator;
-            }
-            0x9D => {
-                // Absolute, X
-                self.memory.primary_memory[self.absolute_x() as usize] = self.cpu.accumulator;
-            }
-            0x99 => {
-                // Absolute, Y
-                self.memory.primary_memory[self.absolute_y() as usize] = self.cpu.accumulator;
-            }
-            0x81 => {
-                // (Indirect, X)
-                self.memory.primary_memory[self.indexed_indirect() as usize] = self.cpu.accumulator;
-            }
-            0x91 => {
-                // (Indirect), Y
-                self.memory.primary_memory[self.indirect_indexed() as usize] = self.cpu.accumulator;
-            }
+    fn machine(program: &[u8]) -> Nes {
+        let mut ns = Nes::new();
+        ns.memory.primary_memory[START as usize..START as usize + program.len()]
+            .copy_from_slice(program);
+        ns.cpu.program_counter = START;
+        ns.cpu.stack_pointer = 0xFD;
+        return ns;
+    }
 
-            // <<<< STA ends <<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<
+    fn run(program: &[u8], steps: usize) -> Nes {
+        let mut ns = machine(program);
+        for _ in 0..steps {
+            ns.step();
+        }
+        return ns;
+    }
 
-            // >>>> STX starts >>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>
-            0x86 => {
-                // Zero page
-                self.memory.primary_memory[self.zero_page() as usize] = self.cpu.x;
-            }
-            0x96 => {
-                // Zero page, Y
-                self.memory.primary_memory[self.zero_page_y() as usize] = self.cpu.x;
-            }
-            0x8E => {
-                // Absolute
-                self.memory.primary_memory[self.absolute() as usize] = self.cpu.x;
-            }
+    fn arithmetic(opcode: u8, carry: bool, a: u8, value: u8) -> (u8, bool, bool) {
+        /*  SEC or CLC, LDA #a, then the operation on #value. Returns A, C and V
+         */
+        let set_carry = if carry { 0x38 } else { 0x18 };
+        let ns = run(&[set_carry, 0xA9, a, opcode, value], 3);
+        return (
+            ns.cpu.accumulator,
+            ns.cpu.status.contains(StatusFlags::CARRY),
+            ns.cpu.status.contains(StatusFlags::OVERFLOW),
+        );
+    }
 
-            // <<<< STX ends <<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<
+    #[test]
+    fn adc_sets_overflow_when_the_sign_flips() {
+        // Two positives making a negative, and two negatives making a positive
+        assert_eq!(arithmetic(0x69, false, 0x50, 0x50), (0xA0, false, true));
+        assert_eq!(arithmetic(0x69, false, 0xD0, 0x90), (0x60, true, true));
+        // Mixed signs never overflow
+        assert_eq!(arithmetic(0x69, false, 0x50, 0xD0), (0x20, true, false));
+        assert_eq!(arithmetic(0x69, false, 0x50, 0x10), (0x60, false, false));
+    }
 
-            // >>>> STY starts >>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>
-            0x84 => {
-                // Zero page
-                self.memory.primary_memory[self.zero_page() as usize] = self.cpu.y;
-            }
-            0x94 => {
-                // Zero page, X
-                self.memory.primary_memory[self.zero_page_x() as usize] = self.cpu.y;
-            }
-            0x8C => {
-                // Absolute
-                self.memory.primary_memory[self.absolute() as usize] = self.cpu.y;
-            }
+    #[test]
+    fn adc_adds_the_carry_in() {
+        assert_eq!(arithmetic(0x69, true, 0xFF, 0x00), (0x00, true, false));
+        assert_eq!(arithmetic(0x69, true, 0x7F, 0x00), (0x80, false, true));
+    }
 
-            // <<<< STY ends <<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<
+    #[test]
+    fn adc_clears_flags_set_before_it() {
+        let ns = run(&[0xA9, 0x50, 0x69, 0x50, 0x69, 0x01], 3);
+        assert_eq!(ns.cpu.accumulator, 0xA1);
+        assert!(!ns.cpu.status.contains(StatusFlags::OVERFLOW));
+        assert!(!ns.cpu.status.contains(StatusFlags::CARRY));
+        assert!(ns.cpu.status.contains(StatusFlags::NEGATIVE));
+    }
 
-            // >>>> TAX starts >>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>
-            0xAA => {
-                // Implied
-                self.cpu.x = self.cpu.accumulator;
-                self.set_zero_flag(self.cpu.x);
-                self.set_negative_flag(self.cpu.x);
-            }
+    #[test]
+    fn sbc_sets_overflow_when_the_sign_flips() {
+        // A positive minus a negative making a negative, and the other way round
+        assert_eq!(arithmetic(0xE9, true, 0x50, 0xB0), (0xA0, false, true));
+        assert_eq!(arithmetic(0xE9, true, 0xD0, 0x70), (0x60, true, true));
+        // Same signs never overflow
+        assert_eq!(arithmetic(0xE9, true, 0x50, 0x30), (0x20, true, false));
+        assert_eq!(arithmetic(0xE9, true, 0xD0, 0xF0), (0xE0, false, false));
+    }
 
-            // <<<< TAX ends <<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<
+    #[test]
+    fn sbc_borrows_when_carry_is_clear() {
+        assert_eq!(arithmetic(0xE9, false, 0x00, 0x00), (0xFF, false, false));
+        assert_eq!(arithmetic(0xE9, false, 0x80, 0x00), (0x7F, true, true));
+    }
 
-            // >>>> TAY starts >>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>
-            0xA8 => {
-                // Implied
-                self.cpu.y = self.cpu.accumulator;
-                self.set_zero_flag(self.cpu.y);
-                self.set_negative_flag(self.cpu.y);
+    #[test]
+    fn unofficial_sbc_matches_the_official_one() {
+        for (a, value) in [(0x50, 0xB0), (0xD0, 0x70), (0x50, 0x30), (0x00, 0x01)] {
+            for carry in [false, true] {
+                assert_eq!(
+                    arithmetic(0xEB, carry, a, value),
+                    arithmetic(0xE9, carry, a, value)
+                );
             }
+        }
+    }
 
-            // <<<< TAY ends <<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<
-
-            // >>>> TSX starts >>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>
-            0xBA => {
-                // Implied
-                self.cpu.x = self.cpu.stack_pointer;
-                self.set_zero_flag(self.cpu.x);
-                self.set_negative_flag(self.cpu.x);
-            }
+    fn cycles(program: &[u8], setup: usize) -> u64 {
+        /*  Cycles the instruction after the first setup ones takes
+         */
+        let mut ns = run(program, setup);
+        return ns.step();
+    }
 
-            // <<<< TSX ends <<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<
+    #[test]
+    fn indexed_reads_pay_a_cycle_for_crossing_a_page() {
+        // LDX #1 then LDA $8010,X and LDA $80FF,X
+        assert_eq!(cycles(&[0xA2, 0x01, 0xBD, 0x10, 0x80], 1), 4);
+        assert_eq!(cycles(&[0xA2, 0x01, 0xBD, 0xFF, 0x80], 1), 5);
+        // LDY #1 then LDA ($10),Y with the pointer at 0x10 holding 0x80FF
+        let mut ns = machine(&[0xA0, 0x01, 0xB1, 0x10]);
+        ns.memory.primary_memory[0x10] = 0xFF;
+        ns.memory.primary_memory[0x11] = 0x80;
+        ns.step();
+        assert_eq!(ns.step(), 6);
+    }
 
-            // >>>> TXA starts >>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>
-            0x8A => {
-                // Implied
-                self.cpu.accumulator = self.cpu.y;
-                self.set_zero_flag(self.cpu.accumulator);
-                self.set_negative_flag(self.cpu.accumulator);
-            }
+    #[test]
+    fn indexed_writes_take_the_same_time_either_way() {
+        // LDX #1 then STA $8010,X and STA $80FF,X
+        assert_eq!(cycles(&[0xA2, 0x01, 0x9D, 0x10, 0x80], 1), 5);
+        assert_eq!(cycles(&[0xA2, 0x01, 0x9D, 0xFF, 0x80], 1), 5);
+    }
 
-            // <<<< TXA ends <<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<
+    #[test]
+    fn branches_pay_for_being_taken_and_for_crossing_a_page() {
+        // Z is clear at power on: BEQ falls through, BNE is taken
+        assert_eq!(cycles(&[0xF0, 0x10], 0), 2);
+        assert_eq!(cycles(&[0xD0, 0x10], 0), 3);
+        // Back three bytes from 0x8002 lands on 0x7FFF
+        let mut ns = machine(&[0xD0, 0xFD]);
+        assert_eq!(ns.step(), 4);
+        assert_eq!(ns.cpu.program_counter, 0x7FFF);
+    }
 
-            // >>>> TXS starts >>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>
-            0x9A => {
-                // Implied
-                self.cpu.stack_pointer = self.cpu.x;
-            }
+    #[test]
+    fn cycles_add_up_on_the_cpu() {
+        // LDA #1, STA $0200, JMP $8000
+        let mut ns = machine(&[0xA9, 0x01, 0x8D, 0x00, 0x02, 0x4C, 0x00, 0x80]);
+        let taken: u64 = (0..6).map(|_| ns.step()).sum();
+        assert_eq!(taken, 2 * (2 + 4 + 3));
+        assert_eq!(ns.cpu.cycles, taken);
+    }
 
-            // <<<< TXS ends <<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<
+    #[test]
+    fn jam_halts_on_the_opcode_until_reset() {
+        let mut ns = machine(&[0xA9, 0x01, 0x02, 0xA9, 0x02]);
+        ns.memory.primary_memory[0xFFFC] = 0x00;
+        ns.memory.primary_memory[0xFFFD] = 0x80;
+        ns.step();
+        assert_eq!(ns.step(), 2);
+        assert!(ns.cpu.halted);
+        assert_eq!(ns.cpu.program_counter, 0x8002);
+        // Time passes a cycle per step, nothing else happens
+        assert_eq!(ns.step(), 1);
+        assert_eq!(ns.step(), 1);
+        assert_eq!(ns.cpu.program_counter, 0x8002);
+        assert_eq!(ns.cpu.accumulator, 0x01);
+        assert_eq!(ns.cpu.cycles, 2 + 2 + 1 + 1);
+
+        ns.reset();
+        assert!(!ns.cpu.halted);
+        assert_eq!(ns.cpu.program_counter, 0x8000);
+    }
 
-            // >>>> TYA starts >>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>
-            0x98 => {
-                // Implied
-                self.cpu.accumulator = self.cpu.y;
-                self.set_zero_flag(self.cpu.accumulator);
-                self.set_negative_flag(self.cpu.accumulator);
-            }
+    #[test]
+    fn every_jam_opcode_halts() {
+        for opcode in [
+            0x02, 0x12, 0x22, 0x32, 0x42, 0x52, 0x62, 0x72, 0x92, 0xB2, 0xD2, 0xF2,
+        ] {
+            let mut ns = machine(&[opcode]);
+            ns.step();
+            assert!(ns.cpu.halted, "{:#04X}", opcode);
+        }
+    }
 
-            // <<<< TYA ends <<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<<
-            _ => panic!("Unrecognized instruction {:#X}", instruction),
+    #[test]
+    fn the_halt_policy_stops_on_every_unstable_opcode() {
+        for opcode in [0x8B, 0x93, 0x9B, 0x9C, 0x9E, 0x9F, 0xAB] {
+            let mut ns = machine(&[opcode, 0x00, 0x02]);
+            ns.unstable = UnstablePolicy::Halt;
+            ns.step();
+            assert!(ns.cpu.halted, "{:#04X}", opcode);
+            assert_eq!(ns.cpu.program_counter, START);
+            assert_eq!(ns.memory.primary_memory[0x0200], 0x00);
+
+            let mut ns = machine(&[opcode, 0x00, 0x02]);
+            ns.step();
+            assert!(!ns.cpu.halted, "{:#04X}", opcode);
         }
-        self.cpu.program_counter += 1; // one cpu cycle
     }
 
-    pub fn emulate_cycle(&mut self) {
-        self.execute(self.memory.primary_memory[self.cpu.program_counter as usize]);
+    #[test]
+    fn ane_and_lxa_or_the_magic_constant_into_a() {
+        // LDA #$01, LDX #$0F, ANE #$FF
+        let ns = run(&[0xA9, 0x01, 0xA2, 0x0F, 0x8B, 0xFF], 3);
+        assert_eq!(ns.cpu.accumulator, (0x01 | MAGIC) & 0x0F);
+        // LDA #$01, LXA #$3F
+        let ns = run(&[0xA9, 0x01, 0xAB, 0x3F], 2);
+        assert_eq!(ns.cpu.accumulator, (0x01 | MAGIC) & 0x3F);
+        assert_eq!(ns.cpu.x, ns.cpu.accumulator);
     }
 
-    pub fn debug(&self) {
-        println!(
-            "Accumulator: {:#X}, x: {:#X}, y: {:#X}, status: {:#b}, Program counter: {:#X}",
-            self.cpu.accumulator, self.cpu.x, self.cpu.y, self.cpu.status, self.cpu.program_counter
-        );
+    #[test]
+    fn high_byte_stores_and_with_the_high_byte_plus_one() {
+        // LDA #$FF, LDX #$FF, LDY #$01, then the store to $0210 indexed by Y
+        let setup = [0xA9, 0xFF, 0xA2, 0xFF, 0xA0, 0x01];
+        for opcode in [0x9E, 0x9F, 0x9B] {
+            let ns = run(&[&setup[..], &[opcode, 0x10, 0x02]].concat(), 4);
+            assert_eq!(ns.memory.primary_memory[0x0211], 0x03, "{:#04X}", opcode);
+        }
+        // SHA ($10),Y with the pointer holding $0210
+        let mut ns = machine(&[&setup[..], &[0x93, 0x10]].concat());
+        ns.memory.primary_memory[0x10] = 0x10;
+        ns.memory.primary_memory[0x11] = 0x02;
+        for _ in 0..4 {
+            ns.step();
+        }
+        assert_eq!(ns.memory.primary_memory[0x0211], 0x03);
+        // LDX #$01, LDY #$FF, SHY $0210,X
+        let ns = run(&[0xA2, 0x01, 0xA0, 0xFF, 0x9C, 0x10, 0x02], 3);
+        assert_eq!(ns.memory.primary_memory[0x0211], 0x03);
+        // TAS also sets the stack pointer to A & X
+        let ns = run(&[0xA9, 0xF0, 0xA2, 0x3C, 0xA0, 0x01, 0x9B, 0x10, 0x02], 4);
+        assert_eq!(ns.cpu.stack_pointer, 0x30);
+        assert_eq!(ns.memory.primary_memory[0x0211], 0x00);
+    }
+
+    #[test]
+    fn high_byte_stores_crossing_a_page_replace_the_high_byte() {
+        // LDX #$01, LDY #$01, SHX $02FF,Y stores 0x01 & 0x03 to 0x0100 rather than 0x0300
+        let ns = run(&[0xA2, 0x01, 0xA0, 0x01, 0x9E, 0xFF, 0x02], 3);
+        assert_eq!(ns.memory.primary_memory[0x0100], 0x01);
+        assert_eq!(ns.memory.primary_memory[0x0300], 0x00);
     }
 }
@@ -1,10 +1,8 @@
-pub struct Cpu {
-    pub program_counter: u16,
-    pub stack_pointer: u8,
-    pub x: u8,
-    pub y: u8,
-    pub accumulator: u8,
+// The PPU's clock runs three times as fast as the CPU's
+pub const PPU_DOTS_PER_CYCLE: u64 = 3;
 
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct StatusFlags {
     /*  Status flag register
         7  bit  0
         ---- ----
@@ -17,19 +15,152 @@ pub struct Cpu {
         ||++------ No CPU effect, see: the B flag
         |+-------- Overflow
         +--------- Negative
+        The two s bits only exist in the copy pushed to the stack: bit 5 is
+        always 1 and bit 4 (B) tells a BRK or PHP apart from an interrupt
     */
-    pub status: u8,
+    bits: u8,
+}
+
+impl StatusFlags {
+    pub const CARRY: u8 = 0b0000_0001;
+    pub const ZERO: u8 = 0b0000_0010;
+    pub const INTERRUPT_DISABLE: u8 = 0b0000_0100;
+    pub const DECIMAL: u8 = 0b0000_1000;
+    pub const BREAK: u8 = 0b0001_0000;
+    pub const UNUSED: u8 = 0b0010_0000;
+    pub const OVERFLOW: u8 = 0b0100_0000;
+    pub const NEGATIVE: u8 = 0b1000_0000;
+
+    pub fn new() -> StatusFlags {
+        /*  The register after power on, interrupts disabled
+         */
+        return StatusFlags {
+            bits: StatusFlags::INTERRUPT_DISABLE | StatusFlags::UNUSED,
+        };
+    }
+
+    pub fn from_bits(bits: u8) -> StatusFlags {
+        /*  The register as pulled from the stack by PLP and RTI, B is dropped
+            and the unused bit reads as 1
+        */
+        return StatusFlags {
+            bits: bits & !StatusFlags::BREAK | StatusFlags::UNUSED,
+        };
+    }
+
+    pub fn bits(&self) -> u8 {
+        return self.bits;
+    }
+
+    pub fn pushed(&self, brk: bool) -> u8 {
+        /*  The byte pushed to the stack, with B set by BRK and PHP and clear
+            for hardware interrupts
+        */
+        let pushed = self.bits | StatusFlags::UNUSED;
+        if brk {
+            return pushed | StatusFlags::BREAK;
+        }
+        return pushed;
+    }
+
+    pub fn contains(&self, flag: u8) -> bool {
+        return self.bits & flag != 0;
+    }
+
+    pub fn set(&mut self, flag: u8, on: bool) {
+        if on {
+            self.bits |= flag;
+        } else {
+            self.bits &= !flag;
+        }
+    }
+
+    pub fn set_zero_negative(&mut self, value: u8) {
+        /*  Z and N as almost every instruction leaves them, from the value it produced
+         */
+        self.set(StatusFlags::ZERO, value == 0);
+        self.set(StatusFlags::NEGATIVE, value & 0b1000_0000 != 0);
+    }
+}
+
+impl Default for StatusFlags {
+    fn default() -> StatusFlags {
+        return StatusFlags::new();
+    }
+}
+
+pub struct Cpu {
+    pub program_counter: u16,
+    pub stack_pointer: u8,
+    pub x: u8,
+    pub y: u8,
+    pub accumulator: u8,
+    pub status: StatusFlags,
+
+    // Cycles run since power on, the clock the PPU and APU keep in step with
+    pub cycles: u64,
+
+    // Locked up by KIL/JAM, only a reset gets it going again
+    pub halted: bool,
 }
 
 impl Cpu {
     pub fn new() -> Cpu {
         return Cpu {
             program_counter: 0xFFFC,
-            stack_pointer: 0xFF,
+            // Reset moves it down to 0xFD
+            stack_pointer: 0x00,
             x: 0x00,
             y: 0x00,
             accumulator: 0x00,
-            status: 0x00,
+            status: StatusFlags::new(),
+            cycles: 0,
+            halted: false,
         };
     }
 }
+
+impl Default for Cpu {
+    fn default() -> Cpu {
+        return Cpu::new();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pushed_sets_unused_and_break_only_for_brk_and_php() {
+        let status = StatusFlags::from_bits(StatusFlags::CARRY);
+        assert_eq!(status.pushed(true), 0b0011_0001);
+        assert_eq!(status.pushed(false), 0b0010_0001);
+    }
+
+    #[test]
+    fn pulled_flags_drop_break_and_keep_unused() {
+        assert_eq!(StatusFlags::from_bits(0xFF).bits(), 0b1110_1111);
+        assert_eq!(StatusFlags::from_bits(0x00).bits(), 0b0010_0000);
+    }
+
+    #[test]
+    fn set_clears_as_well_as_sets() {
+        let mut status = StatusFlags::new();
+        status.set(StatusFlags::OVERFLOW, true);
+        status.set(StatusFlags::INTERRUPT_DISABLE, false);
+        assert_eq!(status.bits(), 0b0110_0000);
+        status.set(StatusFlags::OVERFLOW, false);
+        assert_eq!(status.bits(), 0b0010_0000);
+    }
+
+    #[test]
+    fn zero_and_negative_follow_the_value() {
+        let mut status = StatusFlags::new();
+        status.set_zero_negative(0x00);
+        assert!(status.contains(StatusFlags::ZERO) && !status.contains(StatusFlags::NEGATIVE));
+        status.set_zero_negative(0x80);
+        assert!(!status.contains(StatusFlags::ZERO) && status.contains(StatusFlags::NEGATIVE));
+        status.set_zero_negative(0x01);
+        assert!(!status.contains(StatusFlags::ZERO) && !status.contains(StatusFlags::NEGATIVE));
+    }
+}
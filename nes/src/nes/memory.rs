@@ -1,3 +1,12 @@
+// Size of the iNES header in front of the program ROM
+const INES_HEADER_SIZE: usize = 16;
+
+// Program ROM is mapped from here to the end of the address space
+const PRG_ROM_START: usize = 0x8000;
+
+// The header counts program ROM in banks of this size
+const PRG_ROM_BANK_SIZE: usize = 16 * 1024;
+
 pub struct Memory {
     // Primary memory of size 64K
     pub primary_memory: [u8; 64 * 1024],
@@ -5,9 +14,18 @@ pub struct Memory {
 
 impl Memory {
     pub fn load_program(&mut self, program: &[u8]) {
-        for i in 16..program.len() {
-            println!("{:#X}", 0x8000 + i);
-            self.primary_memory[0x8000 + i] = program[i];
+        /*  Maps the program ROM of an iNES file at 0x8000, a 16K ROM is
+            mirrored at 0xC000 as on an NROM-128 board
+        */
+        let banks = program.get(4).copied().unwrap_or(0) as usize;
+        let end = program
+            .len()
+            .min(INES_HEADER_SIZE + banks * PRG_ROM_BANK_SIZE);
+        let prg_rom = &program[INES_HEADER_SIZE.min(end)..end];
+        let size = prg_rom.len().min(self.primary_memory.len() - PRG_ROM_START);
+        self.primary_memory[PRG_ROM_START..PRG_ROM_START + size].copy_from_slice(&prg_rom[..size]);
+        if size == PRG_ROM_BANK_SIZE {
+            self.primary_memory[PRG_ROM_START + size..].copy_from_slice(&prg_rom[..size]);
         }
     }
 }
@@ -19,3 +37,9 @@ impl Memory {
         };
     }
 }
+
+impl Default for Memory {
+    fn default() -> Memory {
+        return Memory::new();
+    }
+}
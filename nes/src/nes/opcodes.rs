@@ -0,0 +1,631 @@
+use AddressingMode::*;
+use Operation::*;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AddressingMode {
+    /* Where an instruction finds its operand
+     */
+    Implied,
+    Accumulator,
+    Immediate,
+    ZeroPage,
+    ZeroPageX,
+    ZeroPageY,
+    Absolute,
+    AbsoluteX,
+    AbsoluteY,
+    // JMP's pointer
+    Indirect,
+    // (zp,X), a pointer in the zero page indexed before it is read
+    IndexedIndirect,
+    // (zp),Y, a pointer in the zero page, the address it holds is indexed
+    IndirectIndexed,
+    // Branches, a signed offset from the next instruction
+    Relative,
+}
+
+impl AddressingMode {
+    pub fn operand_bytes(&self) -> u16 {
+        /*  Bytes after the opcode
+         */
+        return match self {
+            AddressingMode::Implied | AddressingMode::Accumulator => 0,
+            AddressingMode::Absolute
+            | AddressingMode::AbsoluteX
+            | AddressingMode::AbsoluteY
+            | AddressingMode::Indirect => 2,
+            _ => 1,
+        };
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Operation {
+    // Loads and stores
+    Lda,
+    Ldx,
+    Ldy,
+    Sta,
+    Stx,
+    Sty,
+
+    // Register transfers
+    Tax,
+    Tay,
+    Tsx,
+    Txa,
+    Txs,
+    Tya,
+
+    // Stack
+    Pha,
+    Php,
+    Pla,
+    Plp,
+
+    // Logic and arithmetic
+    And,
+    Eor,
+    Ora,
+    Bit,
+    Adc,
+    Sbc,
+    Cmp,
+    Cpx,
+    Cpy,
+
+    // Increments and decrements
+    Inc,
+    Inx,
+    Iny,
+    Dec,
+    Dex,
+    Dey,
+
+    // Shifts and rotates
+    Asl,
+    Lsr,
+    Rol,
+    Ror,
+
+    // Jumps, calls and interrupts
+    Jmp,
+    Jsr,
+    Rts,
+    Brk,
+    Rti,
+
+    // Branches
+    Bcc,
+    Bcs,
+    Beq,
+    Bmi,
+    Bne,
+    Bpl,
+    Bvc,
+    Bvs,
+
+    // Flags
+    Clc,
+    Cld,
+    Cli,
+    Clv,
+    Sec,
+    Sed,
+    Sei,
+
+    // Nothing
+    Nop,
+
+    // Unofficial: a read-modify-write followed by an accumulator operation
+    Slo,
+    Rla,
+    Sre,
+    Rra,
+    Dcp,
+    Isb,
+
+    // Unofficial: loads, stores and immediate combinations
+    Lax,
+    Sax,
+    Anc,
+    Alr,
+    Arr,
+    Axs,
+    Las,
+
+    // Unofficial and unstable, what they do depends on the chip and even its temperature
+    Ane,
+    Lxa,
+    Sha,
+    Shx,
+    Shy,
+    Tas,
+
+    // Unofficial: locks up the CPU until reset
+    Jam,
+}
+
+impl Operation {
+    pub fn mnemonic(&self) -> &'static str {
+        return match self {
+            Operation::Adc => "ADC",
+            Operation::And => "AND",
+            Operation::Asl => "ASL",
+            Operation::Bcc => "BCC",
+            Operation::Bcs => "BCS",
+            Operation::Beq => "BEQ",
+            Operation::Bit => "BIT",
+            Operation::Bmi => "BMI",
+            Operation::Bne => "BNE",
+            Operation::Bpl => "BPL",
+            Operation::Brk => "BRK",
+            Operation::Bvc => "BVC",
+            Operation::Bvs => "BVS",
+            Operation::Clc => "CLC",
+            Operation::Cld => "CLD",
+            Operation::Cli => "CLI",
+            Operation::Clv => "CLV",
+            Operation::Cmp => "CMP",
+            Operation::Cpx => "CPX",
+            Operation::Cpy => "CPY",
+            Operation::Dec => "DEC",
+            Operation::Dex => "DEX",
+            Operation::Dey => "DEY",
+            Operation::Eor => "EOR",
+            Operation::Inc => "INC",
+            Operation::Inx => "INX",
+            Operation::Iny => "INY",
+            Operation::Jmp => "JMP",
+            Operation::Jsr => "JSR",
+            Operation::Lda => "LDA",
+            Operation::Ldx => "LDX",
+            Operation::Ldy => "LDY",
+            Operation::Lsr => "LSR",
+            Operation::Nop => "NOP",
+            Operation::Ora => "ORA",
+            Operation::Pha => "PHA",
+            Operation::Php => "PHP",
+            Operation::Pla => "PLA",
+            Operation::Plp => "PLP",
+            Operation::Rol => "ROL",
+            Operation::Ror => "ROR",
+            Operation::Rti => "RTI",
+            Operation::Rts => "RTS",
+            Operation::Sbc => "SBC",
+            Operation::Sec => "SEC",
+            Operation::Sed => "SED",
+            Operation::Sei => "SEI",
+            Operation::Sta => "STA",
+            Operation::Stx => "STX",
+            Operation::Sty => "STY",
+            Operation::Tax => "TAX",
+            Operation::Tay => "TAY",
+            Operation::Tsx => "TSX",
+            Operation::Txa => "TXA",
+            Operation::Txs => "TXS",
+            Operation::Tya => "TYA",
+            Operation::Slo => "SLO",
+            Operation::Rla => "RLA",
+            Operation::Sre => "SRE",
+            Operation::Rra => "RRA",
+            Operation::Dcp => "DCP",
+            Operation::Isb => "ISB",
+            Operation::Lax => "LAX",
+            Operation::Sax => "SAX",
+            Operation::Anc => "ANC",
+            Operation::Alr => "ALR",
+            Operation::Arr => "ARR",
+            Operation::Axs => "AXS",
+            Operation::Las => "LAS",
+            Operation::Ane => "ANE",
+            Operation::Lxa => "LXA",
+            Operation::Sha => "SHA",
+            Operation::Shx => "SHX",
+            Operation::Shy => "SHY",
+            Operation::Tas => "TAS",
+            Operation::Jam => "JAM",
+        };
+    }
+
+    pub fn unstable(&self) -> bool {
+        /*  Whether the result differs between chips, see UnstablePolicy
+         */
+        return matches!(
+            self,
+            Operation::Ane
+                | Operation::Lxa
+                | Operation::Sha
+                | Operation::Shx
+                | Operation::Shy
+                | Operation::Tas
+        );
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum UnstablePolicy {
+    /* What the CPU does with the unstable unofficial opcodes
+     */
+    // Stop on them as if they were KIL/JAM
+    Halt,
+
+    // Do what most chips do, with 0xEE as the "magic" constant ANE and LXA OR into A
+    Emulate,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Opcode {
+    pub operation: Operation,
+    pub mode: AddressingMode,
+
+    // Cycles taken, before any penalty
+    pub cycles: u8,
+
+    // Takes a cycle more when indexing the address crosses into another page
+    pub page_cross_penalty: bool,
+
+    // Documented by MOS, the others are side effects of the decoder
+    pub official: bool,
+}
+
+const fn opcode(
+    operation: Operation,
+    mode: AddressingMode,
+    cycles: u8,
+    page_cross_penalty: bool,
+    official: bool,
+) -> Opcode {
+    return Opcode {
+        operation,
+        mode,
+        cycles,
+        page_cross_penalty,
+        official,
+    };
+}
+
+const fn op(operation: Operation, mode: AddressingMode, cycles: u8) -> Opcode {
+    return opcode(operation, mode, cycles, false, true);
+}
+
+const fn paged(operation: Operation, mode: AddressingMode, cycles: u8) -> Opcode {
+    return opcode(operation, mode, cycles, true, true);
+}
+
+const fn unofficial(operation: Operation, mode: AddressingMode, cycles: u8) -> Opcode {
+    return opcode(operation, mode, cycles, false, false);
+}
+
+const fn unofficial_paged(operation: Operation, mode: AddressingMode, cycles: u8) -> Opcode {
+    return opcode(operation, mode, cycles, true, false);
+}
+
+// Every opcode byte
+pub const OPCODES: [Opcode; 256] = [
+    op(Brk, Implied, 7),                       // 0x00
+    op(Ora, IndexedIndirect, 6),               // 0x01
+    unofficial(Jam, Implied, 2),               // 0x02
+    unofficial(Slo, IndexedIndirect, 8),       // 0x03
+    unofficial(Nop, ZeroPage, 3),              // 0x04
+    op(Ora, ZeroPage, 3),                      // 0x05
+    op(Asl, ZeroPage, 5),                      // 0x06
+    unofficial(Slo, ZeroPage, 5),              // 0x07
+    op(Php, Implied, 3),                       // 0x08
+    op(Ora, Immediate, 2),                     // 0x09
+    op(Asl, Accumulator, 2),                   // 0x0A
+    unofficial(Anc, Immediate, 2),             // 0x0B
+    unofficial(Nop, Absolute, 4),              // 0x0C
+    op(Ora, Absolute, 4),                      // 0x0D
+    op(Asl, Absolute, 6),                      // 0x0E
+    unofficial(Slo, Absolute, 6),              // 0x0F
+    op(Bpl, Relative, 2),                      // 0x10
+    paged(Ora, IndirectIndexed, 5),            // 0x11
+    unofficial(Jam, Implied, 2),               // 0x12
+    unofficial(Slo, IndirectIndexed, 8),       // 0x13
+    unofficial(Nop, ZeroPageX, 4),             // 0x14
+    op(Ora, ZeroPageX, 4),                     // 0x15
+    op(Asl, ZeroPageX, 6),                     // 0x16
+    unofficial(Slo, ZeroPageX, 6),             // 0x17
+    op(Clc, Implied, 2),                       // 0x18
+    paged(Ora, AbsoluteY, 4),                  // 0x19
+    unofficial(Nop, Implied, 2),               // 0x1A
+    unofficial(Slo, AbsoluteY, 7),             // 0x1B
+    unofficial_paged(Nop, AbsoluteX, 4),       // 0x1C
+    paged(Ora, AbsoluteX, 4),                  // 0x1D
+    op(Asl, AbsoluteX, 7),                     // 0x1E
+    unofficial(Slo, AbsoluteX, 7),             // 0x1F
+    op(Jsr, Absolute, 6),                      // 0x20
+    op(And, IndexedIndirect, 6),               // 0x21
+    unofficial(Jam, Implied, 2),               // 0x22
+    unofficial(Rla, IndexedIndirect, 8),       // 0x23
+    op(Bit, ZeroPage, 3),                      // 0x24
+    op(And, ZeroPage, 3),                      // 0x25
+    op(Rol, ZeroPage, 5),                      // 0x26
+    unofficial(Rla, ZeroPage, 5),              // 0x27
+    op(Plp, Implied, 4),                       // 0x28
+    op(And, Immediate, 2),                     // 0x29
+    op(Rol, Accumulator, 2),                   // 0x2A
+    unofficial(Anc, Immediate, 2),             // 0x2B
+    op(Bit, Absolute, 4),                      // 0x2C
+    op(And, Absolute, 4),                      // 0x2D
+    op(Rol, Absolute, 6),                      // 0x2E
+    unofficial(Rla, Absolute, 6),              // 0x2F
+    op(Bmi, Relative, 2),                      // 0x30
+    paged(And, IndirectIndexed, 5),            // 0x31
+    unofficial(Jam, Implied, 2),               // 0x32
+    unofficial(Rla, IndirectIndexed, 8),       // 0x33
+    unofficial(Nop, ZeroPageX, 4),             // 0x34
+    op(And, ZeroPageX, 4),                     // 0x35
+    op(Rol, ZeroPageX, 6),                     // 0x36
+    unofficial(Rla, ZeroPageX, 6),             // 0x37
+    op(Sec, Implied, 2),                       // 0x38
+    paged(And, AbsoluteY, 4),                  // 0x39
+    unofficial(Nop, Implied, 2),               // 0x3A
+    unofficial(Rla, AbsoluteY, 7),             // 0x3B
+    unofficial_paged(Nop, AbsoluteX, 4),       // 0x3C
+    paged(And, AbsoluteX, 4),                  // 0x3D
+    op(Rol, AbsoluteX, 7),                     // 0x3E
+    unofficial(Rla, AbsoluteX, 7),             // 0x3F
+    op(Rti, Implied, 6),                       // 0x40
+    op(Eor, IndexedIndirect, 6),               // 0x41
+    unofficial(Jam, Implied, 2),               // 0x42
+    unofficial(Sre, IndexedIndirect, 8),       // 0x43
+    unofficial(Nop, ZeroPage, 3),              // 0x44
+    op(Eor, ZeroPage, 3),                      // 0x45
+    op(Lsr, ZeroPage, 5),                      // 0x46
+    unofficial(Sre, ZeroPage, 5),              // 0x47
+    op(Pha, Implied, 3),                       // 0x48
+    op(Eor, Immediate, 2),                     // 0x49
+    op(Lsr, Accumulator, 2),                   // 0x4A
+    unofficial(Alr, Immediate, 2),             // 0x4B
+    op(Jmp, Absolute, 3),                      // 0x4C
+    op(Eor, Absolute, 4),                      // 0x4D
+    op(Lsr, Absolute, 6),                      // 0x4E
+    unofficial(Sre, Absolute, 6),              // 0x4F
+    op(Bvc, Relative, 2),                      // 0x50
+    paged(Eor, IndirectIndexed, 5),            // 0x51
+    unofficial(Jam, Implied, 2),               // 0x52
+    unofficial(Sre, IndirectIndexed, 8),       // 0x53
+    unofficial(Nop, ZeroPageX, 4),             // 0x54
+    op(Eor, ZeroPageX, 4),                     // 0x55
+    op(Lsr, ZeroPageX, 6),                     // 0x56
+    unofficial(Sre, ZeroPageX, 6),             // 0x57
+    op(Cli, Implied, 2),                       // 0x58
+    paged(Eor, AbsoluteY, 4),                  // 0x59
+    unofficial(Nop, Implied, 2),               // 0x5A
+    unofficial(Sre, AbsoluteY, 7),             // 0x5B
+    unofficial_paged(Nop, AbsoluteX, 4),       // 0x5C
+    paged(Eor, AbsoluteX, 4),                  // 0x5D
+    op(Lsr, AbsoluteX, 7),                     // 0x5E
+    unofficial(Sre, AbsoluteX, 7),             // 0x5F
+    op(Rts, Implied, 6),                       // 0x60
+    op(Adc, IndexedIndirect, 6),               // 0x61
+    unofficial(Jam, Implied, 2),               // 0x62
+    unofficial(Rra, IndexedIndirect, 8),       // 0x63
+    unofficial(Nop, ZeroPage, 3),              // 0x64
+    op(Adc, ZeroPage, 3),                      // 0x65
+    op(Ror, ZeroPage, 5),                      // 0x66
+    unofficial(Rra, ZeroPage, 5),              // 0x67
+    op(Pla, Implied, 4),                       // 0x68
+    op(Adc, Immediate, 2),                     // 0x69
+    op(Ror, Accumulator, 2),                   // 0x6A
+    unofficial(Arr, Immediate, 2),             // 0x6B
+    op(Jmp, Indirect, 5),                      // 0x6C
+    op(Adc, Absolute, 4),                      // 0x6D
+    op(Ror, Absolute, 6),                      // 0x6E
+    unofficial(Rra, Absolute, 6),              // 0x6F
+    op(Bvs, Relative, 2),                      // 0x70
+    paged(Adc, IndirectIndexed, 5),            // 0x71
+    unofficial(Jam, Implied, 2),               // 0x72
+    unofficial(Rra, IndirectIndexed, 8),       // 0x73
+    unofficial(Nop, ZeroPageX, 4),             // 0x74
+    op(Adc, ZeroPageX, 4),                     // 0x75
+    op(Ror, ZeroPageX, 6),                     // 0x76
+    unofficial(Rra, ZeroPageX, 6),             // 0x77
+    op(Sei, Implied, 2),                       // 0x78
+    paged(Adc, AbsoluteY, 4),                  // 0x79
+    unofficial(Nop, Implied, 2),               // 0x7A
+    unofficial(Rra, AbsoluteY, 7),             // 0x7B
+    unofficial_paged(Nop, AbsoluteX, 4),       // 0x7C
+    paged(Adc, AbsoluteX, 4),                  // 0x7D
+    op(Ror, AbsoluteX, 7),                     // 0x7E
+    unofficial(Rra, AbsoluteX, 7),             // 0x7F
+    unofficial(Nop, Immediate, 2),             // 0x80
+    op(Sta, IndexedIndirect, 6),               // 0x81
+    unofficial(Nop, Immediate, 2),             // 0x82
+    unofficial(Sax, IndexedIndirect, 6),       // 0x83
+    op(Sty, ZeroPage, 3),                      // 0x84
+    op(Sta, ZeroPage, 3),                      // 0x85
+    op(Stx, ZeroPage, 3),                      // 0x86
+    unofficial(Sax, ZeroPage, 3),              // 0x87
+    op(Dey, Implied, 2),                       // 0x88
+    unofficial(Nop, Immediate, 2),             // 0x89
+    op(Txa, Implied, 2),                       // 0x8A
+    unofficial(Ane, Immediate, 2),             // 0x8B
+    op(Sty, Absolute, 4),                      // 0x8C
+    op(Sta, Absolute, 4),                      // 0x8D
+    op(Stx, Absolute, 4),                      // 0x8E
+    unofficial(Sax, Absolute, 4),              // 0x8F
+    op(Bcc, Relative, 2),                      // 0x90
+    op(Sta, IndirectIndexed, 6),               // 0x91
+    unofficial(Jam, Implied, 2),               // 0x92
+    unofficial(Sha, IndirectIndexed, 6),       // 0x93
+    op(Sty, ZeroPageX, 4),                     // 0x94
+    op(Sta, ZeroPageX, 4),                     // 0x95
+    op(Stx, ZeroPageY, 4),                     // 0x96
+    unofficial(Sax, ZeroPageY, 4),             // 0x97
+    op(Tya, Implied, 2),                       // 0x98
+    op(Sta, AbsoluteY, 5),                     // 0x99
+    op(Txs, Implied, 2),                       // 0x9A
+    unofficial(Tas, AbsoluteY, 5),             // 0x9B
+    unofficial(Shy, AbsoluteX, 5),             // 0x9C
+    op(Sta, AbsoluteX, 5),                     // 0x9D
+    unofficial(Shx, AbsoluteY, 5),             // 0x9E
+    unofficial(Sha, AbsoluteY, 5),             // 0x9F
+    op(Ldy, Immediate, 2),                     // 0xA0
+    op(Lda, IndexedIndirect, 6),               // 0xA1
+    op(Ldx, Immediate, 2),                     // 0xA2
+    unofficial(Lax, IndexedIndirect, 6),       // 0xA3
+    op(Ldy, ZeroPage, 3),                      // 0xA4
+    op(Lda, ZeroPage, 3),                      // 0xA5
+    op(Ldx, ZeroPage, 3),                      // 0xA6
+    unofficial(Lax, ZeroPage, 3),              // 0xA7
+    op(Tay, Implied, 2),                       // 0xA8
+    op(Lda, Immediate, 2),                     // 0xA9
+    op(Tax, Implied, 2),                       // 0xAA
+    unofficial(Lxa, Immediate, 2),             // 0xAB
+    op(Ldy, Absolute, 4),                      // 0xAC
+    op(Lda, Absolute, 4),                      // 0xAD
+    op(Ldx, Absolute, 4),                      // 0xAE
+    unofficial(Lax, Absolute, 4),              // 0xAF
+    op(Bcs, Relative, 2),                      // 0xB0
+    paged(Lda, IndirectIndexed, 5),            // 0xB1
+    unofficial(Jam, Implied, 2),               // 0xB2
+    unofficial_paged(Lax, IndirectIndexed, 5), // 0xB3
+    op(Ldy, ZeroPageX, 4),                     // 0xB4
+    op(Lda, ZeroPageX, 4),                     // 0xB5
+    op(Ldx, ZeroPageY, 4),                     // 0xB6
+    unofficial(Lax, ZeroPageY, 4),             // 0xB7
+    op(Clv, Implied, 2),                       // 0xB8
+    paged(Lda, AbsoluteY, 4),                  // 0xB9
+    op(Tsx, Implied, 2),                       // 0xBA
+    unofficial_paged(Las, AbsoluteY, 4),       // 0xBB
+    paged(Ldy, AbsoluteX, 4),                  // 0xBC
+    paged(Lda, AbsoluteX, 4),                  // 0xBD
+    paged(Ldx, AbsoluteY, 4),                  // 0xBE
+    unofficial_paged(Lax, AbsoluteY, 4),       // 0xBF
+    op(Cpy, Immediate, 2),                     // 0xC0
+    op(Cmp, IndexedIndirect, 6),               // 0xC1
+    unofficial(Nop, Immediate, 2),             // 0xC2
+    unofficial(Dcp, IndexedIndirect, 8),       // 0xC3
+    op(Cpy, ZeroPage, 3),                      // 0xC4
+    op(Cmp, ZeroPage, 3),                      // 0xC5
+    op(Dec, ZeroPage, 5),                      // 0xC6
+    unofficial(Dcp, ZeroPage, 5),              // 0xC7
+    op(Iny, Implied, 2),                       // 0xC8
+    op(Cmp, Immediate, 2),                     // 0xC9
+    op(Dex, Implied, 2),                       // 0xCA
+    unofficial(Axs, Immediate, 2),             // 0xCB
+    op(Cpy, Absolute, 4),                      // 0xCC
+    op(Cmp, Absolute, 4),                      // 0xCD
+    op(Dec, Absolute, 6),                      // 0xCE
+    unofficial(Dcp, Absolute, 6),              // 0xCF
+    op(Bne, Relative, 2),                      // 0xD0
+    paged(Cmp, IndirectIndexed, 5),            // 0xD1
+    unofficial(Jam, Implied, 2),               // 0xD2
+    unofficial(Dcp, IndirectIndexed, 8),       // 0xD3
+    unofficial(Nop, ZeroPageX, 4),             // 0xD4
+    op(Cmp, ZeroPageX, 4),                     // 0xD5
+    op(Dec, ZeroPageX, 6),                     // 0xD6
+    unofficial(Dcp, ZeroPageX, 6),             // 0xD7
+    op(Cld, Implied, 2),                       // 0xD8
+    paged(Cmp, AbsoluteY, 4),                  // 0xD9
+    unofficial(Nop, Implied, 2),               // 0xDA
+    unofficial(Dcp, AbsoluteY, 7),             // 0xDB
+    unofficial_paged(Nop, AbsoluteX, 4),       // 0xDC
+    paged(Cmp, AbsoluteX, 4),                  // 0xDD
+    op(Dec, AbsoluteX, 7),                     // 0xDE
+    unofficial(Dcp, AbsoluteX, 7),             // 0xDF
+    op(Cpx, Immediate, 2),                     // 0xE0
+    op(Sbc, IndexedIndirect, 6),               // 0xE1
+    unofficial(Nop, Immediate, 2),             // 0xE2
+    unofficial(Isb, IndexedIndirect, 8),       // 0xE3
+    op(Cpx, ZeroPage, 3),                      // 0xE4
+    op(Sbc, ZeroPage, 3),                      // 0xE5
+    op(Inc, ZeroPage, 5),                      // 0xE6
+    unofficial(Isb, ZeroPage, 5),              // 0xE7
+    op(Inx, Implied, 2),                       // 0xE8
+    op(Sbc, Immediate, 2),                     // 0xE9
+    op(Nop, Implied, 2),                       // 0xEA
+    unofficial(Sbc, Immediate, 2),             // 0xEB
+    op(Cpx, Absolute, 4),                      // 0xEC
+    op(Sbc, Absolute, 4),                      // 0xED
+    op(Inc, Absolute, 6),                      // 0xEE
+    unofficial(Isb, Absolute, 6),              // 0xEF
+    op(Beq, Relative, 2),                      // 0xF0
+    paged(Sbc, IndirectIndexed, 5),            // 0xF1
+    unofficial(Jam, Implied, 2),               // 0xF2
+    unofficial(Isb, IndirectIndexed, 8),       // 0xF3
+    unofficial(Nop, ZeroPageX, 4),             // 0xF4
+    op(Sbc, ZeroPageX, 4),                     // 0xF5
+    op(Inc, ZeroPageX, 6),                     // 0xF6
+    unofficial(Isb, ZeroPageX, 6),             // 0xF7
+    op(Sed, Implied, 2),                       // 0xF8
+    paged(Sbc, AbsoluteY, 4),                  // 0xF9
+    unofficial(Nop, Implied, 2),               // 0xFA
+    unofficial(Isb, AbsoluteY, 7),             // 0xFB
+    unofficial_paged(Nop, AbsoluteX, 4),       // 0xFC
+    paged(Sbc, AbsoluteX, 4),                  // 0xFD
+    op(Inc, AbsoluteX, 7),                     // 0xFE
+    unofficial(Isb, AbsoluteX, 7),             // 0xFF
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn there_are_151_official_opcodes() {
+        assert_eq!(OPCODES.iter().filter(|opcode| opcode.official).count(), 151);
+    }
+
+    #[test]
+    fn only_indexed_modes_take_the_page_cross_penalty() {
+        for (byte, opcode) in OPCODES.iter().enumerate() {
+            if opcode.page_cross_penalty {
+                assert!(
+                    matches!(opcode.mode, AbsoluteX | AbsoluteY | IndirectIndexed),
+                    "{:#04X} {:?}",
+                    byte,
+                    opcode
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn writes_always_take_the_longer_indexed_timing() {
+        /*  Stores and read-modify-writes cannot skip the fix-up cycle, so
+            they have no penalty to pay
+        */
+        for byte in [0x9D, 0x99, 0x91, 0x1E, 0xFE, 0x1F, 0xDB, 0xD3] {
+            assert!(!OPCODES[byte].page_cross_penalty, "{:#04X}", byte);
+        }
+        assert_eq!(OPCODES[0x9D], op(Sta, AbsoluteX, 5));
+        assert_eq!(OPCODES[0x91], op(Sta, IndirectIndexed, 6));
+        assert_eq!(OPCODES[0x1E], op(Asl, AbsoluteX, 7));
+    }
+
+    #[test]
+    fn entries_decode_to_their_instruction() {
+        assert_eq!(OPCODES[0x69], op(Adc, Immediate, 2));
+        assert_eq!(OPCODES[0x7D], paged(Adc, AbsoluteX, 4));
+        assert_eq!(OPCODES[0xB1], paged(Lda, IndirectIndexed, 5));
+        assert_eq!(OPCODES[0x6C], op(Jmp, Indirect, 5));
+        assert_eq!(OPCODES[0xEB], unofficial(Sbc, Immediate, 2));
+        assert_eq!(OPCODES[0xBF], unofficial_paged(Lax, AbsoluteY, 4));
+        assert_eq!(OPCODES[0xFC], unofficial_paged(Nop, AbsoluteX, 4));
+    }
+
+    #[test]
+    fn operand_bytes_follow_the_mode() {
+        assert_eq!(Implied.operand_bytes(), 0);
+        assert_eq!(Accumulator.operand_bytes(), 0);
+        assert_eq!(Immediate.operand_bytes(), 1);
+        assert_eq!(IndirectIndexed.operand_bytes(), 1);
+        assert_eq!(Relative.operand_bytes(), 1);
+        assert_eq!(AbsoluteY.operand_bytes(), 2);
+        assert_eq!(Indirect.operand_bytes(), 2);
+    }
+    #[test]
+    fn unstable_operations_are_the_six_unofficial_ones() {
+        let unstable: Vec<u8> = (0..=0xFF)
+            .filter(|&byte| OPCODES[byte as usize].operation.unstable())
+            .collect();
+        assert_eq!(unstable, [0x8B, 0x93, 0x9B, 0x9C, 0x9E, 0x9F, 0xAB]);
+        assert!(unstable
+            .iter()
+            .all(|&byte| !OPCODES[byte as usize].official));
+    }
+}
@@ -0,0 +1,129 @@
+use super::{AddressingMode, Nes, Operation, OPCODES, PPU_DOTS_PER_CYCLE};
+
+// The PPU's frame, in dots per scanline and scanlines
+const DOTS_PER_SCANLINE: u64 = 341;
+const SCANLINES: u64 = 262;
+
+impl Nes {
+    pub fn trace(&self) -> String {
+        /*  The instruction at the program counter and the registers before it
+            runs, laid out as a line of nestest.log, e.g. (wrapped here)
+                C000  4C F5 C5  JMP $C5F5                       A:00 X:00 Y:00 P:24 SP:FD
+                    PPU:  0, 21 CYC:7
+            Operands show the address they resolve to and the value there. PPU
+            is the scanline and dot the PPU has reached, counting from power on
+        */
+        let pc = self.cpu.program_counter;
+        let opcode = OPCODES[self.read(pc) as usize];
+        let bytes: Vec<String> = (0..1 + opcode.mode.operand_bytes())
+            .map(|offset| format!("{:02X}", self.read(pc.wrapping_add(offset))))
+            .collect();
+        // Unofficial opcodes are starred
+        let marker = if opcode.official { ' ' } else { '*' };
+        let dots = self.cpu.cycles * PPU_DOTS_PER_CYCLE;
+        return format!(
+            "{:04X}  {:<8} {}{:<32}A:{:02X} X:{:02X} Y:{:02X} P:{:02X} SP:{:02X} PPU:{:>3},{:>3} CYC:{}",
+            pc,
+            bytes.join(" "),
+            marker,
+            self.disassemble(opcode.operation, opcode.mode),
+            self.cpu.accumulator,
+            self.cpu.x,
+            self.cpu.y,
+            self.cpu.status.bits(),
+            self.cpu.stack_pointer,
+            dots / DOTS_PER_SCANLINE % SCANLINES,
+            dots % DOTS_PER_SCANLINE,
+            self.cpu.cycles
+        );
+    }
+
+    fn disassemble(&self, operation: Operation, mode: AddressingMode) -> String {
+        let pc = self.cpu.program_counter;
+        let byte = self.read(pc.wrapping_add(1));
+        let word = self.read_u16(pc.wrapping_add(1));
+        let (x, y) = (self.cpu.x, self.cpu.y);
+        let operand = match mode {
+            AddressingMode::Implied => String::new(),
+            AddressingMode::Accumulator => "A".to_string(),
+            AddressingMode::Immediate => format!("#${:02X}", byte),
+            AddressingMode::ZeroPage => format!("${:02X} = {:02X}", byte, self.read(byte as u16)),
+            AddressingMode::ZeroPageX => {
+                let address = byte.wrapping_add(x);
+                format!(
+                    "${:02X},X @ {:02X} = {:02X}",
+                    byte,
+                    address,
+                    self.read(address as u16)
+                )
+            }
+            AddressingMode::ZeroPageY => {
+                let address = byte.wrapping_add(y);
+                format!(
+                    "${:02X},Y @ {:02X} = {:02X}",
+                    byte,
+                    address,
+                    self.read(address as u16)
+                )
+            }
+            // Jumps show only where they go
+            AddressingMode::Absolute if matches!(operation, Operation::Jmp | Operation::Jsr) => {
+                format!("${:04X}", word)
+            }
+            AddressingMode::Absolute => format!("${:04X} = {:02X}", word, self.read(word)),
+            AddressingMode::AbsoluteX => {
+                let address = word.wrapping_add(x as u16);
+                format!(
+                    "${:04X},X @ {:04X} = {:02X}",
+                    word,
+                    address,
+                    self.read(address)
+                )
+            }
+            AddressingMode::AbsoluteY => {
+                let address = word.wrapping_add(y as u16);
+                format!(
+                    "${:04X},Y @ {:04X} = {:02X}",
+                    word,
+                    address,
+                    self.read(address)
+                )
+            }
+            AddressingMode::Indirect => {
+                let hi = word & 0xFF00 | word.wrapping_add(1) & 0x00FF;
+                let target = (self.read(hi) as u16) << 8 | self.read(word) as u16;
+                format!("(${:04X}) = {:04X}", word, target)
+            }
+            AddressingMode::IndexedIndirect => {
+                let pointer = byte.wrapping_add(x);
+                let address = self.read_u16_zero_page(pointer);
+                format!(
+                    "(${:02X},X) @ {:02X} = {:04X} = {:02X}",
+                    byte,
+                    pointer,
+                    address,
+                    self.read(address)
+                )
+            }
+            AddressingMode::IndirectIndexed => {
+                let base = self.read_u16_zero_page(byte);
+                let address = base.wrapping_add(y as u16);
+                format!(
+                    "(${:02X}),Y = {:04X} @ {:04X} = {:02X}",
+                    byte,
+                    base,
+                    address,
+                    self.read(address)
+                )
+            }
+            AddressingMode::Relative => {
+                let target = pc.wrapping_add(2).wrapping_add(byte as i8 as u16);
+                format!("${:04X}", target)
+            }
+        };
+        if operand.is_empty() {
+            return operation.mnemonic().to_string();
+        }
+        return format!("{} {}", operation.mnemonic(), operand);
+    }
+}
@@ -0,0 +1,118 @@
+/*  Runs roms/nestest/nestest.nes in automation mode, from 0xC000 with no PPU,
+    and checks every instruction against the reference log the test ROM's
+    author published, roms/nestest/nestest.log
+*/
+#![allow(clippy::needless_return)]
+
+use std::fs;
+
+const ROM: &str = "roms/nestest/nestest.nes";
+const LOG: &str = "roms/nestest/nestest.log";
+
+// Lines of nestest.log, the official opcodes are tested first and the unofficial ones after
+const STEPS: usize = 8991;
+
+// Where nestest writes the code of the last failed test, 0 when all passed
+const OFFICIAL_RESULT: u16 = 0x02;
+const UNOFFICIAL_RESULT: u16 = 0x03;
+
+// Lines of the reference log shown before the first one that differs
+const CONTEXT: usize = 5;
+
+fn run(steps: usize) -> (nes::Nes, Vec<String>) {
+    /*  Runs the given number of instructions, tracing each before it runs
+     */
+    let rom = fs::read(ROM).expect("nestest.nes is missing");
+    let mut ns = nes::Nes::new();
+    ns.memory.load_program(&rom);
+    ns.reset();
+    ns.cpu.program_counter = 0xC000;
+
+    let mut trace = Vec::with_capacity(steps);
+    for _ in 0..steps {
+        trace.push(ns.trace());
+        ns.step();
+    }
+    return (ns, trace);
+}
+
+fn first_difference(expected: &[&str], actual: &[String]) -> Option<String> {
+    /*  The first line that differs, with the lines before it as context
+     */
+    let line = expected
+        .iter()
+        .zip(actual)
+        .position(|(expected, actual)| expected != actual)?;
+    let mut report = String::new();
+    for (number, context) in expected
+        .iter()
+        .enumerate()
+        .take(line)
+        .skip(line.saturating_sub(CONTEXT))
+    {
+        report += &format!("  {:5} {}\n", number + 1, context);
+    }
+    report += &format!("- {:5} {}\n", line + 1, expected[line]);
+    report += &format!("+ {:5} {}\n", line + 1, actual[line]);
+    return Some(report);
+}
+
+#[test]
+fn opcode_tests_pass() {
+    let (ns, _) = run(STEPS);
+    assert_eq!(
+        ns.memory.primary_memory[OFFICIAL_RESULT as usize], 0x00,
+        "nestest reported a failed official opcode test, see nestest.txt for the code"
+    );
+    assert_eq!(
+        ns.memory.primary_memory[UNOFFICIAL_RESULT as usize], 0x00,
+        "nestest reported a failed unofficial opcode test, see nestest.txt for the code"
+    );
+}
+
+#[test]
+fn trace_matches_nestest_log() {
+    let log = fs::read_to_string(LOG).unwrap_or_else(|error| {
+        panic!(
+            "unable to read {}: {}, it is the nestest.log published with the test ROM",
+            LOG, error
+        )
+    });
+    let expected: Vec<&str> = log.lines().collect();
+    assert_eq!(
+        expected.len(),
+        STEPS,
+        "{} is not the whole reference log",
+        LOG
+    );
+    let (_, actual) = run(expected.len());
+    if let Some(report) = first_difference(&expected, &actual) {
+        panic!("trace differs from nestest.log:\n{}", report);
+    }
+}
+
+#[test]
+fn trace_layout() {
+    /*  The first lines of nestest.log
+     */
+    let (_, trace) = run(3);
+    assert_eq!(
+        trace,
+        [
+            "C000  4C F5 C5  JMP $C5F5                       A:00 X:00 Y:00 P:24 SP:FD PPU:  0, 21 CYC:7",
+            "C5F5  A2 00     LDX #$00                        A:00 X:00 Y:00 P:24 SP:FD PPU:  0, 30 CYC:10",
+            "C5F7  86 00     STX $00 = 00                    A:00 X:00 Y:00 P:26 SP:FD PPU:  0, 36 CYC:12",
+        ]
+    );
+}
+
+#[test]
+fn trace_ends_on_the_last_line_of_nestest_log() {
+    /*  The final line of nestest.log is the RTS back out of the unofficial
+        opcode tests, on cycle 26554
+    */
+    let (_, trace) = run(STEPS);
+    let last = trace.last().unwrap();
+    assert!(last.starts_with("C66E  60        RTS "), "{}", last);
+    assert!(last.ends_with(" CYC:26554"), "{}", last);
+}